@@ -31,7 +31,7 @@ fn bench_e2e_latency(c: &mut Criterion) {
 
             // Bundle building
             let txn = bundle_ring.pop().unwrap();
-            builder.add(txn, &output_ring).ok();
+            builder.add(txn, &output_ring);
 
             // Flush if needed
             if builder.is_full() {
@@ -88,7 +88,7 @@ fn bench_bundle_building(c: &mut Criterion) {
             // Fill bundle to max
             for i in 0..BUNDLE_MAX {
                 let txn = Transaction::new_unchecked(i as u64, 1000000, 100, 0, 0);
-                builder.add(txn, &output_ring).ok();
+                builder.add(txn, &output_ring);
             }
 
             // Should have auto-flushed
@@ -110,7 +110,7 @@ fn bench_bundle_cycle_with_tsc(c: &mut Criterion) {
             // Fill bundle to capacity
             for i in 0..BUNDLE_MAX {
                 let txn = Transaction::new_unchecked(i as u64, 1000000 + i as i64, 100, 0, 0);
-                let _ = builder.add(txn, &output_ring);
+                builder.add(txn, &output_ring);
             }
 
             // Measure flush latency
@@ -129,11 +129,32 @@ fn bench_bundle_cycle_with_tsc(c: &mut Criterion) {
     });
 }
 
+fn bench_seeded_market_flow(c: &mut Criterion) {
+    init_tsc();
+
+    // Same seed and clock every iteration: every sample matches the same
+    // orders, so runs are comparable
+    c.bench_function("matching_seeded_burst_4096", |b| {
+        let ring = RingBuffer::<Transaction, 4096>::new();
+
+        b.iter(|| {
+            generate_burst_seeded(&ring, 4096, 1000000, 42, &mut ManualClock::new(0));
+            let mut engine = MatchingEngine::new(900000, 1, 200000, 65536);
+            let mut fills = 0u64;
+            while let Some(txn) = ring.pop() {
+                let _ = engine.submit(&txn, |_| fills += 1);
+            }
+            black_box(fills)
+        });
+    });
+}
+
 criterion_group!(
     benches,
     bench_e2e_latency,
     bench_throughput,
     bench_bundle_building,
-    bench_bundle_cycle_with_tsc
+    bench_bundle_cycle_with_tsc,
+    bench_seeded_market_flow
 );
 criterion_main!(benches);
@@ -29,6 +29,32 @@ fn bench_orderbook_update(c: &mut Criterion) {
     });
 }
 
+fn bench_exact_orderbook_update(c: &mut Criterion) {
+    init_tsc();
+
+    c.bench_function("exact_orderbook_update_bid", |b| {
+        let book = ExactOrderBook::new(900000, 1, 200000);
+        let mut price = 1000000;
+
+        b.iter(|| {
+            price = if price >= 1099999 { 900000 } else { price + 1 };
+            book.update_bid(black_box(price), black_box(100), black_box(0))
+                .unwrap();
+        });
+    });
+
+    c.bench_function("exact_orderbook_update_ask", |b| {
+        let book = ExactOrderBook::new(900000, 1, 200000);
+        let mut price = 1000000;
+
+        b.iter(|| {
+            price = if price >= 1099999 { 900000 } else { price + 1 };
+            book.update_ask(black_box(price), black_box(100), black_box(0))
+                .unwrap();
+        });
+    });
+}
+
 fn bench_orderbook_contention(c: &mut Criterion) {
     init_tsc();
 
@@ -171,6 +197,7 @@ fn bench_orderbook_cas_pressure(c: &mut Criterion) {
 criterion_group!(
     benches,
     bench_orderbook_update,
+    bench_exact_orderbook_update,
     bench_orderbook_contention,
     bench_orderbook_spread,
     bench_orderbook_multithreaded,
@@ -1,4 +1,4 @@
-use criterion::{black_box, criterion_group, criterion_main, Criterion, BenchmarkId};
+use criterion::{black_box, criterion_group, criterion_main, Criterion, BenchmarkId, Throughput};
 use velox_engine::*;
 use std::sync::Arc;
 use std::thread;
@@ -94,6 +94,65 @@ fn bench_ring_bulk_throughput(c: &mut Criterion) {
     group.finish();
 }
 
+fn bench_ring_batch_vs_single(c: &mut Criterion) {
+    init_tsc();
+
+    let mut group = c.benchmark_group("ring_batch_vs_single");
+    // 2^20 operations so every batch size divides evenly
+    const OPS: u64 = 1 << 20;
+    group.throughput(Throughput::Elements(OPS));
+
+    let txn = Transaction::new_unchecked(1, 1000000, 100, 0, 0);
+
+    group.bench_function("single", |b| {
+        let ring = RingBuffer::<Transaction, 4096>::new();
+
+        b.iter(|| {
+            for _ in 0..OPS {
+                ring.push(black_box(txn)).unwrap();
+                black_box(ring.pop().unwrap());
+            }
+        });
+    });
+
+    for batch in [16usize, 64, 256].iter() {
+        group.bench_with_input(BenchmarkId::new("push_slice_pop_into", batch), batch, |b, &batch| {
+            let ring = RingBuffer::<Transaction, 4096>::new();
+            let input = vec![txn; batch];
+            let mut output = vec![txn; batch];
+
+            b.iter(|| {
+                for _ in 0..OPS / batch as u64 {
+                    assert_eq!(ring.push_slice(black_box(&input)), batch);
+                    assert_eq!(ring.pop_into(black_box(&mut output)), batch);
+                }
+            });
+        });
+
+        group.bench_with_input(BenchmarkId::new("reserve_commit", batch), batch, |b, &batch| {
+            let ring = RingBuffer::<Transaction, 4096>::new();
+            let mut output = vec![txn; batch];
+
+            b.iter(|| {
+                for _ in 0..OPS / batch as u64 {
+                    // Batch divides capacity, so reservations never straddle the wrap
+                    // Safety: this thread is the only producer and commits
+                    // each reservation before taking the next
+                    let mut res = unsafe { ring.reserve(batch) };
+                    for slot in res.slots() {
+                        slot.write(black_box(txn));
+                    }
+                    let n = res.len();
+                    unsafe { res.commit(n) };
+                    assert_eq!(ring.pop_into(black_box(&mut output)), batch);
+                }
+            });
+        });
+    }
+
+    group.finish();
+}
+
 fn bench_ring_spsc_latency(c: &mut Criterion) {
     init_tsc();
 
@@ -131,6 +190,7 @@ criterion_group!(
     bench_ring_push_pop,
     bench_ring_transaction,
     bench_ring_bulk_throughput,
+    bench_ring_batch_vs_single,
     bench_ring_spsc_latency
 );
 criterion_main!(benches);
@@ -90,7 +90,7 @@ fn bench_e2e_comparison(c: &mut Criterion) {
                 .unwrap();
             bundle_ring.push(txn).unwrap();
             let txn = bundle_ring.pop().unwrap();
-            builder.add(txn, &output_ring).ok();
+            builder.add(txn, &output_ring);
 
             if builder.is_full() {
                 builder.force_flush(&output_ring).ok();
@@ -131,7 +131,7 @@ fn bench_e2e_comparison(c: &mut Criterion) {
             bundle_ring.push(txn).unwrap();
             let txn = bundle_ring.pop().unwrap();
             let bundle_start = rdtsc();
-            builder.add(txn, &output_ring).ok();
+            builder.add(txn, &output_ring);
             let bundle_latency_us = (tsc_to_ns(rdtsc()) - tsc_to_ns(bundle_start)) as f64 / 1000.0;
             telemetry::record_transaction_processed("bundle", txn.id, bundle_latency_us);
 
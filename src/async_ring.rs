@@ -0,0 +1,299 @@
+/// Async `Stream`/`Sink` adapters over `RingBuffer`
+///
+/// Lets a tokio task sit on either end of an SPSC ring without a dedicated
+/// spinning core. The ring itself is unchanged: the synchronous side keeps
+/// calling `push`/`pop` and then `notify()`s an `AsyncNotifier`, which costs
+/// one fence and one load unless a task is actually waiting. Waker storage is
+/// an `AtomicWaker`, so neither side ever takes a lock.
+use crate::ring::{CachePadded, RingBuffer};
+use crate::wait::Notify;
+use atomic_waker::AtomicWaker;
+use core::pin::Pin;
+use core::task::{Context, Poll};
+use futures_core::Stream;
+use futures_sink::Sink;
+use std::convert::Infallible;
+use std::sync::atomic::{fence, AtomicBool, Ordering};
+use std::sync::Arc;
+
+/// Wakeup channel for one async task waiting on a ring.
+///
+/// Producers (or consumers, for a `RingSink` waiting on space) call `notify`
+/// after publishing; the waiting task registers through the adapters.
+pub struct AsyncNotifier {
+    /// True while a task has registered and is about to return Pending
+    waiting: CachePadded<AtomicBool>,
+    /// Set once the other side will never publish again
+    closed: AtomicBool,
+    waker: AtomicWaker,
+}
+
+impl AsyncNotifier {
+    /// Create an open notifier with no registered task
+    pub fn new() -> Self {
+        Self {
+            waiting: CachePadded::new(AtomicBool::new(false)),
+            closed: AtomicBool::new(false),
+            waker: AtomicWaker::new(),
+        }
+    }
+
+    /// Wake the waiting task, if any
+    #[inline]
+    pub fn notify(&self) {
+        // SeqCst fence pairs with the one in `prepare_wait`: either we see
+        // `waiting`, or the task's re-check sees our publish.
+        fence(Ordering::SeqCst);
+        if self.waiting.value.load(Ordering::Relaxed)
+            && self.waiting.value.swap(false, Ordering::AcqRel)
+        {
+            self.waker.wake();
+        }
+    }
+
+    /// Mark the channel closed and wake the waiting task
+    pub fn close(&self) {
+        self.closed.store(true, Ordering::Release);
+        self.waiting.value.store(false, Ordering::Relaxed);
+        self.waker.wake();
+    }
+
+    /// True once `close` has been called
+    pub fn is_closed(&self) -> bool {
+        self.closed.load(Ordering::Acquire)
+    }
+
+    /// Register the task's waker and announce it is waiting.
+    /// Callers must re-check their condition afterwards.
+    fn prepare_wait(&self, cx: &Context<'_>) {
+        self.waker.register(cx.waker());
+        self.waiting.value.store(true, Ordering::Relaxed);
+        fence(Ordering::SeqCst);
+    }
+
+    /// Withdraw a wait announcement after the re-check succeeded
+    fn cancel_wait(&self) {
+        self.waiting.value.store(false, Ordering::Relaxed);
+    }
+}
+
+impl Default for AsyncNotifier {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Notify for AsyncNotifier {
+    #[inline]
+    fn notify(&self) {
+        AsyncNotifier::notify(self)
+    }
+}
+
+/// Async consumer over a `RingBuffer`.
+///
+/// Yields items as they are published; ends once the notifier is closed and
+/// the ring is drained. Must be the ring's only consumer.
+pub struct RingStream<T, const N: usize> {
+    ring: Arc<RingBuffer<T, N>>,
+    readable: Arc<AsyncNotifier>,
+}
+
+impl<T, const N: usize> RingStream<T, N> {
+    /// Wrap the consumer end of `ring`; producers notify `readable` after push
+    pub fn new(ring: Arc<RingBuffer<T, N>>, readable: Arc<AsyncNotifier>) -> Self {
+        Self { ring, readable }
+    }
+}
+
+impl<T, const N: usize> Stream for RingStream<T, N> {
+    type Item = T;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<T>> {
+        // Fast path: no waker traffic while data is flowing
+        if let Some(item) = self.ring.pop() {
+            return Poll::Ready(Some(item));
+        }
+
+        self.readable.prepare_wait(cx);
+
+        // Re-check after announcing so a racing push is not missed
+        if let Some(item) = self.ring.pop() {
+            self.readable.cancel_wait();
+            return Poll::Ready(Some(item));
+        }
+
+        if self.readable.is_closed() {
+            self.readable.cancel_wait();
+            // Drain anything published before close
+            return Poll::Ready(self.ring.pop());
+        }
+
+        Poll::Pending
+    }
+}
+
+/// Async producer over a `RingBuffer`.
+///
+/// Waits for space when the ring is full instead of dropping. Must be the
+/// ring's only producer. `R` is whatever wakes the consumer: an
+/// `AsyncNotifier` for a `RingStream`, or a `Notifier` for a parked thread.
+pub struct RingSink<T, const N: usize, R: Notify = AsyncNotifier> {
+    ring: Arc<RingBuffer<T, N>>,
+    /// Woken after each push
+    readable: Arc<R>,
+    /// Notified by the consumer after it pops (frees space)
+    writable: Arc<AsyncNotifier>,
+    /// Item accepted by `start_send` that did not fit yet
+    pending: Option<T>,
+}
+
+impl<T, const N: usize, R: Notify> RingSink<T, N, R> {
+    /// Wrap the producer end of `ring`
+    pub fn new(ring: Arc<RingBuffer<T, N>>, readable: Arc<R>, writable: Arc<AsyncNotifier>) -> Self {
+        Self {
+            ring,
+            readable,
+            writable,
+            pending: None,
+        }
+    }
+
+    /// Try to move the pending item into the ring
+    fn poll_push_pending(&mut self, cx: &mut Context<'_>) -> Poll<()> {
+        let Some(item) = self.pending.take() else {
+            return Poll::Ready(());
+        };
+
+        let item = match self.ring.push(item) {
+            Ok(()) => {
+                self.readable.notify();
+                return Poll::Ready(());
+            }
+            Err(item) => item,
+        };
+
+        self.writable.prepare_wait(cx);
+
+        // Re-check after announcing so a racing pop is not missed
+        match self.ring.push(item) {
+            Ok(()) => {
+                self.writable.cancel_wait();
+                self.readable.notify();
+                Poll::Ready(())
+            }
+            Err(item) => {
+                self.pending = Some(item);
+                Poll::Pending
+            }
+        }
+    }
+}
+
+impl<T, const N: usize, R: Notify> Sink<T> for RingSink<T, N, R> {
+    type Error = Infallible;
+
+    fn poll_ready(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Infallible>> {
+        self.get_mut().poll_push_pending(cx).map(Ok)
+    }
+
+    fn start_send(self: Pin<&mut Self>, item: T) -> Result<(), Infallible> {
+        let this = self.get_mut();
+        debug_assert!(this.pending.is_none(), "start_send without poll_ready");
+
+        match this.ring.push(item) {
+            Ok(()) => this.readable.notify(),
+            Err(item) => this.pending = Some(item),
+        }
+        Ok(())
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Infallible>> {
+        // Items in the ring are already visible to the consumer
+        self.get_mut().poll_push_pending(cx).map(Ok)
+    }
+
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Infallible>> {
+        self.get_mut().poll_push_pending(cx).map(Ok)
+    }
+}
+
+// The adapter never pins its fields (`pending` is moved in and out by value)
+impl<T, const N: usize, R: Notify> Unpin for RingSink<T, N, R> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures_util::{SinkExt, StreamExt};
+    use std::thread;
+
+    #[tokio::test]
+    async fn test_stream_receives_from_sync_producer() {
+        let ring = Arc::new(RingBuffer::<u64, 16>::new());
+        let readable = Arc::new(AsyncNotifier::new());
+        let mut stream = RingStream::new(Arc::clone(&ring), Arc::clone(&readable));
+
+        let producer = {
+            let ring = Arc::clone(&ring);
+            let readable = Arc::clone(&readable);
+            thread::spawn(move || {
+                for i in 0..1000 {
+                    while ring.push(i).is_err() {
+                        std::hint::spin_loop();
+                    }
+                    readable.notify();
+                }
+                readable.close();
+            })
+        };
+
+        let mut expected = 0;
+        while let Some(v) = stream.next().await {
+            assert_eq!(v, expected);
+            expected += 1;
+        }
+        assert_eq!(expected, 1000);
+
+        producer.join().unwrap();
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_sink_to_stream_with_backpressure() {
+        let ring = Arc::new(RingBuffer::<u64, 4>::new());
+        let readable = Arc::new(AsyncNotifier::new());
+        let writable = Arc::new(AsyncNotifier::new());
+
+        let mut sink = RingSink::new(Arc::clone(&ring), Arc::clone(&readable), Arc::clone(&writable));
+        let mut stream = RingStream::new(Arc::clone(&ring), Arc::clone(&readable));
+
+        let consumer = tokio::spawn(async move {
+            let mut sum = 0;
+            while let Some(v) = stream.next().await {
+                // Free space for the sink
+                writable.notify();
+                sum += v;
+            }
+            sum
+        });
+
+        // Far more items than capacity: the sink must wait, not drop
+        for i in 0..500u64 {
+            sink.send(i).await.unwrap();
+        }
+        readable.close();
+
+        assert_eq!(consumer.await.unwrap(), (0..500u64).sum::<u64>());
+    }
+
+    #[tokio::test]
+    async fn test_stream_ends_after_close_and_drain() {
+        let ring = Arc::new(RingBuffer::<u64, 4>::new());
+        let readable = Arc::new(AsyncNotifier::new());
+        ring.push(1).unwrap();
+        ring.push(2).unwrap();
+        readable.close();
+
+        let stream = RingStream::new(ring, readable);
+        assert_eq!(stream.collect::<Vec<_>>().await, vec![1, 2]);
+    }
+}
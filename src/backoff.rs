@@ -9,6 +9,9 @@ use std::thread;
 ///
 /// Usage:
 /// ```
+/// # use velox_engine::Backoff;
+/// # let mut attempts = 0;
+/// # let mut try_operation = || { attempts += 1; attempts > 3 };
 /// let mut backoff = Backoff::new();
 /// loop {
 ///     if try_operation() {
@@ -1,101 +1,414 @@
+use crate::flush::{FlushPolicy, FlushReason, FlushTriggers};
+use crate::mpmc::MpmcRingBuffer;
 use crate::ring::RingBuffer;
 use crate::tsc::{rdtsc, tsc_to_ns};
-use crate::types::{Bundle, Transaction, BUNDLE_MAX};
+use crate::types::{Bundle, OrderAction, Transaction, BUNDLE_MAX};
+use std::collections::VecDeque;
+use std::sync::Arc;
 
 /// Timeout for bundle flush (100 microseconds)
 pub const BUNDLE_TIMEOUT_NS: u64 = 100_000;
 
-/// Error when bundle buffer is full
+/// Error when the sink could not take every sealed bundle; the rest are
+/// held by the builder for retry
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct BundleFull;
 
-/// Stack-allocated bundle accumulator.
-/// Flushes when:
-/// 1. Bundle reaches BUNDLE_MAX transactions
+/// Counters for bundles the sink refused
+///
+/// Every sealed bundle either reached the sink immediately or was
+/// `delayed`; every delayed bundle was later `retried` into the sink,
+/// `dropped`, or is still held: `delayed == retried + dropped + held()`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct BackpressureStats {
+    /// Bundles held because the sink was full
+    pub delayed: u64,
+    /// Held bundles that later reached the sink
+    pub retried: u64,
+    /// Held bundles discarded to make room (oldest first)
+    pub dropped: u64,
+    /// Transactions in dropped bundles
+    pub dropped_transactions: u64,
+}
+
+/// Destination for flushed bundles of capacity `N`
+pub trait BundleSink<const N: usize = BUNDLE_MAX> {
+    /// Hand off a bundle, or give it back if the sink has no room
+    fn push_bundle(&self, bundle: Bundle<N>) -> Result<(), Bundle<N>>;
+}
+
+impl<const N: usize, const R: usize> BundleSink<N> for RingBuffer<Bundle<N>, R> {
+    #[inline]
+    fn push_bundle(&self, bundle: Bundle<N>) -> Result<(), Bundle<N>> {
+        self.push(bundle)
+    }
+}
+
+impl<const N: usize, const R: usize> BundleSink<N> for MpmcRingBuffer<Bundle<N>, R> {
+    #[inline]
+    fn push_bundle(&self, bundle: Bundle<N>) -> Result<(), Bundle<N>> {
+        self.push(bundle)
+    }
+}
+
+impl<const N: usize, S: BundleSink<N> + ?Sized> BundleSink<N> for Arc<S> {
+    #[inline]
+    fn push_bundle(&self, bundle: Bundle<N>) -> Result<(), Bundle<N>> {
+        (**self).push_bundle(bundle)
+    }
+}
+
+/// A bundle that `BundleBuilder::add` flushed on its own
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Flushed {
+    pub reason: FlushReason,
+    /// Transactions in the flushed bundle
+    pub size: usize,
+}
+
+/// What `BundleBuilder::add` did with a transaction
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AddOutcome {
+    /// Appended to (or netted into) the pending bundle; nothing flushed
+    Added,
+    /// One bundle was flushed (sealed; if the sink was full it is held for
+    /// retry and counted in `BackpressureStats::delayed`)
+    Flushed(Flushed),
+    /// The pending bundle was flushed before appending, then the bundle the
+    /// transaction started was flushed too (e.g. a timeout followed by a
+    /// value trigger on the new transaction)
+    FlushedTwice(Flushed, Flushed),
+}
+
+impl AddOutcome {
+    /// Flushes performed, in the order they reached the ring
+    pub fn flushes(&self) -> impl Iterator<Item = Flushed> {
+        let pair = match *self {
+            Self::Added => [None, None],
+            Self::Flushed(only) => [Some(only), None],
+            Self::FlushedTwice(first, second) => [Some(first), Some(second)],
+        };
+        pair.into_iter().flatten()
+    }
+
+    fn and_then(self, flushed: Option<Flushed>) -> Self {
+        match (self, flushed) {
+            (outcome, None) => outcome,
+            (Self::Added, Some(only)) => Self::Flushed(only),
+            (Self::Flushed(first), Some(second)) => Self::FlushedTwice(first, second),
+            (Self::FlushedTwice(..), Some(_)) => unreachable!("add flushes at most twice"),
+        }
+    }
+}
+
+/// Accumulator for bundles of up to `N` transactions (`BUNDLE_MAX` by
+/// default), flushed to any `BundleSink`. The open bundle is an inline
+/// array; the only heap storage is the overflow queue of held bundles,
+/// allocated once in `with_overflow` and never on the hot path.
+/// Flushes when its `FlushPolicy` says so; the default (`FlushTriggers`)
+/// flushes when:
+/// 1. Bundle reaches its size cap (BUNDLE_MAX transactions by default)
 /// 2. Timeout expires (BUNDLE_TIMEOUT_NS since first transaction)
-pub struct BundleBuilder {
-    buffer: [Transaction; BUNDLE_MAX],
+///
+/// Cancel/modify/replace transactions for an order that is still pending in
+/// the current bundle are netted against it instead of being appended.
+///
+/// # Backpressure
+/// A flushed bundle the sink refuses is not lost: the builder holds it in a
+/// single retry slot and keeps accepting transactions into a fresh bundle.
+/// Held bundles are retried, oldest first, before anything newer is pushed
+/// (on every `add`, `flush` and `retry`). `with_overflow(n)` allows up to
+/// `n` further bundles to queue behind the retry slot; once that is full the
+/// oldest held bundle is dropped to make room. Both outcomes are counted in
+/// `backpressure()`.
+pub struct BundleBuilder<const N: usize = BUNDLE_MAX, P: FlushPolicy = FlushTriggers> {
+    buffer: [Transaction; N],
     count: usize,
     start_tsc: u64,
+    policy: P,
+    /// Oldest refused bundle, retried first
+    retry_slot: Option<Bundle<N>>,
+    /// Refused bundles queued behind the retry slot, oldest first
+    overflow: VecDeque<Bundle<N>>,
+    overflow_limit: usize,
+    backpressure: BackpressureStats,
 }
 
 impl BundleBuilder {
     /// Create a new bundle builder
     pub fn new() -> Self {
+        Self::with_policy(FlushTriggers::default())
+    }
+
+    /// Create a builder that flushes at `max_size` transactions or after
+    /// `timeout_ns`, whichever comes first.
+    ///
+    /// # Panics
+    /// Panics if `max_size` is 0 or exceeds `BUNDLE_MAX`.
+    pub fn with_limits(max_size: usize, timeout_ns: u64) -> Self {
+        Self::with_policy(FlushTriggers::new(max_size, timeout_ns))
+    }
+}
+
+impl<const N: usize> BundleBuilder<N> {
+    /// Create a builder for `N`-transaction bundles that flushes when all
+    /// `N` slots are filled or after `timeout_ns`
+    pub fn with_timeout(timeout_ns: u64) -> Self {
+        Self::with_policy(FlushTriggers::new(N, timeout_ns))
+    }
+}
+
+impl<const N: usize, P: FlushPolicy> BundleBuilder<N, P> {
+    /// Create a builder that flushes according to `policy`.
+    ///
+    /// # Panics
+    /// Panics if the policy's `max_size` is 0 or exceeds the capacity `N`.
+    pub fn with_policy(policy: P) -> Self {
+        let () = Bundle::<N>::LAYOUT;
+        assert!(
+            (1..=N).contains(&policy.max_size()),
+            "bundle max_size must be in 1..={}",
+            N
+        );
         Self {
-            buffer: [Transaction::new_unchecked(0, 1, 1, 0, 0); BUNDLE_MAX],
+            buffer: [Transaction::new_unchecked(0, 1, 1, 0, 0); N],
             count: 0,
             start_tsc: rdtsc(),
+            policy,
+            retry_slot: None,
+            overflow: VecDeque::new(),
+            overflow_limit: 0,
+            backpressure: BackpressureStats::default(),
         }
     }
 
+    /// Let up to `limit` refused bundles queue behind the retry slot before
+    /// the oldest is dropped. The queue is allocated once, here.
+    pub fn with_overflow(mut self, limit: usize) -> Self {
+        self.overflow = VecDeque::with_capacity(limit);
+        self.overflow_limit = limit;
+        self
+    }
+
     /// Add a transaction to the bundle.
-    /// Automatically flushes if bundle is full or timeout expires.
+    /// Automatically flushes when the policy's size cap, timeout or one of
+    /// its value triggers fires, and reports each flush with its reason and
+    /// size. Retries held bundles first.
     ///
-    /// Returns Err(BundleFull) if ring buffer is full and flush fails.
-    pub fn add(
-        &mut self,
-        txn: Transaction,
-        ring: &RingBuffer<Bundle, 1024>,
-    ) -> Result<(), BundleFull> {
-        // Check if we need to flush before adding (due to timeout or full buffer)
-        if self.count >= BUNDLE_MAX || (self.count > 0 && self.should_flush_timeout()) {
-            self.flush(ring)?;
-        }
-
-        // If buffer is empty, reset start timestamp
-        if self.count == 0 {
-            self.start_tsc = rdtsc();
+    /// Never refuses `txn`: bundles the sink cannot take are held (see
+    /// Backpressure above).
+    pub fn add<S: BundleSink<N> + ?Sized>(&mut self, txn: Transaction, ring: &S) -> AddOutcome {
+        let mut outcome = AddOutcome::Added;
+        self.retry(ring);
+
+        // Lifecycle actions for an order still in this bundle amend it in place
+        if !self.net_pending(&txn) {
+            // Check if we need to flush before adding (due to timeout or full buffer)
+            if self.count >= self.policy.max_size() {
+                outcome = outcome.and_then(self.flush_for(FlushReason::Size, ring));
+            } else if self.count > 0 && self.should_flush_timeout() {
+                let reason = self.policy.timeout_reason();
+                outcome = outcome.and_then(self.flush_for(reason, ring));
+            }
+
+            // If buffer is empty, reset start timestamp
+            if self.count == 0 {
+                self.start_tsc = rdtsc();
+            }
+
+            // Add transaction to buffer
+            self.buffer[self.count] = txn;
+            self.count += 1;
         }
 
-        // Add transaction to buffer
-        self.buffer[self.count] = txn;
-        self.count += 1;
+        let trigger = self.policy.on_add(&self.buffer[..self.count], tsc_to_ns(rdtsc()));
 
-        // Check if we're now full and need to flush immediately
-        if self.count >= BUNDLE_MAX {
-            self.flush(ring)?;
+        // Check if we're now full (or a value trigger fired) and flush immediately
+        let reason = if self.count >= self.policy.max_size() {
+            Some(FlushReason::Size)
+        } else {
+            trigger
+        };
+        if let Some(reason) = reason {
+            outcome = outcome.and_then(self.flush_for(reason, ring));
         }
 
-        Ok(())
+        outcome
     }
 
-    /// Check if bundle should be flushed due to timeout
-    pub fn should_flush_timeout(&self) -> bool {
-        if self.count == 0 {
-            return false;
+    /// Flush on the builder's own initiative
+    fn flush_for<S: BundleSink<N> + ?Sized>(&mut self, reason: FlushReason, ring: &S) -> Option<Flushed> {
+        let size = self.count;
+        if size == 0 {
+            return None;
         }
-
-        let elapsed_tsc = rdtsc() - self.start_tsc;
-        let elapsed_ns = tsc_to_ns(elapsed_tsc);
-        elapsed_ns >= BUNDLE_TIMEOUT_NS
+        self.seal(ring);
+        Some(Flushed { reason, size })
     }
 
-    /// Flush the current bundle to the ring buffer
-    pub fn flush(&mut self, ring: &RingBuffer<Bundle, 1024>) -> Result<(), BundleFull> {
-        if self.count == 0 {
-            return Ok(());
-        }
-
+    /// Close the pending bundle and hand it to the sink, or hold it
+    fn seal<S: BundleSink<N> + ?Sized>(&mut self, ring: &S) {
         // Use unchecked version since we control count internally
-        debug_assert!(self.count <= BUNDLE_MAX, "count exceeds BUNDLE_MAX");
+        debug_assert!(self.count <= N, "count exceeds bundle capacity");
         let bundle = Bundle::with_transactions_unchecked(
             self.buffer,
             self.count as u32,
             tsc_to_ns(self.start_tsc),
         );
 
-        ring.push(bundle).map_err(|_| BundleFull)?;
-
         // Reset builder
         self.count = 0;
         self.start_tsc = rdtsc();
 
+        // Held bundles go first to keep submission order
+        if self.held() > 0 {
+            self.hold(bundle);
+        } else if let Err(bundle) = ring.push_bundle(bundle) {
+            self.hold(bundle);
+        }
+    }
+
+    /// Keep a refused bundle for retry, dropping the oldest if full
+    fn hold(&mut self, bundle: Bundle<N>) {
+        self.backpressure.delayed += 1;
+        if self.retry_slot.is_none() {
+            self.retry_slot = Some(bundle);
+            return;
+        }
+        if self.overflow.len() >= self.overflow_limit {
+            let oldest = match self.overflow.pop_front() {
+                Some(next) => self.retry_slot.replace(next),
+                None => self.retry_slot.take(),
+            };
+            if let Some(oldest) = oldest {
+                self.backpressure.dropped += 1;
+                self.backpressure.dropped_transactions += oldest.count as u64;
+            }
+            if self.retry_slot.is_none() {
+                self.retry_slot = Some(bundle);
+                return;
+            }
+        }
+        self.overflow.push_back(bundle);
+    }
+
+    /// Push held bundles, oldest first, until the sink refuses one.
+    /// Returns how many reached the sink.
+    pub fn retry<S: BundleSink<N> + ?Sized>(&mut self, ring: &S) -> usize {
+        let mut delivered = 0;
+        while let Some(bundle) = self.retry_slot.take() {
+            if let Err(bundle) = ring.push_bundle(bundle) {
+                self.retry_slot = Some(bundle);
+                break;
+            }
+            delivered += 1;
+            self.retry_slot = self.overflow.pop_front();
+        }
+        self.backpressure.retried += delivered as u64;
+        delivered
+    }
+
+    /// Discard every held bundle, counting them as dropped (e.g. when the
+    /// sink is gone for good). Returns how many were discarded.
+    pub fn drop_held(&mut self) -> usize {
+        let held = self.held();
+        for bundle in self.retry_slot.take().into_iter().chain(self.overflow.drain(..)) {
+            self.backpressure.dropped += 1;
+            self.backpressure.dropped_transactions += bundle.count as u64;
+        }
+        held
+    }
+
+    /// Bundles refused by the sink and not yet retried or dropped
+    pub fn held(&self) -> usize {
+        self.retry_slot.is_some() as usize + self.overflow.len()
+    }
+
+    /// Transactions inside held bundles
+    pub fn held_transactions(&self) -> usize {
+        self.retry_slot.iter().chain(&self.overflow).map(|bundle| bundle.count as usize).sum()
+    }
+
+    /// Delayed / retried / dropped bundle counters
+    pub fn backpressure(&self) -> BackpressureStats {
+        self.backpressure
+    }
+
+    /// Flush policy in force
+    pub fn policy(&self) -> &P {
+        &self.policy
+    }
+
+    /// Apply a cancel/modify/replace to a pending transaction with the same id.
+    /// Returns true if `txn` was absorbed and must not be appended.
+    fn net_pending(&mut self, txn: &Transaction) -> bool {
+        let action = match txn.order_action() {
+            Ok(OrderAction::New) | Err(_) => return false,
+            Ok(action) => action,
+        };
+
+        // Newest pending entry for this id wins
+        let Some(pos) = self.buffer[..self.count].iter().rposition(|p| p.id == txn.id) else {
+            return false;
+        };
+        let pending = &mut self.buffer[pos];
+        let pending_is_new = match pending.order_action() {
+            Ok(OrderAction::Cancel) | Err(_) => return false,
+            Ok(pending_action) => pending_action == OrderAction::New,
+        };
+
+        match action {
+            OrderAction::Cancel if pending_is_new => {
+                // Order never left the bundle: drop it entirely
+                self.buffer.copy_within(pos + 1..self.count, pos);
+                self.count -= 1;
+            }
+            OrderAction::Modify => pending.size = txn.size,
+            OrderAction::Replace if pending_is_new => {
+                pending.price = txn.price;
+                pending.size = txn.size;
+                pending.side = txn.side;
+            }
+            // Supersede a pending modify/replace for an order already downstream
+            _ => {
+                let ingress_ts_ns = pending.ingress_ts_ns;
+                *pending = *txn;
+                pending.ingress_ts_ns = ingress_ts_ns;
+            }
+        }
+
+        true
+    }
+
+    /// Check if bundle should be flushed due to timeout
+    pub fn should_flush_timeout(&self) -> bool {
+        if self.count == 0 {
+            return false;
+        }
+
+        let elapsed_tsc = rdtsc() - self.start_tsc;
+        let elapsed_ns = tsc_to_ns(elapsed_tsc);
+        elapsed_ns >= self.policy.timeout_ns()
+    }
+
+    /// Flush the current bundle to the sink, retrying held bundles first.
+    ///
+    /// Returns Err(BundleFull) if anything is still held afterwards; nothing
+    /// is lost, call again (or `retry`) once the sink drains.
+    pub fn flush<S: BundleSink<N> + ?Sized>(&mut self, ring: &S) -> Result<(), BundleFull> {
+        self.retry(ring);
+        if self.count > 0 {
+            self.seal(ring);
+        }
+
+        if self.held() > 0 {
+            return Err(BundleFull);
+        }
         Ok(())
     }
 
     /// Force flush even if bundle is not full or timeout has not expired
-    pub fn force_flush(&mut self, ring: &RingBuffer<Bundle, 1024>) -> Result<(), BundleFull> {
+    pub fn force_flush<S: BundleSink<N> + ?Sized>(&mut self, ring: &S) -> Result<(), BundleFull> {
         self.flush(ring)
     }
 
@@ -111,7 +424,7 @@ impl BundleBuilder {
 
     /// Check if bundle is full
     pub fn is_full(&self) -> bool {
-        self.count >= BUNDLE_MAX
+        self.count >= self.policy.max_size()
     }
 }
 
@@ -133,7 +446,7 @@ mod tests {
         let mut builder = BundleBuilder::new();
 
         let txn = Transaction::new_unchecked(1, 1000, 100, 0, 0);
-        assert!(builder.add(txn, &ring).is_ok());
+        builder.add(txn, &ring);
         assert_eq!(builder.len(), 1);
     }
 
@@ -146,7 +459,7 @@ mod tests {
         // Fill bundle to max
         for i in 0..BUNDLE_MAX {
             let txn = Transaction::new_unchecked(i as u64, 1000, 100, 0, 0);
-            assert!(builder.add(txn, &ring).is_ok());
+            builder.add(txn, &ring);
         }
 
         // Should have auto-flushed
@@ -158,6 +471,172 @@ mod tests {
         assert_eq!(bundle.count, BUNDLE_MAX as u32);
     }
 
+    #[test]
+    fn test_bundle_builder_custom_limits() {
+        init_tsc();
+        let ring = RingBuffer::<Bundle, 1024>::new();
+        let mut builder = BundleBuilder::with_limits(4, 1_000_000_000);
+
+        for i in 0..10 {
+            builder.add(Transaction::new_unchecked(i, 1000, 100, 0, 0), &ring);
+        }
+
+        // Two full bundles of 4, two transactions pending
+        assert_eq!(ring.len(), 2);
+        assert_eq!(ring.pop().unwrap().count, 4);
+        assert_eq!(builder.len(), 2);
+        assert!(!builder.should_flush_timeout());
+    }
+
+    #[test]
+    fn test_bundle_builder_policy_trigger() {
+        init_tsc();
+        let ring = RingBuffer::<Bundle, 1024>::new();
+        let policy = FlushTriggers::new(BUNDLE_MAX, 1_000_000_000).with_max_quantity(250);
+        let mut builder = BundleBuilder::with_policy(policy);
+
+        let outcome = builder.add(Transaction::new_unchecked(1, 1000, 100, 0, 0), &ring);
+        assert_eq!(outcome, AddOutcome::Added);
+        builder.add(Transaction::new_unchecked(2, 1000, 100, 1, 0), &ring);
+        assert!(ring.is_empty());
+
+        let outcome = builder.add(Transaction::new_unchecked(3, 1000, 100, 0, 0), &ring);
+        let quantity = Flushed { reason: FlushReason::Quantity, size: 3 };
+        assert_eq!(outcome, AddOutcome::Flushed(quantity));
+        assert_eq!(ring.pop().unwrap().count, 3);
+
+        // Fill to the size cap: reported as a size flush
+        let mut builder = BundleBuilder::with_limits(2, 1_000_000_000);
+        builder.add(Transaction::new_unchecked(1, 1000, 100, 0, 0), &ring);
+        let outcome = builder.add(Transaction::new_unchecked(2, 1000, 100, 0, 0), &ring);
+        let size = Flushed { reason: FlushReason::Size, size: 2 };
+        assert_eq!(outcome, AddOutcome::Flushed(size));
+        assert_eq!(ring.pop().unwrap().count, 2);
+    }
+
+    #[test]
+    fn test_bundle_builder_reports_timeout_then_size() {
+        init_tsc();
+        let ring = RingBuffer::<Bundle, 1024>::new();
+        // Zero timeout: anything pending is already due
+        let mut builder = BundleBuilder::with_limits(2, 0);
+
+        builder.add(Transaction::new_unchecked(1, 1000, 100, 0, 0), &ring);
+        let outcome = builder.add(Transaction::new_unchecked(2, 1000, 100, 0, 0), &ring);
+        let timeout = Flushed { reason: FlushReason::Timeout, size: 1 };
+        assert_eq!(outcome, AddOutcome::Flushed(timeout));
+
+        // The timed-out bundle, then the one the new transaction trips
+        let policy = FlushTriggers::new(2, 0).with_max_quantity(150);
+        let mut builder = BundleBuilder::with_policy(policy);
+        builder.add(Transaction::new_unchecked(1, 1000, 100, 0, 0), &ring);
+        let outcome = builder.add(Transaction::new_unchecked(2, 1000, 200, 0, 0), &ring);
+        let quantity = Flushed { reason: FlushReason::Quantity, size: 1 };
+        assert_eq!(outcome, AddOutcome::FlushedTwice(timeout, quantity));
+        assert_eq!(outcome.flushes().collect::<Vec<_>>(), vec![timeout, quantity]);
+    }
+
+    #[test]
+    fn test_bundle_builder_holds_refused_bundle() {
+        init_tsc();
+        let ring = RingBuffer::<Bundle, 2>::new();
+        let mut builder = BundleBuilder::with_limits(1, 1_000_000_000);
+        builder.add(Transaction::new_unchecked(1, 1000, 100, 0, 0), &ring);
+        builder.add(Transaction::new_unchecked(2, 1000, 100, 0, 0), &ring);
+
+        // Ring full: the bundle is still flushed, and held for retry
+        let outcome = builder.add(Transaction::new_unchecked(3, 1000, 100, 0, 0), &ring);
+        let size = Flushed { reason: FlushReason::Size, size: 1 };
+        assert_eq!(outcome, AddOutcome::Flushed(size));
+        assert_eq!((builder.len(), builder.held()), (0, 1));
+
+        // No overflow: a second refused bundle displaces the first
+        builder.add(Transaction::new_unchecked(4, 1000, 100, 0, 0), &ring);
+        assert_eq!(builder.held(), 1);
+        assert_eq!(builder.force_flush(&ring), Err(BundleFull));
+
+        ring.pop().unwrap();
+        assert_eq!(builder.force_flush(&ring), Ok(()));
+        ring.pop().unwrap();
+        assert_eq!(ring.pop().unwrap().transactions[0].id, 4);
+
+        let expected = BackpressureStats { delayed: 2, retried: 1, dropped: 1, dropped_transactions: 1 };
+        assert_eq!(builder.backpressure(), expected);
+        assert_eq!(builder.held(), 0);
+    }
+
+    #[test]
+    fn test_bundle_builder_overflow_keeps_order() {
+        init_tsc();
+        let ring = RingBuffer::<Bundle, 2>::new();
+        let mut builder = BundleBuilder::with_limits(1, 1_000_000_000).with_overflow(2);
+        for id in 1..=6 {
+            builder.add(Transaction::new_unchecked(id, 1000, 100, 0, 0), &ring);
+        }
+        // 1 and 2 delivered; 3 dropped for 6; 4, 5, 6 held
+        assert_eq!(builder.held(), 3);
+        assert_eq!(builder.held_transactions(), 3);
+        assert_eq!(builder.backpressure().dropped, 1);
+
+        let mut ids = Vec::new();
+        while builder.held() > 0 {
+            while let Some(bundle) = ring.pop() {
+                ids.push(bundle.transactions[0].id);
+            }
+            let _ = builder.force_flush(&ring);
+        }
+        while let Some(bundle) = ring.pop() {
+            ids.push(bundle.transactions[0].id);
+        }
+        assert_eq!(ids, vec![1, 2, 4, 5, 6]);
+        assert_eq!(builder.backpressure().retried, 3);
+
+        // Giving up on the sink counts what was held
+        builder.add(Transaction::new_unchecked(7, 1000, 100, 0, 0), &ring);
+        builder.add(Transaction::new_unchecked(8, 1000, 100, 0, 0), &ring);
+        builder.add(Transaction::new_unchecked(9, 1000, 100, 0, 0), &ring);
+        assert_eq!(builder.drop_held(), 1);
+        assert_eq!(builder.backpressure().dropped_transactions, 2);
+    }
+
+    #[test]
+    fn test_bundle_builder_custom_capacity_and_sink() {
+        use std::cell::RefCell;
+
+        /// Venue gateway stand-in that records submissions
+        struct Gateway(RefCell<Vec<u32>>);
+
+        impl BundleSink<4> for Gateway {
+            fn push_bundle(&self, bundle: Bundle<4>) -> Result<(), Bundle<4>> {
+                self.0.borrow_mut().push(bundle.count);
+                Ok(())
+            }
+        }
+
+        init_tsc();
+        let gateway = Gateway(RefCell::new(Vec::new()));
+        let mut builder = BundleBuilder::<4>::with_timeout(1_000_000_000);
+        for i in 0..10 {
+            builder.add(Transaction::new_unchecked(i, 1000, 100, 0, 0), &gateway);
+        }
+        builder.force_flush(&gateway).unwrap();
+        assert_eq!(*gateway.0.borrow(), vec![4, 4, 2]);
+
+        // Shared sinks work too
+        let ring = MpmcRingBuffer::<Bundle<4>, 8>::new();
+        let mut builder = BundleBuilder::<4>::with_timeout(1_000_000_000);
+        for i in 0..4 {
+            builder.add(Transaction::new_unchecked(i, 1000, 100, 0, 0), &ring);
+        }
+        assert_eq!(ring.pop().unwrap().count, 4);
+    }
+
+    #[test]
+    #[should_panic(expected = "bundle max_size must be in 1..=4")]
+    fn test_bundle_builder_policy_exceeds_capacity() {
+        BundleBuilder::<4>::with_policy(FlushTriggers::default());
+    }
+
     #[test]
     fn test_bundle_builder_manual_flush() {
         init_tsc();
@@ -166,7 +645,7 @@ mod tests {
 
         for i in 0..5 {
             let txn = Transaction::new_unchecked(i as u64, 1000, 100, 0, 0);
-            assert!(builder.add(txn, &ring).is_ok());
+            builder.add(txn, &ring);
         }
 
         assert_eq!(builder.len(), 5);
@@ -177,6 +656,44 @@ mod tests {
         assert_eq!(bundle.count, 5);
     }
 
+    #[test]
+    fn test_bundle_builder_nets_lifecycle_actions() {
+        init_tsc();
+        let ring = RingBuffer::<Bundle, 1024>::new();
+        let mut builder = BundleBuilder::new();
+
+        builder.add(Transaction::new_unchecked(1, 1000, 100, 0, 0), &ring);
+        builder.add(Transaction::new_unchecked(2, 1000, 100, 1, 0), &ring);
+        builder.add(Transaction::new_unchecked(3, 1000, 100, 0, 0), &ring);
+
+        // Cancel of a pending new removes it
+        let cancel = Transaction::with_action(2, 0, 0, 1, OrderAction::Cancel, 0).unwrap();
+        builder.add(cancel, &ring);
+        assert_eq!(builder.len(), 2);
+
+        // Modify amends the pending size
+        let modify = Transaction::with_action(1, 0, 40, 0, OrderAction::Modify, 0).unwrap();
+        builder.add(modify, &ring);
+
+        // Replace of a pending new stays a new order at the new price
+        let replace = Transaction::with_action(3, 1100, 7, 0, OrderAction::Replace, 0).unwrap();
+        builder.add(replace, &ring);
+
+        // Cancel of an order not in this bundle is forwarded
+        let cancel = Transaction::with_action(99, 0, 0, 0, OrderAction::Cancel, 0).unwrap();
+        builder.add(cancel, &ring);
+
+        builder.force_flush(&ring).unwrap();
+        let bundle = ring.pop().unwrap();
+        let txns = bundle.active_transactions();
+
+        assert_eq!(txns.len(), 3);
+        assert_eq!((txns[0].id, txns[0].size), (1, 40));
+        assert_eq!((txns[1].id, txns[1].price, txns[1].size), (3, 1100, 7));
+        assert_eq!(txns[1].order_action(), Ok(OrderAction::New));
+        assert_eq!((txns[2].id, txns[2].order_action()), (99, Ok(OrderAction::Cancel)));
+    }
+
     #[test]
     fn test_bundle_builder_timeout() {
         use std::thread;
@@ -187,7 +704,7 @@ mod tests {
         let mut builder = BundleBuilder::new();
 
         let txn = Transaction::new_unchecked(1, 1000, 100, 0, 0);
-        builder.add(txn, &ring).unwrap();
+        builder.add(txn, &ring);
         assert_eq!(builder.len(), 1);
 
         // Wait for timeout (500 microseconds >> 100 microseconds to be safe)
@@ -198,10 +715,10 @@ mod tests {
 
         // Should trigger timeout flush on next add
         let txn2 = Transaction::new_unchecked(2, 1000, 100, 0, 0);
-        builder.add(txn2, &ring).unwrap();
+        builder.add(txn2, &ring);
 
         // First bundle should be flushed
-        assert!(ring.len() >= 1, "Expected at least 1 bundle flushed");
+        assert!(!ring.is_empty(), "Expected at least 1 bundle flushed");
         let bundle = ring.pop().unwrap();
         // Bundle could have 1 or 2 transactions depending on timing
         assert!(bundle.count >= 1 && bundle.count <= 2,
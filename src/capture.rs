@@ -0,0 +1,317 @@
+/// Capture files: timestamped wire frames, appended in arrival order
+///
+/// A capture starts with an 8-byte file header, followed by records; all
+/// integers are little-endian:
+///
+/// ```text
+/// file header:  magic "VXCP" (4) | version (1) | reserved, zero (3)
+/// record:       ts_ns u64 (8)    | one wire frame (`wire`), any kind
+/// ```
+///
+/// `ts_ns` is when the record was captured (TSC nanoseconds); replay paces
+/// itself on the differences between consecutive records. Frames carry their
+/// own length and CRC, so a capture can be scanned without this module:
+/// read 8 bytes of timestamp, then a frame header, then the rest of the frame.
+use crate::errors::{CaptureError, WireError};
+use crate::types::{Bundle, Transaction, BUNDLE_MAX};
+use crate::wire::{FrameHeader, FrameKind, HEADER_LEN};
+use std::io::{self, Read, Write};
+
+pub const CAPTURE_MAGIC: [u8; 4] = *b"VXCP";
+pub const CAPTURE_VERSION: u8 = 1;
+/// File header size
+pub const CAPTURE_HEADER_LEN: usize = 8;
+/// Timestamp in front of every frame
+pub const RECORD_TS_LEN: usize = 8;
+/// Largest record a reader accepts: timestamp and a full `BUNDLE_MAX` bundle
+pub const MAX_RECORD_LEN: usize = RECORD_TS_LEN + Bundle::<BUNDLE_MAX>::MAX_FRAME_LEN;
+
+/// One decoded record
+// Bundles stay inline (no boxing), like everywhere else they travel
+#[allow(clippy::large_enum_variant)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CaptureRecord {
+    Transaction { ts_ns: u64, txn: Transaction },
+    Bundle { ts_ns: u64, bundle: Bundle },
+}
+
+impl CaptureRecord {
+    /// Capture timestamp
+    pub fn ts_ns(&self) -> u64 {
+        match self {
+            Self::Transaction { ts_ns, .. } | Self::Bundle { ts_ns, .. } => *ts_ns,
+        }
+    }
+}
+
+/// Appends records to a capture. Wrap files in a `BufWriter`: each record is
+/// one `write_all`.
+pub struct CaptureWriter<W: Write> {
+    inner: W,
+    /// Encoding scratch space, grown to the largest bundle frame seen
+    buf: Vec<u8>,
+    bytes_written: u64,
+}
+
+impl<W: Write> CaptureWriter<W> {
+    /// Start a capture by writing the file header
+    pub fn new(mut inner: W) -> io::Result<Self> {
+        let mut header = [0u8; CAPTURE_HEADER_LEN];
+        header[0..4].copy_from_slice(&CAPTURE_MAGIC);
+        header[4] = CAPTURE_VERSION;
+        inner.write_all(&header)?;
+
+        Ok(Self {
+            inner,
+            buf: Vec::with_capacity(Bundle::<BUNDLE_MAX>::MAX_FRAME_LEN + RECORD_TS_LEN),
+            bytes_written: CAPTURE_HEADER_LEN as u64,
+        })
+    }
+
+    pub fn write_transaction(&mut self, ts_ns: u64, txn: &Transaction) -> io::Result<()> {
+        self.buf.clear();
+        self.buf.extend_from_slice(&ts_ns.to_le_bytes());
+        self.buf.extend_from_slice(&txn.encode());
+        self.write_buf()
+    }
+
+    pub fn write_bundle<const N: usize>(
+        &mut self,
+        ts_ns: u64,
+        bundle: &Bundle<N>,
+    ) -> io::Result<()> {
+        let len = RECORD_TS_LEN + bundle.frame_len();
+        self.buf.clear();
+        self.buf.resize(len, 0);
+        self.buf[..RECORD_TS_LEN].copy_from_slice(&ts_ns.to_le_bytes());
+        bundle
+            .encode_into(&mut self.buf[RECORD_TS_LEN..])
+            .expect("buffer sized to frame_len");
+        self.write_buf()
+    }
+
+    fn write_buf(&mut self) -> io::Result<()> {
+        self.inner.write_all(&self.buf)?;
+        self.bytes_written += self.buf.len() as u64;
+        Ok(())
+    }
+
+    /// File size so far, header included
+    pub fn bytes_written(&self) -> u64 {
+        self.bytes_written
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+
+    pub fn get_ref(&self) -> &W {
+        &self.inner
+    }
+
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+}
+
+/// Reads records back in file order
+pub struct CaptureReader<R: Read> {
+    inner: R,
+    /// Current record, timestamp included
+    buf: Vec<u8>,
+    /// File offset of the next record
+    offset: u64,
+}
+
+impl<R: Read> CaptureReader<R> {
+    /// Check the file header
+    pub fn new(mut inner: R) -> Result<Self, CaptureError> {
+        let mut header = [0u8; CAPTURE_HEADER_LEN];
+        if read_full(&mut inner, &mut header)? < CAPTURE_HEADER_LEN {
+            return Err(CaptureError::Truncated { offset: 0 });
+        }
+        let magic = [header[0], header[1], header[2], header[3]];
+        if magic != CAPTURE_MAGIC {
+            return Err(CaptureError::BadMagic(magic));
+        }
+        if header[4] != CAPTURE_VERSION {
+            return Err(CaptureError::UnsupportedVersion(header[4]));
+        }
+
+        Ok(Self {
+            inner,
+            buf: Vec::new(),
+            offset: CAPTURE_HEADER_LEN as u64,
+        })
+    }
+
+    /// Next record, or None at the end of the file.
+    ///
+    /// # Errors
+    /// `Truncated` if the file ends inside a record (for instance one still
+    /// being written), `Frame` if a frame does not decode or is longer than
+    /// `MAX_RECORD_LEN`, `Io` on read failure.
+    pub fn next_record(&mut self) -> Result<Option<CaptureRecord>, CaptureError> {
+        let offset = self.offset;
+        let prefix = RECORD_TS_LEN + HEADER_LEN;
+        self.buf.resize(prefix, 0);
+        match read_full(&mut self.inner, &mut self.buf)? {
+            0 => return Ok(None),
+            len if len < prefix => return Err(CaptureError::Truncated { offset }),
+            _ => {}
+        }
+
+        let header = FrameHeader::parse(&self.buf[RECORD_TS_LEN..])
+            .map_err(|err| CaptureError::Frame { offset, err })?;
+        let record_len = RECORD_TS_LEN + header.frame_len();
+        // Bound the length before allocating: a damaged header can claim ~4 GiB
+        if record_len > MAX_RECORD_LEN {
+            let err = WireError::BadLength { kind: header.kind as u8, length: header.length };
+            return Err(CaptureError::Frame { offset, err });
+        }
+        self.buf.resize(record_len, 0);
+        if read_full(&mut self.inner, &mut self.buf[prefix..])? < record_len - prefix {
+            return Err(CaptureError::Truncated { offset });
+        }
+        self.offset += record_len as u64;
+
+        let ts_ns = u64::from_le_bytes(self.buf[..RECORD_TS_LEN].try_into().unwrap());
+        let frame = &self.buf[RECORD_TS_LEN..];
+        let record = match header.kind {
+            FrameKind::Transaction => {
+                Transaction::decode(frame).map(|(txn, _)| CaptureRecord::Transaction { ts_ns, txn })
+            }
+            FrameKind::Bundle => {
+                Bundle::decode(frame).map(|(bundle, _)| CaptureRecord::Bundle { ts_ns, bundle })
+            }
+        };
+        record
+            .map(Some)
+            .map_err(|err: WireError| CaptureError::Frame { offset, err })
+    }
+
+    /// File offset of the next record
+    pub fn offset(&self) -> u64 {
+        self.offset
+    }
+}
+
+impl<R: Read> Iterator for CaptureReader<R> {
+    type Item = Result<CaptureRecord, CaptureError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_record().transpose()
+    }
+}
+
+/// Fill `buf` unless the reader ends first; returns the bytes read
+pub(crate) fn read_full<R: Read>(reader: &mut R, buf: &mut [u8]) -> io::Result<usize> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        match reader.read(&mut buf[filled..]) {
+            Ok(0) => break,
+            Ok(len) => filled += len,
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+            Err(err) => return Err(err),
+        }
+    }
+    Ok(filled)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> Vec<u8> {
+        let mut writer = CaptureWriter::new(Vec::new()).unwrap();
+        writer
+            .write_transaction(100, &Transaction::new_unchecked(1, 1_000_000, 10, 0, 5))
+            .unwrap();
+        let mut txns = [Transaction::new_unchecked(0, 1, 1, 0, 0); BUNDLE_MAX];
+        txns[0] = Transaction::new_unchecked(2, 1_000_100, 20, 1, 6);
+        writer
+            .write_bundle(250, &Bundle::with_transactions(txns, 1, 7).unwrap())
+            .unwrap();
+        writer
+            .write_transaction(300, &Transaction::new_unchecked(3, 999_900, 30, 1, 8))
+            .unwrap();
+        assert_eq!(writer.bytes_written() as usize, writer.get_ref().len());
+        writer.into_inner()
+    }
+
+    #[test]
+    fn test_capture_roundtrip() {
+        let records: Vec<_> = CaptureReader::new(&sample()[..])
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+
+        assert_eq!(records.len(), 3);
+        assert_eq!(
+            records.iter().map(CaptureRecord::ts_ns).collect::<Vec<_>>(),
+            [100, 250, 300]
+        );
+        assert!(matches!(records[0], CaptureRecord::Transaction { txn, .. } if txn.id == 1));
+        assert!(matches!(records[1], CaptureRecord::Bundle { bundle, .. } if bundle.count == 1));
+    }
+
+    #[test]
+    fn test_capture_rejects_bad_files() {
+        let data = sample();
+
+        assert_eq!(
+            CaptureReader::new(&b"VXC"[..]).err(),
+            Some(CaptureError::Truncated { offset: 0 })
+        );
+        assert_eq!(
+            CaptureReader::new(&b"PCAPxxxx"[..]).err(),
+            Some(CaptureError::BadMagic(*b"PCAP"))
+        );
+        let mut future = data.clone();
+        future[4] = 9;
+        assert_eq!(
+            CaptureReader::new(&future[..]).err(),
+            Some(CaptureError::UnsupportedVersion(9))
+        );
+
+        // Cut inside the last record: earlier records still read
+        let mut reader = CaptureReader::new(&data[..data.len() - 3]).unwrap();
+        assert!(reader.next_record().unwrap().is_some());
+        assert!(reader.next_record().unwrap().is_some());
+        let offset = reader.offset();
+        assert_eq!(
+            reader.next_record(),
+            Err(CaptureError::Truncated { offset })
+        );
+
+        // Oversized bundle length is rejected before anything is allocated
+        let frame_at = CAPTURE_HEADER_LEN + RECORD_TS_LEN;
+        let mut huge = data[..frame_at + HEADER_LEN].to_vec();
+        huge[frame_at + 3] = FrameKind::Bundle as u8;
+        huge[frame_at + 4..frame_at + 8].copy_from_slice(&0xFFFF_FFF0u32.to_le_bytes());
+        huge.extend_from_slice(&[0; 8]);
+        let err = CaptureReader::new(&huge[..]).unwrap().next_record().unwrap_err();
+        assert!(matches!(
+            err,
+            CaptureError::Frame {
+                offset: 8,
+                err: WireError::BadLength { length: 0xFFFF_FFF0, .. }
+            }
+        ));
+
+        // Corrupt frame body
+        let mut corrupt = data;
+        corrupt[CAPTURE_HEADER_LEN + RECORD_TS_LEN + 12] ^= 0x10;
+        let err = CaptureReader::new(&corrupt[..])
+            .unwrap()
+            .next_record()
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            CaptureError::Frame {
+                offset: 8,
+                err: WireError::ChecksumMismatch { .. }
+            }
+        ));
+    }
+}
@@ -0,0 +1,660 @@
+/// Runtime configuration for the velox-engine binary
+///
+/// Settings come from (lowest to highest precedence) built-in defaults, a
+/// TOML file, the `OTLP_ENDPOINT` environment variable and command-line
+/// flags. Every section and key is optional; unknown keys are rejected so
+/// typos surface at startup instead of silently falling back to defaults.
+///
+/// ```toml
+/// [run]
+/// duration_secs = 60
+///
+/// [ingress]
+/// rate_hz = 250000.0
+///
+/// [ingress.model]
+/// buy_ratio = 0.6
+/// arrivals = { kind = "hawkes", branching = 0.7, decay_hz = 50000.0 }
+///
+/// [stages.orderbook]
+/// core = 1
+/// wait = "busy_spin"
+///
+/// [bundle]
+/// max_size = 8
+/// timeout_ns = 50000
+///
+/// [telemetry]
+/// enabled = false
+/// ```
+///
+/// Ring capacities and the `BUNDLE_MAX` array size are compile-time
+/// parameters and are not configurable here.
+use crate::bundle::BUNDLE_TIMEOUT_NS;
+use crate::errors::ConfigError;
+use crate::flush::{AdaptiveTimeout, FlushTriggers};
+use crate::ingress::model::MarketModel;
+use crate::pipeline::{
+    BookConfig, PipelineBuilder, StageConfig, DEFAULT_INGRESS_RATE_HZ, MAX_BOOK_LEVELS,
+    MAX_BOOK_ORDERS,
+};
+use crate::types::BUNDLE_MAX;
+use crate::wait::WaitStrategy;
+use serde::Deserialize;
+use std::path::Path;
+use std::thread;
+
+/// Default OTLP gRPC endpoint (local collector from docker-compose.yml)
+pub const DEFAULT_OTLP_ENDPOINT: &str = "http://localhost:4317";
+
+/// Complete binary configuration
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct VeloxConfig {
+    pub run: RunSettings,
+    pub ingress: IngressSettings,
+    pub stages: StagesSettings,
+    pub bundle: BundleSettings,
+    pub book: BookSettings,
+    pub telemetry: TelemetrySettings,
+}
+
+/// How long the binary runs and how often it reports
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RunSettings {
+    pub duration_secs: u64,
+    pub stats_interval_secs: u64,
+}
+
+impl Default for RunSettings {
+    fn default() -> Self {
+        Self {
+            duration_secs: 300,
+            stats_interval_secs: 1,
+        }
+    }
+}
+
+/// Synthetic ingress generator
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct IngressSettings {
+    /// Mean arrival rate in transactions per second
+    pub rate_hz: f64,
+    /// Prices, sizes, sides and arrival clustering
+    pub model: MarketModel,
+    /// Seed for the generator, for runs that offer the same transactions
+    pub seed: Option<u64>,
+}
+
+impl Default for IngressSettings {
+    fn default() -> Self {
+        Self {
+            rate_hz: DEFAULT_INGRESS_RATE_HZ,
+            model: MarketModel::default(),
+            seed: None,
+        }
+    }
+}
+
+/// Thread placement for one stage
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct StageSettings {
+    /// Core to pin to
+    pub core: usize,
+    /// Set false to let the OS schedule the thread
+    #[serde(default = "default_pinned")]
+    pub pinned: bool,
+    /// Idle behaviour: "busy_spin", "backoff" or "park"
+    #[serde(default = "default_wait")]
+    pub wait: WaitStrategy,
+}
+
+fn default_pinned() -> bool {
+    true
+}
+
+fn default_wait() -> WaitStrategy {
+    WaitStrategy::Park
+}
+
+impl StageSettings {
+    fn on_core(core: usize) -> Self {
+        Self {
+            core,
+            pinned: true,
+            wait: WaitStrategy::Park,
+        }
+    }
+
+    /// Equivalent `StageConfig`
+    pub fn stage_config(&self) -> StageConfig {
+        let config = if self.pinned {
+            StageConfig::pinned(self.core)
+        } else {
+            StageConfig::unpinned()
+        };
+        config.with_wait(self.wait)
+    }
+}
+
+/// Per-stage placement; defaults pin stages to cores 0-3
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct StagesSettings {
+    pub ingress: StageSettings,
+    pub orderbook: StageSettings,
+    pub bundle: StageSettings,
+    pub output: StageSettings,
+}
+
+impl StagesSettings {
+    /// Stages in pipeline order, with their names
+    pub fn iter(&self) -> impl Iterator<Item = (&'static str, &StageSettings)> {
+        [
+            ("ingress", &self.ingress),
+            ("orderbook", &self.orderbook),
+            ("bundle", &self.bundle),
+            ("output", &self.output),
+        ]
+        .into_iter()
+    }
+
+    /// Mutable settings in pipeline order
+    pub fn all_mut(&mut self) -> [&mut StageSettings; 4] {
+        [&mut self.ingress, &mut self.orderbook, &mut self.bundle, &mut self.output]
+    }
+}
+
+impl Default for StagesSettings {
+    fn default() -> Self {
+        Self {
+            ingress: StageSettings::on_core(0),
+            orderbook: StageSettings::on_core(1),
+            bundle: StageSettings::on_core(2),
+            output: StageSettings::on_core(3),
+        }
+    }
+}
+
+/// Bundle flush policy (see `FlushTriggers`)
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct BundleSettings {
+    /// Flush once this many transactions are pending (1..=BUNDLE_MAX)
+    pub max_size: usize,
+    /// Flush once the oldest pending transaction is this old
+    pub timeout_ns: u64,
+    /// Replaces `timeout_ns` with an arrival-rate-based timeout
+    pub adaptive_timeout: Option<AdaptiveTimeout>,
+    /// Flush once Σ price × size reaches this (raw fixed-point units)
+    pub max_notional: Option<u64>,
+    /// Flush once Σ size reaches this
+    pub max_quantity: Option<u64>,
+    /// Flush once |Σ bid size − Σ ask size| reaches this
+    pub max_imbalance: Option<u64>,
+    /// Bundles queued behind the retry slot while the output ring is full
+    pub overflow: usize,
+}
+
+impl BundleSettings {
+    /// Equivalent flush policy
+    pub fn flush_policy(&self) -> FlushTriggers {
+        let mut policy = FlushTriggers::new(self.max_size, self.timeout_ns);
+        if let Some(AdaptiveTimeout { min_ns, max_ns }) = self.adaptive_timeout {
+            policy = policy.with_adaptive_timeout(min_ns, max_ns);
+        }
+        if let Some(limit) = self.max_notional {
+            policy = policy.with_max_notional(limit as u128);
+        }
+        if let Some(limit) = self.max_quantity {
+            policy = policy.with_max_quantity(limit);
+        }
+        if let Some(limit) = self.max_imbalance {
+            policy = policy.with_max_imbalance(limit);
+        }
+        policy
+    }
+}
+
+impl Default for BundleSettings {
+    fn default() -> Self {
+        Self {
+            max_size: BUNDLE_MAX,
+            timeout_ns: BUNDLE_TIMEOUT_NS,
+            adaptive_timeout: None,
+            max_notional: None,
+            max_quantity: None,
+            max_imbalance: None,
+            overflow: 0,
+        }
+    }
+}
+
+/// Matching engine price window and order pool
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct BookSettings {
+    pub min_price: i64,
+    pub tick_size: i64,
+    pub levels: usize,
+    pub max_orders: usize,
+}
+
+impl Default for BookSettings {
+    fn default() -> Self {
+        let book = BookConfig::default();
+        Self {
+            min_price: book.min_price,
+            tick_size: book.tick_size,
+            levels: book.levels,
+            max_orders: book.max_orders,
+        }
+    }
+}
+
+/// OpenTelemetry export
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TelemetrySettings {
+    pub enabled: bool,
+    /// OTLP gRPC endpoint
+    pub endpoint: String,
+    pub service_name: String,
+}
+
+impl Default for TelemetrySettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            endpoint: DEFAULT_OTLP_ENDPOINT.to_string(),
+            service_name: "velox-engine".to_string(),
+        }
+    }
+}
+
+impl VeloxConfig {
+    /// Parse a TOML document; missing keys keep their defaults
+    pub fn from_toml_str(toml: &str) -> Result<Self, ConfigError> {
+        toml::from_str(toml).map_err(|e| ConfigError::Parse(e.to_string()))
+    }
+
+    /// Read and parse a TOML file
+    pub fn load(path: &Path) -> Result<Self, ConfigError> {
+        let text = std::fs::read_to_string(path).map_err(|e| ConfigError::Io {
+            path: path.display().to_string(),
+            kind: e.kind(),
+        })?;
+        Self::from_toml_str(&text).map_err(|e| match e {
+            ConfigError::Parse(msg) => ConfigError::Parse(format!("{}: {}", path.display(), msg)),
+            other => other,
+        })
+    }
+
+    /// Check every setting against this host; returns the first invalid one
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        let cores = thread::available_parallelism().map_or(1, |n| n.get());
+        self.validate_for_cores(cores)
+    }
+
+    /// `validate` for a host with `cores` cores: pinned stages must name one
+    /// of them
+    pub fn validate_for_cores(&self, cores: usize) -> Result<(), ConfigError> {
+        fn invalid(field: &'static str, reason: impl Into<String>) -> Result<(), ConfigError> {
+            Err(ConfigError::Invalid {
+                field,
+                reason: reason.into(),
+            })
+        }
+
+        if self.run.duration_secs == 0 {
+            return invalid("run.duration_secs", "must be at least 1");
+        }
+        if self.run.stats_interval_secs == 0 {
+            return invalid("run.stats_interval_secs", "must be at least 1");
+        }
+        if !(self.ingress.rate_hz.is_finite() && self.ingress.rate_hz > 0.0) {
+            return invalid(
+                "ingress.rate_hz",
+                format!("{} (must be a positive number)", self.ingress.rate_hz),
+            );
+        }
+        self.ingress.model.validate()?;
+        if !(1..=BUNDLE_MAX).contains(&self.bundle.max_size) {
+            return invalid(
+                "bundle.max_size",
+                format!("{} (must be in 1..={})", self.bundle.max_size, BUNDLE_MAX),
+            );
+        }
+        if self.bundle.timeout_ns == 0 {
+            return invalid("bundle.timeout_ns", "must be at least 1");
+        }
+        if let Some(AdaptiveTimeout { min_ns, max_ns }) = self.bundle.adaptive_timeout {
+            if min_ns == 0 || min_ns > max_ns {
+                return invalid(
+                    "bundle.adaptive_timeout",
+                    format!("min_ns = {}, max_ns = {} (need 1 <= min_ns <= max_ns)", min_ns, max_ns),
+                );
+            }
+        }
+        for (field, limit) in [
+            ("bundle.max_notional", self.bundle.max_notional),
+            ("bundle.max_quantity", self.bundle.max_quantity),
+            ("bundle.max_imbalance", self.bundle.max_imbalance),
+        ] {
+            if limit == Some(0) {
+                return invalid(field, "must be at least 1 (omit it to disable the trigger)");
+            }
+        }
+        if self.book.min_price <= 0 {
+            return invalid(
+                "book.min_price",
+                format!("{} (must be positive)", self.book.min_price),
+            );
+        }
+        if self.book.tick_size <= 0 {
+            return invalid(
+                "book.tick_size",
+                format!("{} (must be positive)", self.book.tick_size),
+            );
+        }
+        // Both are allocated up front: bound them so a typo is an error, not
+        // an allocation failure
+        if !(1..=MAX_BOOK_LEVELS).contains(&self.book.levels) {
+            return invalid(
+                "book.levels",
+                format!("{} (must be in 1..={})", self.book.levels, MAX_BOOK_LEVELS),
+            );
+        }
+        if !(1..=MAX_BOOK_ORDERS).contains(&self.book.max_orders) {
+            return invalid(
+                "book.max_orders",
+                format!("{} (must be in 1..={})", self.book.max_orders, MAX_BOOK_ORDERS),
+            );
+        }
+        // Same window check as ExactOrderBook::new, without its panic
+        let book_max = match i64::try_from(self.book.levels - 1)
+            .ok()
+            .and_then(|span| span.checked_mul(self.book.tick_size))
+            .and_then(|span| self.book.min_price.checked_add(span))
+        {
+            Some(max) => max,
+            None => {
+                return invalid(
+                    "book.levels",
+                    format!(
+                        "{} (min_price + (levels - 1) * tick_size overflows i64)",
+                        self.book.levels
+                    ),
+                )
+            }
+        };
+        // Orders outside the book's range are rejected by the matching engine
+        let model = &self.ingress.model;
+        if model.min_price < self.book.min_price || model.max_price > book_max {
+            return invalid(
+                "ingress.model.max_price",
+                format!(
+                    "{}..={} (must be inside the book's range {}..={})",
+                    model.min_price, model.max_price, self.book.min_price, book_max
+                ),
+            );
+        }
+        // Pinning to a core the host does not have is silently ignored
+        for (field, stage) in [
+            ("stages.ingress.core", &self.stages.ingress),
+            ("stages.orderbook.core", &self.stages.orderbook),
+            ("stages.bundle.core", &self.stages.bundle),
+            ("stages.output.core", &self.stages.output),
+        ] {
+            if stage.pinned && stage.core >= cores {
+                return invalid(
+                    field,
+                    format!(
+                        "{} (this host has cores 0..{}; set pinned = false to let the OS schedule it)",
+                        stage.core, cores
+                    ),
+                );
+            }
+        }
+        if self.telemetry.enabled {
+            let endpoint = &self.telemetry.endpoint;
+            if !(endpoint.starts_with("http://") || endpoint.starts_with("https://")) {
+                return invalid(
+                    "telemetry.endpoint",
+                    format!("{:?} (must be an http:// or https:// URL)", endpoint),
+                );
+            }
+            if self.telemetry.service_name.is_empty() {
+                return invalid("telemetry.service_name", "must not be empty");
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Matching engine sizing
+    pub fn book_config(&self) -> BookConfig {
+        BookConfig {
+            min_price: self.book.min_price,
+            tick_size: self.book.tick_size,
+            levels: self.book.levels,
+            max_orders: self.book.max_orders,
+        }
+    }
+
+    /// Pipeline builder with every setting applied (call `validate` first)
+    pub fn pipeline_builder(&self) -> PipelineBuilder {
+        let builder = match self.ingress.seed {
+            Some(seed) => PipelineBuilder::new().ingress_seed(seed),
+            None => PipelineBuilder::new(),
+        };
+        builder
+            .ingress_rate_hz(self.ingress.rate_hz)
+            .market_model(self.ingress.model)
+            .book(self.book_config())
+            .flush_policy(self.bundle.flush_policy())
+            .bundle_overflow(self.bundle.overflow)
+            .ingress(self.stages.ingress.stage_config())
+            .orderbook(self.stages.orderbook.stage_config())
+            .bundle(self.stages.bundle.stage_config())
+            .output(self.stages.output.stage_config())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_defaults_are_valid() {
+        let config = VeloxConfig::default();
+        assert!(config.validate_for_cores(8).is_ok());
+        assert_eq!(config, VeloxConfig::from_toml_str("").unwrap());
+        assert_eq!(config.stages.orderbook.stage_config(), StageConfig::pinned(1));
+    }
+
+    #[test]
+    fn test_partial_file_overrides_defaults() {
+        let config = VeloxConfig::from_toml_str(
+            r#"
+            [ingress]
+            rate_hz = 5000.0
+
+            [stages.bundle]
+            core = 5
+            wait = "busy_spin"
+
+            [stages.output]
+            core = 0
+            pinned = false
+
+            [bundle]
+            max_size = 4
+            "#,
+        )
+        .unwrap();
+
+        assert!(config.validate_for_cores(8).is_ok());
+        assert_eq!(config.ingress.rate_hz, 5000.0);
+        assert_eq!(
+            config.stages.bundle.stage_config(),
+            StageConfig::pinned(5).with_wait(WaitStrategy::BusySpin)
+        );
+        assert_eq!(config.stages.output.stage_config(), StageConfig::unpinned());
+        assert_eq!(config.bundle.max_size, 4);
+        // Untouched settings keep their defaults
+        assert_eq!(config.bundle.timeout_ns, BUNDLE_TIMEOUT_NS);
+        assert_eq!(config.run, RunSettings::default());
+    }
+
+    #[test]
+    fn test_unknown_keys_and_bad_types_rejected() {
+        let typo = VeloxConfig::from_toml_str("[ingress]\nrate_hertz = 10.0\n");
+        assert!(matches!(typo, Err(ConfigError::Parse(msg)) if msg.contains("rate_hertz")));
+
+        let wait = VeloxConfig::from_toml_str("[stages.output]\ncore = 3\nwait = \"sleep\"\n");
+        assert!(matches!(wait, Err(ConfigError::Parse(_))));
+
+        let negative = VeloxConfig::from_toml_str("[run]\nduration_secs = -5\n");
+        assert!(matches!(negative, Err(ConfigError::Parse(_))));
+    }
+
+    #[test]
+    fn test_flush_policy_settings() {
+        use crate::flush::{FlushPolicy, FlushReason};
+
+        let config = VeloxConfig::from_toml_str(
+            r#"
+            [bundle]
+            max_size = 8
+            max_quantity = 1000
+            adaptive_timeout = { min_ns = 10000, max_ns = 200000 }
+            overflow = 4
+            "#,
+        )
+        .unwrap();
+        assert!(config.validate_for_cores(8).is_ok());
+        assert_eq!(config.bundle.overflow, 4);
+
+        let policy = config.bundle.flush_policy();
+        assert_eq!(policy.max_size(), 8);
+        assert_eq!(policy.timeout_reason(), FlushReason::AdaptiveTimeout);
+        assert_eq!(policy.min_timeout_ns(), 10_000);
+    }
+
+    #[test]
+    fn test_market_model_settings() {
+        use crate::ingress::model::Arrivals;
+
+        let config = VeloxConfig::from_toml_str(
+            r#"
+            [ingress]
+            seed = 7
+
+            [ingress.model]
+            buy_ratio = 0.7
+            size_alpha = 1.2
+            arrivals = { kind = "on_off", on_mean_ns = 200000, off_mean_ns = 800000 }
+            "#,
+        )
+        .unwrap();
+        assert!(config.validate_for_cores(8).is_ok());
+        assert_eq!(config.ingress.seed, Some(7));
+        assert_eq!(config.ingress.model.buy_ratio, 0.7);
+        assert_eq!(
+            config.ingress.model.arrivals,
+            Arrivals::OnOff {
+                on_mean_ns: 200_000,
+                off_mean_ns: 800_000
+            }
+        );
+        // Untouched model settings keep their defaults
+        assert_eq!(config.ingress.model.tick_size, MarketModel::default().tick_size);
+
+        let typo = r#"
+            [ingress.model]
+            arrivals = { kind = "hawkes", branch = 0.5, decay_hz = 1.0 }
+            "#;
+        assert!(matches!(VeloxConfig::from_toml_str(typo), Err(ConfigError::Parse(_))));
+    }
+
+    #[test]
+    fn test_validate_reports_field() {
+        let field_of = |config: VeloxConfig| match config.validate_for_cores(8) {
+            Err(ConfigError::Invalid { field, .. }) => field,
+            other => panic!("expected Invalid, got {:?}", other),
+        };
+
+        let mut config = VeloxConfig::default();
+        config.ingress.rate_hz = f64::NAN;
+        assert_eq!(field_of(config), "ingress.rate_hz");
+
+        let mut config = VeloxConfig::default();
+        config.bundle.max_size = BUNDLE_MAX + 1;
+        assert_eq!(field_of(config), "bundle.max_size");
+
+        let mut config = VeloxConfig::default();
+        config.bundle.adaptive_timeout = Some(AdaptiveTimeout { min_ns: 500, max_ns: 100 });
+        assert_eq!(field_of(config), "bundle.adaptive_timeout");
+
+        let mut config = VeloxConfig::default();
+        config.bundle.max_imbalance = Some(0);
+        assert_eq!(field_of(config), "bundle.max_imbalance");
+
+        let mut config = VeloxConfig::default();
+        config.book.tick_size = 0;
+        assert_eq!(field_of(config), "book.tick_size");
+
+        let mut config = VeloxConfig::default();
+        config.ingress.model.buy_ratio = -0.1;
+        assert_eq!(field_of(config), "ingress.model.buy_ratio");
+
+        // Sizes are bounded before anything is allocated
+        let mut config = VeloxConfig::default();
+        config.book.max_orders = MAX_BOOK_ORDERS + 1;
+        assert_eq!(field_of(config), "book.max_orders");
+
+        let mut config = VeloxConfig::default();
+        config.book.levels = 4_000_000_000;
+        assert_eq!(field_of(config), "book.levels");
+
+        let mut config = VeloxConfig::default();
+        config.book.levels = usize::MAX;
+        assert_eq!(field_of(config), "book.levels");
+
+        // The price window must not overflow
+        let mut config = VeloxConfig::default();
+        config.book.levels = MAX_BOOK_LEVELS;
+        config.book.tick_size = i64::MAX / 2;
+        assert_eq!(field_of(config), "book.levels");
+
+        // Pinned cores must exist on the host
+        let mut config = VeloxConfig::default();
+        config.stages.bundle.core = 8;
+        assert_eq!(field_of(config.clone()), "stages.bundle.core");
+        config.stages.bundle.pinned = false;
+        assert!(config.validate_for_cores(8).is_ok());
+
+        // Model prices must fit the book
+        let mut config = VeloxConfig::default();
+        config.book.levels = 100_000;
+        assert_eq!(field_of(config), "ingress.model.max_price");
+
+        let mut config = VeloxConfig::default();
+        config.telemetry.endpoint = "localhost:4317".to_string();
+        assert_eq!(field_of(config.clone()), "telemetry.endpoint");
+        // Endpoint is irrelevant once telemetry is off
+        config.telemetry.enabled = false;
+        assert!(config.validate_for_cores(8).is_ok());
+    }
+
+    #[test]
+    fn test_load_missing_file() {
+        let err = VeloxConfig::load(Path::new("/nonexistent/velox.toml")).unwrap_err();
+        assert!(matches!(err, ConfigError::Io { kind: std::io::ErrorKind::NotFound, .. }));
+        assert!(err.to_string().contains("/nonexistent/velox.toml"));
+    }
+}
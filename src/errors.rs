@@ -10,6 +10,12 @@ pub enum TransactionError {
     NegativePrice(i64),
     /// Size must be non-zero
     ZeroSize,
+    /// Action byte is not a known `OrderAction`
+    UnknownAction(u8),
+    /// Cancel/modify/replace refers to an order that is not resting
+    UnknownOrderId(u64),
+    /// Reserved bytes of a serialized transaction are not zero
+    NonZeroPadding([u8; 2]),
 }
 
 impl fmt::Display for TransactionError {
@@ -18,6 +24,11 @@ impl fmt::Display for TransactionError {
             Self::InvalidSide(side) => write!(f, "Invalid side: {} (must be 0 or 1)", side),
             Self::NegativePrice(price) => write!(f, "Negative price: {} (must be positive)", price),
             Self::ZeroSize => write!(f, "Zero size (must be non-zero)"),
+            Self::UnknownAction(action) => write!(f, "Unknown order action: {} (must be 0-3)", action),
+            Self::UnknownOrderId(id) => write!(f, "Unknown order id: {}", id),
+            Self::NonZeroPadding(bytes) => {
+                write!(f, "Non-zero padding: {:02x}{:02x} (must be zero)", bytes[0], bytes[1])
+            }
         }
     }
 }
@@ -43,6 +54,146 @@ impl fmt::Display for BundleError {
 
 impl std::error::Error for BundleError {}
 
+/// Errors encoding or decoding a wire frame (see `wire`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WireError {
+    /// Fewer bytes than the frame needs
+    Truncated { needed: usize, available: usize },
+    /// Output buffer too small for the encoded frame
+    BufferTooSmall { needed: usize, available: usize },
+    /// Frame does not start with the `VX` magic
+    BadMagic([u8; 2]),
+    /// Frame version this decoder does not understand
+    UnsupportedVersion(u8),
+    /// Kind byte is not a known message kind
+    UnknownKind(u8),
+    /// Frame holds a different message kind than requested
+    UnexpectedKind { expected: u8, found: u8 },
+    /// Payload length is impossible for the message kind
+    BadLength { kind: u8, length: u32 },
+    /// CRC32 over header and payload does not match the trailer
+    ChecksumMismatch { expected: u32, actual: u32 },
+    /// Bundle does not fit the decoder's capacity
+    Bundle(BundleError),
+    /// Checksum matched but a transaction fails validation
+    Transaction(TransactionError),
+}
+
+impl fmt::Display for WireError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Truncated { needed, available } => {
+                write!(f, "Truncated frame: need {} bytes, have {}", needed, available)
+            }
+            Self::BufferTooSmall { needed, available } => {
+                write!(f, "Buffer too small: need {} bytes, have {}", needed, available)
+            }
+            Self::BadMagic(magic) => write!(f, "Bad frame magic: {:02x}{:02x}", magic[0], magic[1]),
+            Self::UnsupportedVersion(version) => write!(f, "Unsupported frame version: {}", version),
+            Self::UnknownKind(kind) => write!(f, "Unknown frame kind: {}", kind),
+            Self::UnexpectedKind { expected, found } => {
+                write!(f, "Unexpected frame kind: {} (expected {})", found, expected)
+            }
+            Self::BadLength { kind, length } => {
+                write!(f, "Invalid payload length {} for frame kind {}", length, kind)
+            }
+            Self::ChecksumMismatch { expected, actual } => {
+                write!(f, "Frame checksum mismatch: expected {:08x}, got {:08x}", expected, actual)
+            }
+            Self::Bundle(err) => write!(f, "Invalid bundle frame: {}", err),
+            Self::Transaction(err) => write!(f, "Invalid transaction in frame: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for WireError {}
+
+impl From<BundleError> for WireError {
+    fn from(err: BundleError) -> Self {
+        Self::Bundle(err)
+    }
+}
+
+impl From<TransactionError> for WireError {
+    fn from(err: TransactionError) -> Self {
+        Self::Transaction(err)
+    }
+}
+
+/// Errors reading a capture file (see `capture`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CaptureError {
+    /// The underlying reader failed
+    Io(std::io::ErrorKind),
+    /// File does not start with the capture magic
+    BadMagic([u8; 4]),
+    /// Capture format version this reader does not understand
+    UnsupportedVersion(u8),
+    /// File ends inside the record starting at `offset`
+    Truncated { offset: u64 },
+    /// Record at `offset` holds an invalid frame
+    Frame { offset: u64, err: WireError },
+}
+
+impl fmt::Display for CaptureError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(kind) => write!(f, "Capture read failed: {}", kind),
+            Self::BadMagic(magic) => write!(f, "Not a capture file (magic {:02x?})", magic),
+            Self::UnsupportedVersion(version) => write!(f, "Unsupported capture version: {}", version),
+            Self::Truncated { offset } => write!(f, "Capture truncated in record at offset {}", offset),
+            Self::Frame { offset, err } => write!(f, "Bad frame in record at offset {}: {}", offset, err),
+        }
+    }
+}
+
+impl std::error::Error for CaptureError {}
+
+impl From<std::io::Error> for CaptureError {
+    fn from(err: std::io::Error) -> Self {
+        Self::Io(err.kind())
+    }
+}
+
+/// Errors reading a pcap or pcapng file (see `ingress::pcap`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PcapError {
+    /// The underlying reader failed
+    Io(std::io::ErrorKind),
+    /// File starts with neither a pcap nor a pcapng magic number
+    BadMagic(u32),
+    /// File ends inside the header or block starting at `offset`
+    Truncated { offset: u64 },
+    /// Record or block at `offset` has an impossible length
+    BadLength { offset: u64, length: u32 },
+    /// pcapng packet refers to an interface that was never described
+    UnknownInterface { offset: u64, interface: u32 },
+}
+
+impl fmt::Display for PcapError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(kind) => write!(f, "Pcap read failed: {}", kind),
+            Self::BadMagic(magic) => write!(f, "Not a pcap or pcapng file (magic {:08x})", magic),
+            Self::Truncated { offset } => write!(f, "Pcap truncated in block at offset {}", offset),
+            Self::BadLength { offset, length } => {
+                write!(f, "Bad block length {} at offset {}", length, offset)
+            }
+            Self::UnknownInterface { offset, interface } => {
+                write!(f, "Packet at offset {} uses undeclared interface {}", offset, interface)
+            }
+        }
+    }
+}
+
+impl std::error::Error for PcapError {}
+
+impl From<std::io::Error> for PcapError {
+    fn from(err: std::io::Error) -> Self {
+        Self::Io(err.kind())
+    }
+}
+
 /// Errors that can occur in the order book
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum OrderBookError {
@@ -50,6 +201,16 @@ pub enum OrderBookError {
     QuantityOverflow,
     /// CAS loop exceeded maximum retries
     Timeout,
+    /// Price falls outside the book's configured price window
+    PriceOutOfRange { price: i64, min: i64, max: i64 },
+    /// Price is not a multiple of the tick size from the window anchor
+    OffTick { price: i64, tick_size: i64 },
+    /// An order with this id is already resting in the book
+    DuplicateOrderId(u64),
+    /// No free slots left for another resting order
+    OrderPoolFull,
+    /// Transaction carries an invalid action or refers to an unknown order
+    InvalidTransaction(TransactionError),
 }
 
 impl fmt::Display for OrderBookError {
@@ -57,8 +218,72 @@ impl fmt::Display for OrderBookError {
         match self {
             Self::QuantityOverflow => write!(f, "Order book quantity overflow"),
             Self::Timeout => write!(f, "CAS operation timed out after max retries"),
+            Self::PriceOutOfRange { price, min, max } => {
+                write!(f, "Price {} outside book window [{}, {}]", price, min, max)
+            }
+            Self::OffTick { price, tick_size } => {
+                write!(f, "Price {} is not on a tick boundary (tick size {})", price, tick_size)
+            }
+            Self::DuplicateOrderId(id) => write!(f, "Order id {} is already resting", id),
+            Self::OrderPoolFull => write!(f, "Resting order pool is full"),
+            Self::InvalidTransaction(err) => write!(f, "Invalid transaction: {}", err),
         }
     }
 }
 
 impl std::error::Error for OrderBookError {}
+
+impl From<TransactionError> for OrderBookError {
+    fn from(err: TransactionError) -> Self {
+        Self::InvalidTransaction(err)
+    }
+}
+
+/// Errors that can occur when starting or stopping a Pipeline
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PipelineError {
+    /// The OS refused to spawn a stage thread
+    Spawn(std::io::ErrorKind),
+    /// A stage thread panicked before it could be joined
+    StagePanicked(&'static str),
+    /// A builder setting is out of range; nothing was spawned
+    InvalidConfig(String),
+    /// The external ingress source stopped with an I/O error
+    IngressFailed { kind: std::io::ErrorKind, message: String },
+}
+
+impl fmt::Display for PipelineError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Spawn(kind) => write!(f, "Failed to spawn stage thread: {}", kind),
+            Self::StagePanicked(stage) => write!(f, "Stage thread panicked: {}", stage),
+            Self::InvalidConfig(reason) => write!(f, "Invalid pipeline configuration: {}", reason),
+            Self::IngressFailed { message, .. } => write!(f, "Ingress source failed: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for PipelineError {}
+
+/// Errors loading or validating the binary's configuration
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConfigError {
+    /// The config file could not be read
+    Io { path: String, kind: std::io::ErrorKind },
+    /// The config file is not valid TOML or has unknown/mistyped keys
+    Parse(String),
+    /// A setting is out of range
+    Invalid { field: &'static str, reason: String },
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io { path, kind } => write!(f, "Cannot read config file {}: {}", path, kind),
+            Self::Parse(msg) => write!(f, "Invalid config file: {}", msg),
+            Self::Invalid { field, reason } => write!(f, "Invalid value for {}: {}", field, reason),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
@@ -0,0 +1,429 @@
+use core::sync::atomic::{AtomicI64, AtomicU64, Ordering};
+use crate::errors::OrderBookError;
+use crate::orderbook::BookQuery;
+
+/// Maximum CAS retry attempts before timeout
+const MAX_RETRIES: usize = 100;
+
+/// Cache-line padded atomic for best bid/ask tracking
+#[repr(C, align(64))]
+struct CachePadded<T> {
+    value: T,
+}
+
+impl<T> CachePadded<T> {
+    fn new(value: T) -> Self {
+        Self { value }
+    }
+}
+
+/// A single exact price level.
+/// Not cache-line padded: windows span thousands of ticks and adjacent
+/// levels are almost always touched by the same thread.
+#[repr(C)]
+struct TickLevel {
+    /// Net quantity at this tick (can be negative)
+    quantity: AtomicI64,
+    /// Last update timestamp (TSC or nanoseconds)
+    timestamp: AtomicU64,
+}
+
+impl TickLevel {
+    fn new() -> Self {
+        Self {
+            quantity: AtomicI64::new(0),
+            timestamp: AtomicU64::new(0),
+        }
+    }
+}
+
+/// Lock-free order book with one level per tick over a fixed price window.
+///
+/// Unlike `OrderBook`, every tick in `[min_price, max_price]` has its own
+/// level, so best bid/ask and per-price quantities are exact. Prices outside
+/// the window (or between ticks) are rejected instead of aliasing onto
+/// another level.
+///
+/// Level storage is allocated once at construction; updates never allocate.
+pub struct ExactOrderBook {
+    /// Bid side (buy orders), indexed by tick offset from `min_price`
+    bids: Box<[TickLevel]>,
+    /// Ask side (sell orders), indexed by tick offset from `min_price`
+    asks: Box<[TickLevel]>,
+    /// Lowest price in the window (anchor)
+    min_price: i64,
+    /// Price increment between adjacent levels
+    tick_size: i64,
+    /// Best bid price (highest)
+    best_bid: CachePadded<AtomicI64>,
+    /// Best ask price (lowest)
+    best_ask: CachePadded<AtomicI64>,
+}
+
+impl ExactOrderBook {
+    /// Create a book covering `levels` ticks starting at `min_price`.
+    ///
+    /// The window is `[min_price, min_price + (levels - 1) * tick_size]`.
+    ///
+    /// # Panics
+    /// Panics if `min_price` or `tick_size` is not positive, if `levels` is 0,
+    /// or if the window would overflow `i64`.
+    pub fn new(min_price: i64, tick_size: i64, levels: usize) -> Self {
+        assert!(min_price > 0, "ExactOrderBook min_price must be positive");
+        assert!(tick_size > 0, "ExactOrderBook tick_size must be positive");
+        assert!(levels > 0, "ExactOrderBook needs at least one level");
+        assert!(
+            (levels as i64 - 1)
+                .checked_mul(tick_size)
+                .and_then(|span| min_price.checked_add(span))
+                .is_some(),
+            "ExactOrderBook price window overflows i64"
+        );
+
+        Self {
+            bids: (0..levels).map(|_| TickLevel::new()).collect(),
+            asks: (0..levels).map(|_| TickLevel::new()).collect(),
+            min_price,
+            tick_size,
+            best_bid: CachePadded::new(AtomicI64::new(0)),
+            best_ask: CachePadded::new(AtomicI64::new(i64::MAX)),
+        }
+    }
+
+    /// Lowest price accepted by the book
+    pub fn min_price(&self) -> i64 {
+        self.min_price
+    }
+
+    /// Highest price accepted by the book
+    pub fn max_price(&self) -> i64 {
+        self.min_price + (self.bids.len() as i64 - 1) * self.tick_size
+    }
+
+    /// Price increment between adjacent levels
+    pub fn tick_size(&self) -> i64 {
+        self.tick_size
+    }
+
+    /// Number of price levels per side
+    pub fn levels(&self) -> usize {
+        self.bids.len()
+    }
+
+    /// Check whether a price maps to a level in this book
+    pub fn contains(&self, price: i64) -> bool {
+        self.level_index(price).is_ok()
+    }
+
+    /// Map price to its exact level index
+    #[inline(always)]
+    pub(crate) fn level_index(&self, price: i64) -> Result<usize, OrderBookError> {
+        if price < self.min_price || price > self.max_price() {
+            return Err(OrderBookError::PriceOutOfRange {
+                price,
+                min: self.min_price,
+                max: self.max_price(),
+            });
+        }
+
+        let offset = price - self.min_price;
+        if offset % self.tick_size != 0 {
+            return Err(OrderBookError::OffTick {
+                price,
+                tick_size: self.tick_size,
+            });
+        }
+
+        Ok((offset / self.tick_size) as usize)
+    }
+
+    /// Price of the level at `idx`
+    #[inline(always)]
+    pub(crate) fn level_price(&self, idx: usize) -> i64 {
+        self.min_price + idx as i64 * self.tick_size
+    }
+
+    /// Update a bid level with delta quantity.
+    /// Uses bounded CAS retry with exponential backoff.
+    pub fn update_bid(&self, price: i64, delta: i64, timestamp: u64) -> Result<(), OrderBookError> {
+        let idx = self.level_index(price)?;
+        let new_qty = Self::apply_delta(&self.bids[idx], delta, timestamp)?;
+        self.update_best_bid(idx, new_qty);
+        Ok(())
+    }
+
+    /// Update an ask level with delta quantity
+    pub fn update_ask(&self, price: i64, delta: i64, timestamp: u64) -> Result<(), OrderBookError> {
+        let idx = self.level_index(price)?;
+        let new_qty = Self::apply_delta(&self.asks[idx], delta, timestamp)?;
+        self.update_best_ask(idx, new_qty);
+        Ok(())
+    }
+
+    /// CAS loop shared by both sides; returns the new level quantity
+    fn apply_delta(level: &TickLevel, delta: i64, timestamp: u64) -> Result<i64, OrderBookError> {
+        let mut backoff = 1;
+        for _ in 0..MAX_RETRIES {
+            let current = level.quantity.load(Ordering::Acquire);
+
+            // Check for overflow before adding
+            let new_qty = current.checked_add(delta)
+                .ok_or(OrderBookError::QuantityOverflow)?;
+
+            match level.quantity.compare_exchange_weak(
+                current,
+                new_qty,
+                Ordering::Release,
+                Ordering::Relaxed,
+            ) {
+                Ok(_) => {
+                    level.timestamp.store(timestamp, Ordering::Relaxed);
+                    return Ok(new_qty);
+                }
+                Err(_) => {
+                    for _ in 0..backoff {
+                        core::hint::spin_loop();
+                    }
+                    backoff = (backoff * 2).min(64);
+                }
+            }
+        }
+
+        Err(OrderBookError::Timeout)
+    }
+
+    /// Update best bid after a level change (optimistic, may be slightly stale)
+    fn update_best_bid(&self, idx: usize, new_qty: i64) {
+        let price = self.level_price(idx);
+        let mut current_best = self.best_bid.value.load(Ordering::Relaxed);
+
+        if new_qty > 0 {
+            while price > current_best {
+                match self.best_bid.value.compare_exchange_weak(
+                    current_best,
+                    price,
+                    Ordering::Relaxed,
+                    Ordering::Relaxed,
+                ) {
+                    Ok(_) => break,
+                    Err(x) => current_best = x,
+                }
+            }
+        } else if price == current_best {
+            // Levels are exact, so the next best bid is the first
+            // non-empty level below this one
+            let next_best = self.bids[..idx]
+                .iter()
+                .rposition(|lvl| lvl.quantity.load(Ordering::Relaxed) > 0)
+                .map_or(0, |i| self.level_price(i));
+            let _ = self.best_bid.value.compare_exchange(
+                current_best,
+                next_best,
+                Ordering::Relaxed,
+                Ordering::Relaxed,
+            );
+        }
+    }
+
+    /// Update best ask after a level change
+    fn update_best_ask(&self, idx: usize, new_qty: i64) {
+        let price = self.level_price(idx);
+        let mut current_best = self.best_ask.value.load(Ordering::Relaxed);
+
+        if new_qty > 0 {
+            while price < current_best {
+                match self.best_ask.value.compare_exchange_weak(
+                    current_best,
+                    price,
+                    Ordering::Relaxed,
+                    Ordering::Relaxed,
+                ) {
+                    Ok(_) => break,
+                    Err(x) => current_best = x,
+                }
+            }
+        } else if price == current_best {
+            let next_best = self.asks[idx + 1..]
+                .iter()
+                .position(|lvl| lvl.quantity.load(Ordering::Relaxed) > 0)
+                .map_or(i64::MAX, |i| self.level_price(idx + 1 + i));
+            let _ = self.best_ask.value.compare_exchange(
+                current_best,
+                next_best,
+                Ordering::Relaxed,
+                Ordering::Relaxed,
+            );
+        }
+    }
+
+    /// Get current best bid price (may be slightly stale)
+    pub fn best_bid(&self) -> i64 {
+        self.best_bid.value.load(Ordering::Relaxed)
+    }
+
+    /// Get current best ask price (may be slightly stale)
+    pub fn best_ask(&self) -> i64 {
+        self.best_ask.value.load(Ordering::Relaxed)
+    }
+
+    /// Get quantity at an exact bid price (0 if outside the window)
+    pub fn bid_quantity(&self, price: i64) -> i64 {
+        self.level_index(price)
+            .map_or(0, |idx| self.bids[idx].quantity.load(Ordering::Acquire))
+    }
+
+    /// Get quantity at an exact ask price (0 if outside the window)
+    pub fn ask_quantity(&self, price: i64) -> i64 {
+        self.level_index(price)
+            .map_or(0, |idx| self.asks[idx].quantity.load(Ordering::Acquire))
+    }
+
+    /// Get spread (best_ask - best_bid)
+    pub fn spread(&self) -> i64 {
+        BookQuery::spread(self)
+    }
+
+    /// Count of bid levels with non-zero quantity (for telemetry).
+    /// Scans the whole window; call at low frequency.
+    #[inline]
+    pub fn depth_bid(&self) -> u64 {
+        self.bids
+            .iter()
+            .filter(|lvl| lvl.quantity.load(Ordering::Relaxed) != 0)
+            .count() as u64
+    }
+
+    /// Count of ask levels with non-zero quantity (for telemetry).
+    /// Scans the whole window; call at low frequency.
+    #[inline]
+    pub fn depth_ask(&self) -> u64 {
+        self.asks
+            .iter()
+            .filter(|lvl| lvl.quantity.load(Ordering::Relaxed) != 0)
+            .count() as u64
+    }
+}
+
+impl BookQuery for ExactOrderBook {
+    fn best_bid(&self) -> i64 {
+        ExactOrderBook::best_bid(self)
+    }
+
+    fn best_ask(&self) -> i64 {
+        ExactOrderBook::best_ask(self)
+    }
+
+    fn bid_quantity(&self, price: i64) -> i64 {
+        ExactOrderBook::bid_quantity(self, price)
+    }
+
+    fn ask_quantity(&self, price: i64) -> i64 {
+        ExactOrderBook::ask_quantity(self, price)
+    }
+
+    fn depth_bid(&self) -> u64 {
+        ExactOrderBook::depth_bid(self)
+    }
+
+    fn depth_ask(&self) -> u64 {
+        ExactOrderBook::depth_ask(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_window_bounds() {
+        let book = ExactOrderBook::new(1000, 1, 100);
+        assert_eq!(book.min_price(), 1000);
+        assert_eq!(book.max_price(), 1099);
+        assert!(book.contains(1000));
+        assert!(book.contains(1099));
+        assert!(!book.contains(999));
+        assert!(!book.contains(1100));
+    }
+
+    #[test]
+    fn test_adjacent_ticks_are_distinct() {
+        let book = ExactOrderBook::new(1000, 1, 100);
+
+        book.update_bid(1000, 10, 1).unwrap();
+        book.update_bid(1001, 20, 2).unwrap();
+
+        assert_eq!(book.bid_quantity(1000), 10);
+        assert_eq!(book.bid_quantity(1001), 20);
+        assert_eq!(book.best_bid(), 1001);
+        assert_eq!(book.depth_bid(), 2);
+    }
+
+    #[test]
+    fn test_out_of_window_rejected() {
+        let book = ExactOrderBook::new(1000, 1, 100);
+
+        assert_eq!(
+            book.update_bid(1100, 10, 0),
+            Err(OrderBookError::PriceOutOfRange { price: 1100, min: 1000, max: 1099 })
+        );
+        assert_eq!(
+            book.update_ask(999, 10, 0),
+            Err(OrderBookError::PriceOutOfRange { price: 999, min: 1000, max: 1099 })
+        );
+        assert_eq!(book.depth_bid(), 0);
+        assert_eq!(book.depth_ask(), 0);
+    }
+
+    #[test]
+    fn test_off_tick_rejected() {
+        let book = ExactOrderBook::new(1000, 5, 10);
+
+        assert!(book.update_bid(1005, 10, 0).is_ok());
+        assert_eq!(
+            book.update_bid(1003, 10, 0),
+            Err(OrderBookError::OffTick { price: 1003, tick_size: 5 })
+        );
+    }
+
+    #[test]
+    fn test_best_bid_rescans_on_empty_level() {
+        let book = ExactOrderBook::new(1000, 1, 100);
+
+        book.update_bid(1010, 10, 1).unwrap();
+        book.update_bid(1020, 10, 2).unwrap();
+        assert_eq!(book.best_bid(), 1020);
+
+        book.update_bid(1020, -10, 3).unwrap();
+        assert_eq!(book.best_bid(), 1010);
+
+        book.update_bid(1010, -10, 4).unwrap();
+        assert_eq!(book.best_bid(), 0);
+    }
+
+    #[test]
+    fn test_best_ask_rescans_on_empty_level() {
+        let book = ExactOrderBook::new(1000, 1, 100);
+
+        book.update_ask(1050, 10, 1).unwrap();
+        book.update_ask(1040, 10, 2).unwrap();
+        assert_eq!(book.best_ask(), 1040);
+
+        book.update_ask(1040, -10, 3).unwrap();
+        assert_eq!(book.best_ask(), 1050);
+
+        book.update_ask(1050, -10, 4).unwrap();
+        assert_eq!(book.best_ask(), i64::MAX);
+    }
+
+    #[test]
+    fn test_spread() {
+        let book = ExactOrderBook::new(1000, 1, 200);
+
+        assert_eq!(book.spread(), 0);
+
+        book.update_bid(1000, 100, 1).unwrap();
+        book.update_ask(1100, 100, 2).unwrap();
+
+        assert_eq!(book.spread(), 100);
+    }
+}
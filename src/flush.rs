@@ -0,0 +1,302 @@
+/// Bundle flush policies
+///
+/// A `FlushPolicy` decides when `BundleBuilder` closes the pending bundle.
+/// A size cap (at most the bundle's array capacity) and a timeout are always
+/// in force; `FlushTriggers`, the standard policy, adds optional triggers on
+/// notional value, aggregate quantity and bid/ask imbalance, and can replace
+/// the fixed timeout with one that adapts to the observed arrival rate.
+///
+/// Policies are chosen when the builder is constructed
+/// (`BundleBuilder::with_policy`) and are statically dispatched.
+use crate::bundle::BUNDLE_TIMEOUT_NS;
+use crate::types::{Transaction, BUNDLE_MAX};
+use serde::Deserialize;
+
+/// Why a bundle was flushed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FlushReason {
+    /// Reached the policy's size cap
+    Size,
+    /// Oldest pending transaction reached the fixed timeout
+    Timeout,
+    /// Oldest pending transaction reached the arrival-rate-adjusted timeout
+    AdaptiveTimeout,
+    /// Sum of price × size reached the notional limit
+    Notional,
+    /// Sum of sizes reached the quantity limit
+    Quantity,
+    /// |bid size − ask size| reached the imbalance limit
+    Imbalance,
+}
+
+impl FlushReason {
+    /// Label for `telemetry::record_bundle_flushed`
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Size => "size",
+            Self::Timeout => "timeout",
+            Self::AdaptiveTimeout => "adaptive_timeout",
+            Self::Notional => "notional",
+            Self::Quantity => "quantity",
+            Self::Imbalance => "imbalance",
+        }
+    }
+}
+
+/// When to close the pending bundle
+pub trait FlushPolicy: Send {
+    /// Size cap, at most the builder's capacity; reaching it flushes with `Size`
+    fn max_size(&self) -> usize;
+
+    /// Maximum age of the pending bundle, in nanoseconds
+    fn timeout_ns(&self) -> u64;
+
+    /// Lower bound on `timeout_ns` over the policy's lifetime, so idle
+    /// consumers can wake in time to flush
+    fn min_timeout_ns(&self) -> u64 {
+        self.timeout_ns()
+    }
+
+    /// Reason reported when `timeout_ns` expires
+    fn timeout_reason(&self) -> FlushReason {
+        FlushReason::Timeout
+    }
+
+    /// Called after each transaction is added to (or netted into) the
+    /// bundle; `pending` is the bundle's contents. Return a reason to flush
+    /// now.
+    fn on_add(&mut self, pending: &[Transaction], now_ns: u64) -> Option<FlushReason>;
+}
+
+/// EWMA weight for inter-arrival samples (1/8)
+const ARRIVAL_EWMA_SHIFT: u32 = 3;
+
+/// Timeout that tracks how long a full bundle takes to accumulate
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AdaptiveTimeout {
+    pub min_ns: u64,
+    pub max_ns: u64,
+}
+
+/// Standard policy: size cap plus a fixed or adaptive timeout, and any of
+/// the optional value triggers
+///
+/// ```
+/// # use velox_engine::FlushTriggers;
+/// // Flush at 8 orders, $1M notional, or 50µs, whichever comes first
+/// let policy = FlushTriggers::new(8, 50_000).with_max_notional(1_000_000 * 10_000);
+/// ```
+#[derive(Debug, Clone)]
+pub struct FlushTriggers {
+    max_size: usize,
+    timeout_ns: u64,
+    adaptive: Option<AdaptiveTimeout>,
+    /// Raw price (4 implied decimals) × size
+    max_notional: Option<u128>,
+    max_quantity: Option<u64>,
+    max_imbalance: Option<u64>,
+    /// Smoothed inter-arrival time (adaptive timeout only)
+    arrival_ewma_ns: u64,
+    last_arrival_ns: u64,
+}
+
+impl FlushTriggers {
+    /// Flush at `max_size` transactions or after `timeout_ns`
+    pub fn new(max_size: usize, timeout_ns: u64) -> Self {
+        Self {
+            max_size,
+            timeout_ns,
+            adaptive: None,
+            max_notional: None,
+            max_quantity: None,
+            max_imbalance: None,
+            arrival_ewma_ns: 0,
+            last_arrival_ns: 0,
+        }
+    }
+
+    /// Flush once Σ price × size (raw fixed-point units) reaches `limit`
+    pub fn with_max_notional(mut self, limit: u128) -> Self {
+        self.max_notional = Some(limit);
+        self
+    }
+
+    /// Flush once Σ size reaches `limit`
+    pub fn with_max_quantity(mut self, limit: u64) -> Self {
+        self.max_quantity = Some(limit);
+        self
+    }
+
+    /// Flush once |Σ bid size − Σ ask size| reaches `limit`
+    pub fn with_max_imbalance(mut self, limit: u64) -> Self {
+        self.max_imbalance = Some(limit);
+        self
+    }
+
+    /// Replace the fixed timeout with the expected time to fill a bundle at
+    /// the observed arrival rate, clamped to `[min_ns, max_ns]`. Until the
+    /// first inter-arrival sample the timeout is `max_ns`.
+    pub fn with_adaptive_timeout(mut self, min_ns: u64, max_ns: u64) -> Self {
+        self.adaptive = Some(AdaptiveTimeout { min_ns, max_ns });
+        self
+    }
+}
+
+impl Default for FlushTriggers {
+    /// `BUNDLE_MAX` transactions or `BUNDLE_TIMEOUT_NS`
+    fn default() -> Self {
+        Self::new(BUNDLE_MAX, BUNDLE_TIMEOUT_NS)
+    }
+}
+
+impl FlushPolicy for FlushTriggers {
+    fn max_size(&self) -> usize {
+        self.max_size
+    }
+
+    fn timeout_ns(&self) -> u64 {
+        match self.adaptive {
+            None => self.timeout_ns,
+            Some(AdaptiveTimeout { min_ns, max_ns }) if self.arrival_ewma_ns == 0 => {
+                max_ns.max(min_ns)
+            }
+            Some(AdaptiveTimeout { min_ns, max_ns }) => self
+                .arrival_ewma_ns
+                .saturating_mul(self.max_size as u64)
+                .clamp(min_ns, max_ns.max(min_ns)),
+        }
+    }
+
+    fn min_timeout_ns(&self) -> u64 {
+        match self.adaptive {
+            None => self.timeout_ns,
+            Some(AdaptiveTimeout { min_ns, .. }) => min_ns,
+        }
+    }
+
+    fn timeout_reason(&self) -> FlushReason {
+        match self.adaptive {
+            None => FlushReason::Timeout,
+            Some(_) => FlushReason::AdaptiveTimeout,
+        }
+    }
+
+    fn on_add(&mut self, pending: &[Transaction], now_ns: u64) -> Option<FlushReason> {
+        if self.adaptive.is_some() {
+            if self.last_arrival_ns != 0 {
+                let sample = now_ns.saturating_sub(self.last_arrival_ns);
+                self.arrival_ewma_ns = if self.arrival_ewma_ns == 0 {
+                    sample
+                } else {
+                    let ewma = self.arrival_ewma_ns as i64;
+                    (ewma + ((sample as i64 - ewma) >> ARRIVAL_EWMA_SHIFT)) as u64
+                };
+            }
+            self.last_arrival_ns = now_ns;
+        }
+
+        if self.max_notional.is_none() && self.max_quantity.is_none() && self.max_imbalance.is_none() {
+            return None;
+        }
+
+        // Bundles hold at most BUNDLE_MAX entries: recomputing is cheaper
+        // than keeping running sums consistent with netting
+        let mut notional = 0u128;
+        let mut bid_qty = 0u64;
+        let mut ask_qty = 0u64;
+        for txn in pending {
+            notional += txn.price.unsigned_abs() as u128 * txn.size as u128;
+            if txn.is_bid() {
+                bid_qty += txn.size as u64;
+            } else {
+                ask_qty += txn.size as u64;
+            }
+        }
+
+        if self.max_notional.is_some_and(|limit| notional >= limit) {
+            return Some(FlushReason::Notional);
+        }
+        if self.max_quantity.is_some_and(|limit| bid_qty + ask_qty >= limit) {
+            return Some(FlushReason::Quantity);
+        }
+        if self.max_imbalance.is_some_and(|limit| bid_qty.abs_diff(ask_qty) >= limit) {
+            return Some(FlushReason::Imbalance);
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn order(price: i64, size: u32, side: u8) -> Transaction {
+        Transaction::new_unchecked(0, price, size, side, 0)
+    }
+
+    #[test]
+    fn test_default_has_no_value_triggers() {
+        let mut policy = FlushTriggers::default();
+        let pending = [order(1_000_000, 1_000_000, 0); 4];
+        assert_eq!(policy.on_add(&pending, 1), None);
+        assert_eq!(policy.max_size(), BUNDLE_MAX);
+        assert_eq!(policy.timeout_ns(), BUNDLE_TIMEOUT_NS);
+        assert_eq!(policy.timeout_reason(), FlushReason::Timeout);
+    }
+
+    #[test]
+    fn test_value_triggers() {
+        let mut notional = FlushTriggers::default().with_max_notional(2_000_000 * 100);
+        assert_eq!(notional.on_add(&[order(1_000_000, 100, 0)], 0), None);
+        assert_eq!(
+            notional.on_add(&[order(1_000_000, 100, 0), order(1_000_000, 100, 1)], 0),
+            Some(FlushReason::Notional)
+        );
+
+        let mut quantity = FlushTriggers::default().with_max_quantity(500);
+        assert_eq!(quantity.on_add(&[order(1, 300, 0)], 0), None);
+        assert_eq!(
+            quantity.on_add(&[order(1, 300, 0), order(1, 200, 1)], 0),
+            Some(FlushReason::Quantity)
+        );
+
+        let mut imbalance = FlushTriggers::default().with_max_imbalance(100);
+        // Balanced flow never trips it
+        assert_eq!(imbalance.on_add(&[order(1, 500, 0), order(1, 450, 1)], 0), None);
+        assert_eq!(
+            imbalance.on_add(&[order(1, 500, 0), order(1, 400, 1)], 0),
+            Some(FlushReason::Imbalance)
+        );
+    }
+
+    #[test]
+    fn test_adaptive_timeout_tracks_arrival_rate() {
+        let mut policy = FlushTriggers::new(10, BUNDLE_TIMEOUT_NS).with_adaptive_timeout(1_000, 1_000_000);
+        assert_eq!(policy.timeout_reason(), FlushReason::AdaptiveTimeout);
+        // No samples yet: upper bound
+        assert_eq!(policy.timeout_ns(), 1_000_000);
+
+        // Steady 2µs arrivals: ~10 × 2µs to fill a bundle
+        let mut now = 1;
+        for _ in 0..50 {
+            now += 2_000;
+            policy.on_add(&[], now);
+        }
+        assert_eq!(policy.timeout_ns(), 20_000);
+
+        // Slow flow: capped at max
+        for _ in 0..50 {
+            now += 500_000;
+            policy.on_add(&[], now);
+        }
+        assert_eq!(policy.timeout_ns(), 1_000_000);
+
+        // Burst: floored at min
+        for _ in 0..100 {
+            now += 10;
+            policy.on_add(&[], now);
+        }
+        assert_eq!(policy.timeout_ns(), 1_000);
+    }
+}
@@ -1,76 +1,202 @@
+use crate::ingress::model::{MarketGenerator, MarketModel};
 use crate::ring::RingBuffer;
-use crate::tsc::{rdtsc, spin_sleep_ns, tsc_to_ns};
+use crate::stage::Emit;
+use crate::tsc::{Clock, TscClock};
 use crate::types::Transaction;
-use rand::Rng;
+use rand::{RngCore, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use std::io;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+pub mod model;
+pub mod pcap;
+pub mod replay;
+pub mod tcp;
+pub mod udp;
+
+/// An external transaction feed, run by the pipeline's ingress thread in
+/// place of the synthetic generator (`PipelineBuilder::ingress_source`)
+pub trait IngressSource: Send {
+    /// Push transactions into `out` until `stop` is set. Drop-on-full is the
+    /// source's choice: `out.emit` hands back what the ring refused.
+    fn run<E: Emit<Transaction>>(&mut self, out: &mut E, stop: &AtomicBool) -> io::Result<()>;
+
+    /// Whether a transaction `out` refuses is retried rather than dropped.
+    /// The pipeline only counts refusals as ingress drops for sources that
+    /// drop.
+    fn retries_when_full(&self) -> bool {
+        false
+    }
+}
+
+/// Receives the outcome of every transaction a `SyntheticIngress` generates:
+/// counters, telemetry, recording. Called on the generating thread.
+pub trait IngressSink {
+    /// `txn` entered the ring `latency_ns` after it was generated
+    fn pushed(&mut self, txn: &Transaction, latency_ns: u64);
+
+    /// The ring was full and `txn` was dropped
+    fn dropped(&mut self, txn: &Transaction);
+}
+
+/// Synthetic ingress driver: draws transactions from a `MarketGenerator`,
+/// paces them on a `Clock` and pushes them into any `Emit` (a ring of any
+/// capacity, or the pipeline's ingress emitter), dropping on full.
+///
+/// Runs until the stop signal is set, or until `for_duration` has elapsed on
+/// its clock, whichever comes first. Transaction ids count up from 0, one per
+/// generated transaction, dropped or not.
+pub struct SyntheticIngress<C: Clock = TscClock> {
+    market: MarketGenerator,
+    clock: C,
+    duration_ns: Option<u64>,
+    next_id: u64,
+}
+
+impl SyntheticIngress {
+    /// Driver on the TSC clock (call `init_tsc` first), bounded only by the
+    /// stop signal
+    pub fn new(market: MarketGenerator) -> Self {
+        Self {
+            market,
+            clock: TscClock,
+            duration_ns: None,
+            next_id: 0,
+        }
+    }
+}
+
+impl<C: Clock> SyntheticIngress<C> {
+    /// Take timestamps and pacing from `clock` instead (a `ManualClock`
+    /// makes a seeded run reproducible and instant)
+    pub fn with_clock<D: Clock>(self, clock: D) -> SyntheticIngress<D> {
+        SyntheticIngress {
+            market: self.market,
+            clock,
+            duration_ns: self.duration_ns,
+            next_id: self.next_id,
+        }
+    }
+
+    /// Also stop once `duration` has elapsed on the driver's clock
+    pub fn for_duration(mut self, duration: Duration) -> Self {
+        self.duration_ns = Some(duration.as_nanos() as u64);
+        self
+    }
+
+    /// Generate into `out` until stopped, reporting every push and drop to
+    /// `sink`
+    pub fn run_with<E, S>(&mut self, out: &mut E, stop: &AtomicBool, sink: &mut S)
+    where
+        E: Emit<Transaction>,
+        S: IngressSink + ?Sized,
+    {
+        let start_ns = self.clock.now_ns();
+
+        while !stop.load(Ordering::Acquire) {
+            if let Some(duration_ns) = self.duration_ns {
+                if self.clock.now_ns() - start_ns >= duration_ns {
+                    break;
+                }
+            }
+
+            let generated_ns = self.clock.now_ns();
+            let txn = self.market.next_transaction(self.next_id, generated_ns);
+            self.next_id += 1;
+
+            // Drop on full (backpressure)
+            match out.emit(txn) {
+                Ok(()) => sink.pushed(&txn, self.clock.now_ns() - generated_ns),
+                Err(txn) => sink.dropped(&txn),
+            }
+
+            let delay_ns = self.market.next_gap_ns();
+            if delay_ns > 0 {
+                self.clock.sleep_ns(delay_ns);
+            }
+        }
+    }
+}
+
+impl<C: Clock + Send> IngressSource for SyntheticIngress<C> {
+    fn run<E: Emit<Transaction>>(&mut self, out: &mut E, stop: &AtomicBool) -> io::Result<()> {
+        self.run_with(out, stop, &mut SyntheticStats::default());
+        Ok(())
+    }
+}
 
 /// Synthetic transaction ingress with Poisson arrival process.
-/// Generates random transactions and pushes to ring buffer.
+/// Generates transactions from the default `MarketModel` and pushes to ring
+/// buffer.
 /// Drops transactions on buffer full (backpressure).
 ///
 /// # Parameters
 /// - `ring`: Ring buffer to push transactions into
 /// - `rate_hz`: Target transaction rate in transactions per second
 /// - `duration_secs`: How long to generate transactions (0 = infinite)
-pub fn synthetic_ingress(
-    ring: &RingBuffer<Transaction, 4096>,
+///
+/// Use `SyntheticIngress` directly to stop on a signal or observe each push.
+pub fn synthetic_ingress<const N: usize>(
+    ring: &RingBuffer<Transaction, N>,
     rate_hz: f64,
     duration_secs: u64,
 ) -> SyntheticStats {
-    let mut rng = rand::thread_rng();
-    let lambda = rate_hz;
-
-    let mut stats = SyntheticStats::default();
-    let start_tsc = rdtsc();
-    let duration_ns = duration_secs * 1_000_000_000;
-
-    loop {
-        // Check if duration exceeded
-        if duration_secs > 0 {
-            let elapsed_ns = tsc_to_ns(rdtsc() - start_tsc);
-            if elapsed_ns >= duration_ns {
-                break;
-            }
-        }
-
-        // Generate random transaction
-        let txn = Transaction::new_unchecked(
-            stats.generated,
-            rng.gen_range(900000..1100000), // $90-$110 in fixed-point (4 decimals)
-            rng.gen_range(1..1000),
-            rng.gen_range(0..2) as u8,
-            tsc_to_ns(rdtsc()),
-        );
-
-        stats.generated += 1;
-
-        // Push to ring buffer (drop on full)
-        match ring.push(txn) {
-            Ok(_) => stats.pushed += 1,
-            Err(_) => stats.dropped += 1,
-        }
+    let market = MarketGenerator::from_entropy(MarketModel::default(), rate_hz);
+    run_synthetic(ring, SyntheticIngress::new(market), duration_secs)
+}
 
-        // Poisson inter-arrival delay using exponential distribution
-        // Generate exponential random variable: -ln(U) / lambda
-        let u: f64 = rng.gen();
-        // Avoid u == 0.0 which would cause -inf
-        let u = u.max(f64::EPSILON);
-        let delay_ns = ((-u.ln()) / lambda * 1_000_000_000.0) as u64;
-        if delay_ns > 0 {
-            spin_sleep_ns(delay_ns);
-        }
-    }
+/// `synthetic_ingress` with a seeded generator and an injected clock.
+///
+/// The duration is measured on `clock` and every timestamp comes from it: with
+/// a `ManualClock` the run takes no real time and produces the same
+/// transactions, in the same order, on every call with the same seed.
+pub fn synthetic_ingress_seeded<const N: usize, C: Clock>(
+    ring: &RingBuffer<Transaction, N>,
+    rate_hz: f64,
+    duration_secs: u64,
+    seed: u64,
+    clock: &mut C,
+) -> SyntheticStats {
+    let market = MarketGenerator::new(MarketModel::default(), rate_hz, seed);
+    run_synthetic(ring, SyntheticIngress::new(market).with_clock(clock), duration_secs)
+}
 
+fn run_synthetic<const N: usize, C: Clock>(
+    mut ring: &RingBuffer<Transaction, N>,
+    ingress: SyntheticIngress<C>,
+    duration_secs: u64,
+) -> SyntheticStats {
+    let mut ingress = if duration_secs > 0 {
+        ingress.for_duration(Duration::from_secs(duration_secs))
+    } else {
+        ingress
+    };
+    let mut stats = SyntheticStats::default();
+    ingress.run_with(&mut ring, &AtomicBool::new(false), &mut stats);
     stats
 }
 
 /// Statistics from synthetic ingress
-#[derive(Debug, Default, Clone, Copy)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
 pub struct SyntheticStats {
     pub generated: u64,
     pub pushed: u64,
     pub dropped: u64,
 }
 
+impl IngressSink for SyntheticStats {
+    fn pushed(&mut self, _txn: &Transaction, _latency_ns: u64) {
+        self.generated += 1;
+        self.pushed += 1;
+    }
+
+    fn dropped(&mut self, _txn: &Transaction) {
+        self.generated += 1;
+        self.dropped += 1;
+    }
+}
+
 impl SyntheticStats {
     pub fn drop_rate(&self) -> f64 {
         if self.generated == 0 {
@@ -83,21 +209,44 @@ impl SyntheticStats {
 
 /// Generate a burst of transactions for testing.
 /// Returns number of transactions successfully pushed.
-pub fn generate_burst(
-    ring: &RingBuffer<Transaction, 4096>,
+pub fn generate_burst<const N: usize>(
+    ring: &RingBuffer<Transaction, N>,
+    count: usize,
+    base_price: i64,
+) -> usize {
+    push_burst(ring, count, base_price, ChaCha8Rng::from_entropy(), &mut TscClock)
+}
+
+/// `generate_burst` with a seeded generator and an injected clock: the same
+/// seed pushes the same transactions, stamped with `clock`'s current time.
+pub fn generate_burst_seeded<const N: usize, C: Clock>(
+    ring: &RingBuffer<Transaction, N>,
     count: usize,
     base_price: i64,
+    seed: u64,
+    clock: &mut C,
+) -> usize {
+    push_burst(ring, count, base_price, ChaCha8Rng::seed_from_u64(seed), clock)
+}
+
+/// Uniform prices in `base_price ± 5000`, sizes 1..100 and sides. Integer
+/// draws only, so a seed yields the same burst on every platform.
+fn push_burst<const N: usize, C: Clock>(
+    ring: &RingBuffer<Transaction, N>,
+    count: usize,
+    base_price: i64,
+    mut rng: ChaCha8Rng,
+    clock: &mut C,
 ) -> usize {
     let mut pushed = 0;
-    let mut rng = rand::thread_rng();
 
     for i in 0..count {
         let txn = Transaction::new_unchecked(
             i as u64,
-            base_price + rng.gen_range(-5000..5000),
-            rng.gen_range(1..100),
-            rng.gen_range(0..2) as u8,
-            tsc_to_ns(rdtsc()),
+            base_price - 5000 + (rng.next_u32() % 10_000) as i64,
+            1 + rng.next_u32() % 99,
+            (rng.next_u32() & 1) as u8,
+            clock.now_ns(),
         );
 
         if ring.push(txn).is_ok() {
@@ -111,7 +260,7 @@ pub fn generate_burst(
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::tsc::init_tsc;
+    use crate::tsc::{init_tsc, ManualClock};
 
     #[test]
     fn test_generate_burst() {
@@ -135,13 +284,123 @@ mod tests {
     }
 
     #[test]
-    #[ignore] // This test runs forever with duration=0, skip by default
     fn test_synthetic_ingress_duration() {
         init_tsc();
-        let _ring = RingBuffer::<Transaction, 4096>::new();
+        let ring = RingBuffer::<Transaction, 64>::new();
+
+        // Any ring capacity; ends on its own after the duration
+        let start = std::time::Instant::now();
+        let stats = synthetic_ingress(&ring, 10_000.0, 1);
+        assert!(start.elapsed() >= Duration::from_secs(1));
+        assert_eq!(stats.pushed, 64);
+        assert_eq!(stats.generated, stats.pushed + stats.dropped);
+    }
+
+    #[test]
+    fn test_synthetic_driver_stops_on_signal() {
+        init_tsc();
+        let ring = RingBuffer::<Transaction, 1024>::new();
+        let stop = AtomicBool::new(false);
+        let market = MarketGenerator::new(MarketModel::default(), 100_000.0, 1);
+        // No duration: only the signal ends the run
+        let mut ingress = SyntheticIngress::new(market);
+        let mut stats = SyntheticStats::default();
+
+        std::thread::scope(|scope| {
+            scope.spawn(|| {
+                std::thread::sleep(Duration::from_millis(20));
+                stop.store(true, Ordering::Release);
+            });
+            ingress.run_with(&mut &ring, &stop, &mut stats);
+        });
+
+        assert!(stats.generated > 0);
+        assert_eq!(stats.pushed, ring.len() as u64);
+        assert_eq!(stats.generated, stats.pushed + stats.dropped);
+    }
+
+    #[test]
+    fn test_synthetic_driver_reports_to_sink() {
+        /// Pushed ids and latencies, dropped ids
+        #[derive(Default)]
+        struct Log {
+            pushed: Vec<(u64, u64)>,
+            dropped: Vec<u64>,
+        }
+
+        impl IngressSink for Log {
+            fn pushed(&mut self, txn: &Transaction, latency_ns: u64) {
+                self.pushed.push((txn.id, latency_ns));
+            }
+
+            fn dropped(&mut self, txn: &Transaction) {
+                self.dropped.push(txn.id);
+            }
+        }
+
+        let ring = RingBuffer::<Transaction, 16>::new();
+        let market = MarketGenerator::new(MarketModel::default(), 1_000.0, 5);
+        let mut clock = ManualClock::new(0);
+        let mut ingress = SyntheticIngress::new(market)
+            .with_clock(&mut clock)
+            .for_duration(Duration::from_millis(100));
+        let mut log = Log::default();
+        ingress.run_with(&mut &ring, &AtomicBool::new(false), &mut log);
+
+        // ~100 generated in 100 simulated ms; the first 16 fit
+        assert!(clock.now_ns() >= 100_000_000);
+        assert_eq!(log.pushed, (0..16).map(|id| (id, 0)).collect::<Vec<_>>());
+        assert_eq!(log.dropped.first(), Some(&16));
+        assert!((80..120).contains(&(16 + log.dropped.len())), "{}", log.dropped.len());
+    }
+
+    #[test]
+    fn test_seeded_burst_is_reproducible() {
+        let burst = |seed| {
+            let ring = RingBuffer::<Transaction, 4096>::new();
+            let mut clock = ManualClock::new(42);
+            assert_eq!(generate_burst_seeded(&ring, 500, 1000000, seed, &mut clock), 500);
+            std::iter::from_fn(|| ring.pop()).collect::<Vec<_>>()
+        };
+
+        let first = burst(7);
+        assert_eq!(first, burst(7));
+        assert_ne!(first, burst(8));
+        assert!(first.iter().all(|txn| txn.ingress_ts_ns == 42));
+        assert!(first
+            .iter()
+            .all(|txn| (995_000..1_005_000).contains(&txn.price) && (1..100).contains(&txn.size)));
+    }
+
+    #[test]
+    fn test_burst_accepts_any_base_price() {
+        init_tsc();
+        let ring = RingBuffer::<Transaction, 4096>::new();
+
+        // Uniform draw off the base: no tick grid or model bounds to satisfy
+        assert_eq!(generate_burst(&ring, 1000, 5_001), 1000);
+        let prices: Vec<i64> = std::iter::from_fn(|| ring.pop()).map(|txn| txn.price).collect();
+        assert!(prices.iter().all(|price| (1..10_001).contains(price)));
+        assert!(prices.iter().any(|price| price % 100 != 0));
+    }
+
+    #[test]
+    fn test_seeded_synthetic_ingress_on_manual_clock() {
+        let run = || {
+            let ring = RingBuffer::<Transaction, 4096>::new();
+            let mut clock = ManualClock::new(0);
+            // One simulated second at 1000/s: no real waiting
+            let stats = synthetic_ingress_seeded(&ring, 1000.0, 1, 11, &mut clock);
+            assert!(clock.now_ns() >= 1_000_000_000);
+            (stats, std::iter::from_fn(|| ring.pop()).collect::<Vec<_>>())
+        };
 
-        // This test is ignored because duration=0 means infinite loop
-        // To test properly, use a thread with timeout
+        let (stats, txns) = run();
+        assert!((900..1100).contains(&stats.generated), "{:?}", stats);
+        assert_eq!(stats.pushed, stats.generated);
+        // Timestamps advance with the simulated gaps
+        assert!(txns.windows(2).all(|w| w[0].ingress_ts_ns <= w[1].ingress_ts_ns));
+        assert_eq!(run(), (stats, txns));
     }
 
     #[test]
@@ -0,0 +1,592 @@
+/// Market model for synthetic ingress
+///
+/// Generates order flow with the shape of a real book instead of uniform
+/// noise:
+/// - the mid price follows a Gaussian random walk, reflected inside
+///   `[min_price, max_price]`
+/// - passive orders rest an exponentially distributed number of ticks behind
+///   the touch of their side; a share of orders is priced at the opposite
+///   touch so it trades
+/// - sizes follow a Pareto (power-law) distribution
+/// - buys and sells are skewed by `buy_ratio`
+/// - arrivals are Poisson, ON/OFF bursts or a self-exciting Hawkes process,
+///   each scaled so the long-run mean rate is the requested one
+///
+/// `MarketGenerator::new` takes a seed: the same model, rate and seed always
+/// produce the same orders and gaps. The stream is pinned to ChaCha8 and
+/// libm's software float functions, so it does not change with the `rand`
+/// version or the platform's math library.
+use crate::errors::ConfigError;
+use crate::types::Transaction;
+use rand::{RngCore, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use serde::Deserialize;
+
+/// Order flow parameters. Prices are fixed-point with 4 decimals.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MarketModel {
+    /// Mid price at the start of the run
+    pub initial_mid: i64,
+    /// Price grid; every generated price is a multiple of it
+    pub tick_size: i64,
+    /// Range the mid is kept in and prices are clamped to (keep it inside
+    /// the book's price range)
+    pub min_price: i64,
+    pub max_price: i64,
+    /// Standard deviation of the mid's move per order, in ticks
+    pub volatility_ticks: f64,
+    /// Best ask minus best bid, in ticks
+    pub spread_ticks: i64,
+    /// Mean distance of passive orders behind their touch, in ticks
+    pub depth_ticks: f64,
+    /// Share of orders priced at the opposite touch (0..=1)
+    pub aggressive_ratio: f64,
+    /// Share of orders that are buys (0..=1; 0.5 is balanced)
+    pub buy_ratio: f64,
+    /// Sizes: P(size >= s) = (min_size / s)^size_alpha, capped at max_size
+    pub min_size: u32,
+    pub max_size: u32,
+    pub size_alpha: f64,
+    pub arrivals: Arrivals,
+}
+
+impl Default for MarketModel {
+    /// Cent ticks around $100, inside the default book's $90-$110 range
+    fn default() -> Self {
+        Self {
+            initial_mid: 1_000_000,
+            tick_size: 100,
+            min_price: 900_000,
+            max_price: 1_099_900,
+            volatility_ticks: 0.3,
+            spread_ticks: 2,
+            depth_ticks: 5.0,
+            aggressive_ratio: 0.1,
+            buy_ratio: 0.5,
+            min_size: 1,
+            max_size: 1000,
+            size_alpha: 1.5,
+            arrivals: Arrivals::Poisson,
+        }
+    }
+}
+
+impl MarketModel {
+    /// Check every parameter; field names are those of the `[ingress.model]`
+    /// config section
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        fn invalid(field: &'static str, reason: String) -> Result<(), ConfigError> {
+            Err(ConfigError::Invalid { field, reason })
+        }
+        fn ratio(value: f64) -> bool {
+            (0.0..=1.0).contains(&value)
+        }
+
+        if self.tick_size <= 0 {
+            return invalid(
+                "ingress.model.tick_size",
+                format!("{} (must be positive)", self.tick_size),
+            );
+        }
+        let range = self.max_price.checked_sub(self.min_price);
+        if self.min_price < self.tick_size || range.is_none_or(|range| range < self.tick_size) {
+            return invalid(
+                "ingress.model.min_price",
+                format!(
+                    "{}..={} (need tick_size <= min_price and at least one tick of range)",
+                    self.min_price, self.max_price
+                ),
+            );
+        }
+        if !(self.min_price..=self.max_price).contains(&self.initial_mid) {
+            return invalid(
+                "ingress.model.initial_mid",
+                format!(
+                    "{} (must be in {}..={})",
+                    self.initial_mid, self.min_price, self.max_price
+                ),
+            );
+        }
+        for (field, value) in [
+            ("ingress.model.volatility_ticks", self.volatility_ticks),
+            ("ingress.model.depth_ticks", self.depth_ticks),
+        ] {
+            if !(value.is_finite() && value >= 0.0) {
+                return invalid(field, format!("{} (must be zero or positive)", value));
+            }
+        }
+        if self.spread_ticks < 1 {
+            return invalid(
+                "ingress.model.spread_ticks",
+                format!("{} (must be at least 1)", self.spread_ticks),
+            );
+        }
+        for (field, value) in [
+            ("ingress.model.aggressive_ratio", self.aggressive_ratio),
+            ("ingress.model.buy_ratio", self.buy_ratio),
+        ] {
+            if !ratio(value) {
+                return invalid(field, format!("{} (must be in 0..=1)", value));
+            }
+        }
+        if self.min_size == 0 || self.min_size > self.max_size {
+            return invalid(
+                "ingress.model.min_size",
+                format!(
+                    "min_size = {}, max_size = {} (need 1 <= min_size <= max_size)",
+                    self.min_size, self.max_size
+                ),
+            );
+        }
+        if !(self.size_alpha.is_finite() && self.size_alpha > 0.0) {
+            return invalid(
+                "ingress.model.size_alpha",
+                format!("{} (must be positive)", self.size_alpha),
+            );
+        }
+        match self.arrivals {
+            Arrivals::Poisson => {}
+            Arrivals::OnOff {
+                on_mean_ns,
+                off_mean_ns,
+            } => {
+                if on_mean_ns == 0 {
+                    return invalid(
+                        "ingress.model.arrivals.on_mean_ns",
+                        format!("{} (must be at least 1)", on_mean_ns),
+                    );
+                }
+                if off_mean_ns == 0 {
+                    return invalid(
+                        "ingress.model.arrivals.off_mean_ns",
+                        format!("{} (must be at least 1)", off_mean_ns),
+                    );
+                }
+            }
+            Arrivals::Hawkes {
+                branching,
+                decay_hz,
+            } => {
+                if !(0.0..1.0).contains(&branching) {
+                    return invalid(
+                        "ingress.model.arrivals.branching",
+                        format!("{} (must be in 0..1)", branching),
+                    );
+                }
+                if !(decay_hz.is_finite() && decay_hz > 0.0) {
+                    return invalid(
+                        "ingress.model.arrivals.decay_hz",
+                        format!("{} (must be positive)", decay_hz),
+                    );
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Arrival process. Every variant keeps the long-run mean rate at the
+/// generator's `rate_hz`; they differ in how arrivals cluster.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case", deny_unknown_fields)]
+pub enum Arrivals {
+    /// Independent arrivals: exponential gaps
+    Poisson,
+    /// Alternating active and silent periods with exponential lengths;
+    /// during active periods the rate is raised to keep the mean
+    OnOff { on_mean_ns: u64, off_mean_ns: u64 },
+    /// Self-exciting: each arrival raises the intensity, which decays back
+    /// at `decay_hz`. `branching` (0..1) is the mean number of arrivals one
+    /// arrival triggers; the baseline rate is lowered to keep the mean.
+    Hawkes { branching: f64, decay_hz: f64 },
+}
+
+/// Arrival process state, rates in Hz
+enum ArrivalState {
+    Poisson {
+        rate_hz: f64,
+    },
+    OnOff {
+        on_rate_hz: f64,
+        on_mean_s: f64,
+        off_mean_s: f64,
+        /// Time left in the current active period
+        remaining_on_s: f64,
+    },
+    Hawkes {
+        baseline_hz: f64,
+        /// Intensity added by each arrival
+        jump_hz: f64,
+        decay_hz: f64,
+        /// Intensity above the baseline, as of the last arrival
+        excess_hz: f64,
+    },
+}
+
+/// Seedable order flow generator for a `MarketModel`
+pub struct MarketGenerator {
+    model: MarketModel,
+    rng: ChaCha8Rng,
+    /// Mid price in ticks
+    mid: f64,
+    /// Mid bounds in ticks
+    lowest: f64,
+    highest: f64,
+    arrivals: ArrivalState,
+}
+
+impl MarketGenerator {
+    /// Generator for `model` at a mean of `rate_hz` orders per second, fully
+    /// determined by `seed`.
+    ///
+    /// # Panics
+    /// Panics if the model is invalid (see `MarketModel::validate`) or
+    /// `rate_hz` is not finite and positive.
+    pub fn new(model: MarketModel, rate_hz: f64, seed: u64) -> Self {
+        Self::with_rng(model, rate_hz, ChaCha8Rng::seed_from_u64(seed))
+    }
+
+    /// Generator seeded from the operating system
+    ///
+    /// # Panics
+    /// As `new`.
+    pub fn from_entropy(model: MarketModel, rate_hz: f64) -> Self {
+        Self::with_rng(model, rate_hz, ChaCha8Rng::from_entropy())
+    }
+
+    fn with_rng(model: MarketModel, rate_hz: f64, mut rng: ChaCha8Rng) -> Self {
+        if let Err(err) = model.validate() {
+            panic!("{}", err);
+        }
+        assert!(
+            rate_hz.is_finite() && rate_hz > 0.0,
+            "ingress rate must be positive"
+        );
+
+        let tick = model.tick_size as f64;
+        let arrivals = match model.arrivals {
+            Arrivals::Poisson => ArrivalState::Poisson { rate_hz },
+            Arrivals::OnOff {
+                on_mean_ns,
+                off_mean_ns,
+            } => {
+                let on_mean_s = on_mean_ns as f64 / 1e9;
+                let off_mean_s = off_mean_ns as f64 / 1e9;
+                ArrivalState::OnOff {
+                    on_rate_hz: rate_hz * (on_mean_s + off_mean_s) / on_mean_s,
+                    on_mean_s,
+                    off_mean_s,
+                    remaining_on_s: exponential(&mut rng, on_mean_s),
+                }
+            }
+            Arrivals::Hawkes {
+                branching,
+                decay_hz,
+            } => ArrivalState::Hawkes {
+                baseline_hz: rate_hz * (1.0 - branching),
+                jump_hz: branching * decay_hz,
+                decay_hz,
+                excess_hz: 0.0,
+            },
+        };
+
+        Self {
+            model,
+            rng,
+            mid: model.initial_mid as f64 / tick,
+            lowest: (model.min_price as f64 / tick).ceil(),
+            highest: (model.max_price as f64 / tick).floor(),
+            arrivals,
+        }
+    }
+
+    pub fn model(&self) -> &MarketModel {
+        &self.model
+    }
+
+    /// Current mid price, rounded to the tick grid
+    pub fn mid_price(&self) -> i64 {
+        self.mid.round() as i64 * self.model.tick_size
+    }
+
+    /// Move the mid and draw the next order
+    pub fn next_transaction(&mut self, id: u64, ingress_ts_ns: u64) -> Transaction {
+        let model = self.model;
+
+        // Random walk, reflected at the bounds
+        self.mid += model.volatility_ticks * gaussian(&mut self.rng);
+        if self.mid < self.lowest {
+            self.mid = (2.0 * self.lowest - self.mid).min(self.highest);
+        } else if self.mid > self.highest {
+            self.mid = (2.0 * self.highest - self.mid).max(self.lowest);
+        }
+
+        let best_bid = (self.mid - model.spread_ticks as f64 / 2.0).floor();
+        let best_ask = best_bid + model.spread_ticks as f64;
+        let buy = uniform(&mut self.rng) < model.buy_ratio;
+        let ticks = if uniform(&mut self.rng) < model.aggressive_ratio {
+            if buy {
+                best_ask
+            } else {
+                best_bid
+            }
+        } else {
+            let depth = exponential(&mut self.rng, model.depth_ticks).floor();
+            if buy {
+                best_bid - depth
+            } else {
+                best_ask + depth
+            }
+        };
+        let price = ticks.clamp(self.lowest, self.highest) as i64 * model.tick_size;
+
+        // Pareto by inversion
+        let u = 1.0 - uniform(&mut self.rng);
+        let size = (model.min_size as f64 * libm::pow(u, -1.0 / model.size_alpha))
+            .min(model.max_size as f64) as u32;
+
+        Transaction::new_unchecked(id, price, size, if buy { 0 } else { 1 }, ingress_ts_ns)
+    }
+
+    /// Time until the next arrival
+    pub fn next_gap_ns(&mut self) -> u64 {
+        let rng = &mut self.rng;
+        let gap_s = match &mut self.arrivals {
+            ArrivalState::Poisson { rate_hz } => exponential(rng, 1.0 / *rate_hz),
+            ArrivalState::OnOff {
+                on_rate_hz,
+                on_mean_s,
+                off_mean_s,
+                remaining_on_s,
+            } => {
+                let mut elapsed = 0.0;
+                loop {
+                    // Exponential gaps are memoryless: redraw after a pause
+                    let gap = exponential(rng, 1.0 / *on_rate_hz);
+                    if gap <= *remaining_on_s {
+                        *remaining_on_s -= gap;
+                        break elapsed + gap;
+                    }
+                    elapsed += *remaining_on_s + exponential(rng, *off_mean_s);
+                    *remaining_on_s = exponential(rng, *on_mean_s);
+                }
+            }
+            ArrivalState::Hawkes {
+                baseline_hz,
+                jump_hz,
+                decay_hz,
+                excess_hz,
+            } => {
+                // Ogata thinning: the intensity only decays between arrivals,
+                // so its current value bounds it until the next one
+                let mut elapsed = 0.0;
+                loop {
+                    let bound = *baseline_hz + *excess_hz;
+                    let gap = exponential(rng, 1.0 / bound);
+                    elapsed += gap;
+                    *excess_hz *= libm::exp(-*decay_hz * gap);
+                    if uniform(rng) * bound <= *baseline_hz + *excess_hz {
+                        *excess_hz += *jump_hz;
+                        break elapsed;
+                    }
+                }
+            }
+        };
+        (gap_s * 1e9) as u64
+    }
+}
+
+/// Uniform variate in [0, 1) from the top 53 bits of one draw
+fn uniform(rng: &mut ChaCha8Rng) -> f64 {
+    (rng.next_u64() >> 11) as f64 * (1.0 / (1u64 << 53) as f64)
+}
+
+/// Exponential variate with the given mean
+fn exponential(rng: &mut ChaCha8Rng, mean: f64) -> f64 {
+    // 1 - [0, 1) avoids ln(0)
+    -libm::log(1.0 - uniform(rng)) * mean
+}
+
+/// Standard normal variate (Box-Muller)
+fn gaussian(rng: &mut ChaCha8Rng) -> f64 {
+    let u = 1.0 - uniform(rng);
+    let v = uniform(rng);
+    (-2.0 * libm::log(u)).sqrt() * libm::cos(std::f64::consts::TAU * v)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RATE_HZ: f64 = 100_000.0;
+
+    fn orders(model: MarketModel, seed: u64, count: u64) -> Vec<Transaction> {
+        let mut generator = MarketGenerator::new(model, RATE_HZ, seed);
+        (0..count)
+            .map(|id| generator.next_transaction(id, 0))
+            .collect()
+    }
+
+    /// Mean and coefficient of variation of `count` gaps
+    fn gap_stats(arrivals: Arrivals, count: usize) -> (f64, f64) {
+        let model = MarketModel {
+            arrivals,
+            ..MarketModel::default()
+        };
+        let mut generator = MarketGenerator::new(model, RATE_HZ, 7);
+        let gaps: Vec<f64> = (0..count).map(|_| generator.next_gap_ns() as f64).collect();
+        let mean = gaps.iter().sum::<f64>() / count as f64;
+        let variance = gaps.iter().map(|g| (g - mean).powi(2)).sum::<f64>() / count as f64;
+        (mean, variance.sqrt() / mean)
+    }
+
+    #[test]
+    fn test_model_seed_reproducible() {
+        let a = orders(MarketModel::default(), 42, 1000);
+        assert_eq!(a, orders(MarketModel::default(), 42, 1000));
+        assert_ne!(a, orders(MarketModel::default(), 43, 1000));
+
+        let gaps = |seed| {
+            let mut generator = MarketGenerator::new(MarketModel::default(), RATE_HZ, seed);
+            (0..100)
+                .map(|_| generator.next_gap_ns())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(gaps(42), gaps(42));
+    }
+
+    #[test]
+    fn test_model_prices_on_grid_and_in_range() {
+        // High volatility in a narrow range exercises the reflection
+        let model = MarketModel {
+            min_price: 990_000,
+            max_price: 1_010_000,
+            volatility_ticks: 20.0,
+            ..MarketModel::default()
+        };
+        let mut generator = MarketGenerator::new(model, RATE_HZ, 1);
+        for id in 0..20_000 {
+            let txn = generator.next_transaction(id, 0);
+            assert_eq!(txn.price % 100, 0);
+            assert!((990_000..=1_010_000).contains(&txn.price), "{}", txn.price);
+            assert!((990_000..=1_010_000).contains(&generator.mid_price()));
+        }
+    }
+
+    #[test]
+    fn test_model_book_shape() {
+        // Passive orders only: bids below asks around the mid
+        let model = MarketModel {
+            volatility_ticks: 0.0,
+            aggressive_ratio: 0.0,
+            ..MarketModel::default()
+        };
+        let txns = orders(model, 3, 10_000);
+        let best_bid = txns.iter().filter(|t| t.side == 0).map(|t| t.price).max();
+        let best_ask = txns.iter().filter(|t| t.side == 1).map(|t| t.price).min();
+        assert_eq!((best_bid, best_ask), (Some(999_900), Some(1_000_100)));
+
+        // Aggressive orders cross the spread
+        let model = MarketModel {
+            volatility_ticks: 0.0,
+            aggressive_ratio: 1.0,
+            ..MarketModel::default()
+        };
+        assert!(orders(model, 3, 100)
+            .iter()
+            .all(|t| t.price == if t.side == 0 { 1_000_100 } else { 999_900 }));
+    }
+
+    #[test]
+    fn test_model_side_skew() {
+        let model = MarketModel {
+            buy_ratio: 0.8,
+            ..MarketModel::default()
+        };
+        let buys = orders(model, 5, 10_000)
+            .iter()
+            .filter(|t| t.side == 0)
+            .count();
+        assert!((7_700..8_300).contains(&buys), "{}", buys);
+    }
+
+    #[test]
+    fn test_model_power_law_sizes() {
+        let txns = orders(MarketModel::default(), 9, 20_000);
+        assert!(txns.iter().all(|t| (1..=1000).contains(&t.size)));
+
+        // P(size >= 10) = 10^-1.5 ≈ 3.2%
+        let large = txns.iter().filter(|t| t.size >= 10).count() as f64 / 20_000.0;
+        assert!((0.025..0.04).contains(&large), "{}", large);
+        assert!(txns.iter().filter(|t| t.size == 1).count() > 10_000);
+    }
+
+    #[test]
+    fn test_arrivals_keep_mean_rate() {
+        let processes = [
+            Arrivals::Poisson,
+            Arrivals::OnOff {
+                on_mean_ns: 100_000,
+                off_mean_ns: 400_000,
+            },
+            Arrivals::Hawkes {
+                branching: 0.8,
+                decay_hz: 200_000.0,
+            },
+        ];
+        let stats: Vec<_> = processes.iter().map(|&p| gap_stats(p, 200_000)).collect();
+
+        for (mean, _) in &stats {
+            // 10µs mean gap
+            assert!((9_000.0..11_000.0).contains(mean), "{}", mean);
+        }
+        // Poisson gaps have CV 1; bursty processes cluster
+        assert!((0.95..1.05).contains(&stats[0].1), "{}", stats[0].1);
+        assert!(stats[1].1 > 2.0, "{}", stats[1].1);
+        assert!(stats[2].1 > 1.5, "{}", stats[2].1);
+    }
+
+    #[test]
+    fn test_model_validate() {
+        assert!(MarketModel::default().validate().is_ok());
+
+        let field = |model: MarketModel| match model.validate() {
+            Err(ConfigError::Invalid { field, .. }) => field,
+            other => panic!("expected Invalid, got {:?}", other),
+        };
+        let base = MarketModel::default();
+        assert_eq!(
+            field(MarketModel {
+                buy_ratio: 1.5,
+                ..base
+            }),
+            "ingress.model.buy_ratio"
+        );
+        assert_eq!(
+            field(MarketModel {
+                initial_mid: 2_000_000,
+                ..base
+            }),
+            "ingress.model.initial_mid"
+        );
+        assert_eq!(
+            field(MarketModel {
+                arrivals: Arrivals::Hawkes {
+                    branching: 1.0,
+                    decay_hz: 1.0
+                },
+                ..base
+            }),
+            "ingress.model.arrivals.branching"
+        );
+        // max_price - min_price would overflow
+        assert_eq!(
+            field(MarketModel {
+                max_price: i64::MIN,
+                ..base
+            }),
+            "ingress.model.min_price"
+        );
+    }
+}
@@ -0,0 +1,848 @@
+/// Pcap ingress: runs the pipeline against UDP feed captures
+///
+/// Reads classic pcap (microsecond or nanosecond, either byte order) and
+/// pcapng (section, interface description and enhanced packet blocks; other
+/// blocks are skipped). Packets are unwrapped from Ethernet (with VLAN tags),
+/// Linux cooked or raw IPv4 link layers; unfragmented IPv4 UDP datagrams
+/// sent to the configured port and/or group go through `FeedDecoder`, the
+/// same framing and sequencing as the live UDP handler.
+///
+/// Replay is paced on packet timestamps (`ReplaySpeed`) and waits for room
+/// in the ring like `ReplayIngress`, so a capture always yields the same
+/// transactions. Each transaction's `ingress_ts_ns` is the local time it was
+/// pushed, so pipeline latency is measured as for a live feed.
+use crate::capture::read_full;
+use crate::errors::PcapError;
+use crate::ingress::replay::{BlockingEmit, Pacer, ReplaySpeed};
+use crate::ingress::udp::{FeedDecoder, UdpIngressStats};
+use crate::ingress::IngressSource;
+use crate::stage::Emit;
+use crate::tsc::{rdtsc, tsc_to_ns};
+use crate::types::Transaction;
+use std::fs::File;
+use std::io::{self, BufReader, Read};
+use std::net::Ipv4Addr;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+pub const LINKTYPE_ETHERNET: u32 = 1;
+pub const LINKTYPE_RAW: u32 = 101;
+pub const LINKTYPE_LINUX_SLL: u32 = 113;
+pub const LINKTYPE_IPV4: u32 = 228;
+
+/// Largest packet or block accepted (guards against corrupt lengths)
+const MAX_RECORD_LEN: u32 = 16 * 1024 * 1024;
+
+const PCAP_MICROS: u32 = 0xa1b2_c3d4;
+const PCAP_NANOS: u32 = 0xa1b2_3c4d;
+const PCAPNG_SHB: u32 = 0x0a0d_0d0a;
+const PCAPNG_BYTE_ORDER: u32 = 0x1a2b_3c4d;
+const PCAPNG_IDB: u32 = 1;
+const PCAPNG_EPB: u32 = 6;
+/// Interface option holding the timestamp resolution
+const IF_TSRESOL: u16 = 9;
+
+fn u16_at(bytes: &[u8], at: usize, big: bool) -> u16 {
+    let raw = [bytes[at], bytes[at + 1]];
+    if big {
+        u16::from_be_bytes(raw)
+    } else {
+        u16::from_le_bytes(raw)
+    }
+}
+
+fn u32_at(bytes: &[u8], at: usize, big: bool) -> u32 {
+    let raw = bytes[at..at + 4].try_into().unwrap();
+    if big {
+        u32::from_be_bytes(raw)
+    } else {
+        u32::from_le_bytes(raw)
+    }
+}
+
+/// One captured packet
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Packet<'a> {
+    /// Capture time in nanoseconds since the Unix epoch
+    pub ts_ns: u64,
+    pub linktype: u32,
+    /// Captured bytes (possibly shorter than the packet on the wire)
+    pub data: &'a [u8],
+}
+
+/// pcapng interface: link type and timestamp units
+#[derive(Debug, Clone, Copy)]
+struct Interface {
+    linktype: u32,
+    /// `if_tsresol`: high bit clear = 10^-n seconds, set = 2^-n seconds
+    tsresol: u8,
+}
+
+impl Interface {
+    fn ts_ns(&self, ticks: u64) -> u64 {
+        let exp = u32::from(self.tsresol & 0x7f);
+        if self.tsresol & 0x80 == 0 {
+            if exp <= 9 {
+                ticks.saturating_mul(10u64.pow(9 - exp))
+            } else {
+                ticks / 10u64.saturating_pow(exp - 9)
+            }
+        } else {
+            ((u128::from(ticks) * 1_000_000_000) >> exp.min(127)) as u64
+        }
+    }
+}
+
+enum Format {
+    Classic {
+        big: bool,
+        nanos: bool,
+        linktype: u32,
+    },
+    Ng {
+        big: bool,
+        interfaces: Vec<Interface>,
+    },
+}
+
+/// Packet reader for pcap and pcapng
+pub struct PcapReader<R: Read> {
+    inner: R,
+    format: Format,
+    /// Current record or block body
+    buf: Vec<u8>,
+    /// File offset of the next record or block
+    offset: u64,
+}
+
+impl<R: Read> PcapReader<R> {
+    /// Detect the format from the file's magic number and read its header
+    pub fn new(mut inner: R) -> Result<Self, PcapError> {
+        let mut magic = [0u8; 4];
+        if read_full(&mut inner, &mut magic)? < 4 {
+            return Err(PcapError::Truncated { offset: 0 });
+        }
+        let magic_le = u32::from_le_bytes(magic);
+
+        let mut reader = Self {
+            inner,
+            format: Format::Ng {
+                big: false,
+                interfaces: Vec::new(),
+            },
+            buf: Vec::new(),
+            offset: 0,
+        };
+
+        if magic_le == PCAPNG_SHB {
+            reader.read_section_header()?;
+            return Ok(reader);
+        }
+
+        let (big, nanos) = match magic_le {
+            PCAP_MICROS => (false, false),
+            PCAP_NANOS => (false, true),
+            _ if magic_le.swap_bytes() == PCAP_MICROS => (true, false),
+            _ if magic_le.swap_bytes() == PCAP_NANOS => (true, true),
+            _ => return Err(PcapError::BadMagic(magic_le)),
+        };
+        // Version, zone, sigfigs, snaplen, link type
+        let mut header = [0u8; 20];
+        if read_full(&mut reader.inner, &mut header)? < header.len() {
+            return Err(PcapError::Truncated { offset: 0 });
+        }
+        reader.format = Format::Classic {
+            big,
+            nanos,
+            // Upper bits carry FCS information
+            linktype: u32_at(&header, 16, big) & 0xffff,
+        };
+        reader.offset = 24;
+        Ok(reader)
+    }
+
+    /// Read a pcapng section header block whose type was just read; resets
+    /// the interface list
+    fn read_section_header(&mut self) -> Result<(), PcapError> {
+        let offset = self.offset;
+        let mut head = [0u8; 8];
+        if read_full(&mut self.inner, &mut head)? < head.len() {
+            return Err(PcapError::Truncated { offset });
+        }
+        let big = match u32::from_le_bytes(head[4..8].try_into().unwrap()) {
+            PCAPNG_BYTE_ORDER => false,
+            other if other.swap_bytes() == PCAPNG_BYTE_ORDER => true,
+            other => return Err(PcapError::BadMagic(other)),
+        };
+        let length = u32_at(&head, 0, big);
+        // Type, length, byte order, version, section length, trailing length
+        if !(28..=MAX_RECORD_LEN).contains(&length) || !length.is_multiple_of(4) {
+            return Err(PcapError::BadLength { offset, length });
+        }
+        self.read_body(length as usize - 12, offset)?;
+        self.format = Format::Ng {
+            big,
+            interfaces: Vec::new(),
+        };
+        self.offset += u64::from(length);
+        Ok(())
+    }
+
+    /// Read `len` bytes into `buf`
+    fn read_body(&mut self, len: usize, offset: u64) -> Result<(), PcapError> {
+        self.buf.resize(len, 0);
+        if read_full(&mut self.inner, &mut self.buf)? < len {
+            return Err(PcapError::Truncated { offset });
+        }
+        Ok(())
+    }
+
+    /// Next packet, or None at the end of the file
+    pub fn next_packet(&mut self) -> Result<Option<Packet<'_>>, PcapError> {
+        let (ts_ns, linktype, start, end) = match self.format {
+            Format::Classic {
+                big,
+                nanos,
+                linktype,
+            } => {
+                let offset = self.offset;
+                let mut head = [0u8; 16];
+                match read_full(&mut self.inner, &mut head)? {
+                    0 => return Ok(None),
+                    16 => {}
+                    _ => return Err(PcapError::Truncated { offset }),
+                }
+                let captured = u32_at(&head, 8, big);
+                if captured > MAX_RECORD_LEN {
+                    return Err(PcapError::BadLength {
+                        offset,
+                        length: captured,
+                    });
+                }
+                self.read_body(captured as usize, offset)?;
+                self.offset += 16 + u64::from(captured);
+
+                let secs = u64::from(u32_at(&head, 0, big));
+                let frac = u64::from(u32_at(&head, 4, big));
+                let ts_ns = secs * 1_000_000_000 + if nanos { frac } else { frac * 1_000 };
+                (ts_ns, linktype, 0, captured as usize)
+            }
+            Format::Ng { .. } => match self.next_enhanced_packet()? {
+                Some(packet) => packet,
+                None => return Ok(None),
+            },
+        };
+
+        Ok(Some(Packet {
+            ts_ns,
+            linktype,
+            data: &self.buf[start..end],
+        }))
+    }
+
+    /// Skip to the next enhanced packet block; returns its timestamp, link
+    /// type and data range in `buf`
+    fn next_enhanced_packet(&mut self) -> Result<Option<(u64, u32, usize, usize)>, PcapError> {
+        loop {
+            let offset = self.offset;
+            let mut head = [0u8; 4];
+            match read_full(&mut self.inner, &mut head)? {
+                0 => return Ok(None),
+                4 => {}
+                _ => return Err(PcapError::Truncated { offset }),
+            }
+            let Format::Ng { big, .. } = self.format else {
+                unreachable!("pcapng block in a classic pcap");
+            };
+            let block_type = u32_at(&head, 0, big);
+            if block_type == PCAPNG_SHB {
+                // New section, possibly in the other byte order
+                self.read_section_header()?;
+                continue;
+            }
+
+            if read_full(&mut self.inner, &mut head)? < 4 {
+                return Err(PcapError::Truncated { offset });
+            }
+            let length = u32_at(&head, 0, big);
+            if !(12..=MAX_RECORD_LEN).contains(&length) || !length.is_multiple_of(4) {
+                return Err(PcapError::BadLength { offset, length });
+            }
+            // Body plus the trailing copy of the length
+            self.read_body(length as usize - 8, offset)?;
+            self.offset += u64::from(length);
+            let body_len = length as usize - 12;
+
+            let Format::Ng { interfaces, .. } = &mut self.format else {
+                unreachable!();
+            };
+            match block_type {
+                PCAPNG_IDB if body_len >= 8 => {
+                    let mut interface = Interface {
+                        linktype: u32::from(u16_at(&self.buf, 0, big)),
+                        tsresol: 6,
+                    };
+                    // Options: code, length, value padded to 4 bytes
+                    let mut at = 8;
+                    while at + 4 <= body_len {
+                        let code = u16_at(&self.buf, at, big);
+                        let len = u16_at(&self.buf, at + 2, big) as usize;
+                        if code == 0 || at + 4 + len > body_len {
+                            break;
+                        }
+                        if code == IF_TSRESOL && len >= 1 {
+                            interface.tsresol = self.buf[at + 4];
+                        }
+                        at += 4 + len.div_ceil(4) * 4;
+                    }
+                    interfaces.push(interface);
+                }
+                PCAPNG_EPB if body_len >= 20 => {
+                    let id = u32_at(&self.buf, 0, big);
+                    let interface =
+                        *interfaces
+                            .get(id as usize)
+                            .ok_or(PcapError::UnknownInterface {
+                                offset,
+                                interface: id,
+                            })?;
+                    let captured = u32_at(&self.buf, 12, big);
+                    if captured as usize > body_len - 20 {
+                        return Err(PcapError::BadLength { offset, length });
+                    }
+                    let ticks = u64::from(u32_at(&self.buf, 4, big)) << 32
+                        | u64::from(u32_at(&self.buf, 8, big));
+                    return Ok(Some((
+                        interface.ts_ns(ticks),
+                        interface.linktype,
+                        20,
+                        20 + captured as usize,
+                    )));
+                }
+                // Simple packet blocks (no timestamp), statistics, name
+                // resolution, custom blocks...
+                _ => {}
+            }
+        }
+    }
+}
+
+/// Which UDP datagrams carry the feed; `None` fields match anything
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct PcapFilter {
+    /// Destination port
+    pub port: Option<u16>,
+    /// Destination address (multicast group)
+    pub group: Option<Ipv4Addr>,
+}
+
+impl PcapFilter {
+    /// Datagrams to `port` on any address
+    pub fn port(port: u16) -> Self {
+        Self {
+            port: Some(port),
+            group: None,
+        }
+    }
+
+    /// Datagrams to `group:port`
+    pub fn group(group: Ipv4Addr, port: u16) -> Self {
+        Self {
+            port: Some(port),
+            group: Some(group),
+        }
+    }
+
+    fn matches(&self, dst: Ipv4Addr, port: u16) -> bool {
+        self.port.is_none_or(|p| p == port) && self.group.is_none_or(|g| g == dst)
+    }
+}
+
+/// Why a packet carried no usable datagram
+enum Skip {
+    /// Not IPv4 UDP on a supported link type
+    NotUdp,
+    /// IPv4 fragment (not reassembled)
+    Fragment,
+    /// Captured bytes end before the headers say they should
+    Truncated,
+}
+
+/// Destination and payload of an IPv4 UDP datagram
+struct Datagram<'a> {
+    dst: Ipv4Addr,
+    port: u16,
+    payload: &'a [u8],
+}
+
+/// Strip the link layer; returns the IPv4 packet
+fn ipv4_packet(linktype: u32, data: &[u8]) -> Result<&[u8], Skip> {
+    let (ethertype, offset) = match linktype {
+        LINKTYPE_ETHERNET => {
+            let mut offset = 12;
+            loop {
+                if data.len() < offset + 2 {
+                    return Err(Skip::Truncated);
+                }
+                let ethertype = u16::from_be_bytes([data[offset], data[offset + 1]]);
+                // 802.1Q / 802.1ad tags
+                if ethertype == 0x8100 || ethertype == 0x88a8 {
+                    offset += 4;
+                } else {
+                    break (ethertype, offset + 2);
+                }
+            }
+        }
+        LINKTYPE_LINUX_SLL => {
+            if data.len() < 16 {
+                return Err(Skip::Truncated);
+            }
+            (u16::from_be_bytes([data[14], data[15]]), 16)
+        }
+        LINKTYPE_RAW | LINKTYPE_IPV4 => (0x0800, 0),
+        _ => return Err(Skip::NotUdp),
+    };
+    if ethertype != 0x0800 {
+        return Err(Skip::NotUdp);
+    }
+    Ok(&data[offset..])
+}
+
+fn udp_datagram(ip: &[u8]) -> Result<Datagram<'_>, Skip> {
+    if ip.len() < 20 {
+        return Err(Skip::Truncated);
+    }
+    let header_len = usize::from(ip[0] & 0x0f) * 4;
+    if ip[0] >> 4 != 4 || header_len < 20 {
+        return Err(Skip::NotUdp);
+    }
+    let total_len = usize::from(u16::from_be_bytes([ip[2], ip[3]]));
+    if total_len < header_len + 8 || ip.len() < total_len {
+        return Err(Skip::Truncated);
+    }
+    let fragment = u16::from_be_bytes([ip[6], ip[7]]);
+    // More-fragments flag or a non-zero offset
+    if fragment & 0x3fff != 0 {
+        return Err(Skip::Fragment);
+    }
+    if ip[9] != 17 {
+        return Err(Skip::NotUdp);
+    }
+
+    let udp = &ip[header_len..total_len];
+    let udp_len = usize::from(u16::from_be_bytes([udp[4], udp[5]]));
+    if udp_len < 8 || udp_len > udp.len() {
+        return Err(Skip::Truncated);
+    }
+    Ok(Datagram {
+        dst: Ipv4Addr::new(ip[16], ip[17], ip[18], ip[19]),
+        port: u16::from_be_bytes([udp[2], udp[3]]),
+        payload: &udp[8..udp_len],
+    })
+}
+
+/// Counters for one capture
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct PcapStats {
+    /// Packets read from the file
+    pub packets: u64,
+    /// UDP datagrams that passed the filter and went to the feed decoder
+    pub matched: u64,
+    /// Other packets: not IPv4 UDP, or not to the filtered port/group
+    pub filtered: u64,
+    /// IPv4 fragments (not reassembled)
+    pub fragments: u64,
+    /// Packets cut short by the capture's snap length
+    pub truncated: u64,
+    /// 1 if the file ended inside its last block (a capture still being
+    /// written); replay stops there as at the end of the file
+    pub truncated_tail: u64,
+    /// Datagram decoding: frames, gaps, duplicates, malformed...
+    pub feed: UdpIngressStats,
+}
+
+/// Replays the feed datagrams of one capture
+pub struct PcapIngress<R: Read> {
+    reader: PcapReader<R>,
+    filter: PcapFilter,
+    pacer: Pacer,
+    decoder: FeedDecoder,
+    stats: PcapStats,
+}
+
+impl PcapIngress<BufReader<File>> {
+    /// Replay the pcap or pcapng file at `path`
+    pub fn open<P: AsRef<Path>>(
+        path: P,
+        filter: PcapFilter,
+        speed: ReplaySpeed,
+    ) -> Result<Self, PcapError> {
+        let file = File::open(path)?;
+        Ok(Self::new(
+            PcapReader::new(BufReader::new(file))?,
+            filter,
+            speed,
+        ))
+    }
+}
+
+impl<R: Read> PcapIngress<R> {
+    /// # Panics
+    /// Panics if a `Multiplier` speed is not finite and positive.
+    pub fn new(reader: PcapReader<R>, filter: PcapFilter, speed: ReplaySpeed) -> Self {
+        Self {
+            reader,
+            filter,
+            pacer: Pacer::new(speed),
+            decoder: FeedDecoder::new(),
+            stats: PcapStats::default(),
+        }
+    }
+
+    /// Push the transactions of every matching datagram into `out`, or until
+    /// `stop` is set
+    ///
+    /// # Errors
+    /// `BadLength`, `UnknownInterface` or `Io` from the reader. A block cut
+    /// off by the end of the file is not an error: it is counted in
+    /// `truncated_tail`.
+    pub fn replay<E: Emit<Transaction>>(
+        &mut self,
+        out: &mut E,
+        stop: &AtomicBool,
+    ) -> Result<PcapStats, PcapError> {
+        let mut out = BlockingEmit {
+            out,
+            stop,
+            stalls: 0,
+        };
+
+        while !stop.load(Ordering::Acquire) {
+            let packet = match self.reader.next_packet() {
+                Ok(Some(packet)) => packet,
+                Ok(None) => break,
+                Err(PcapError::Truncated { .. }) => {
+                    self.stats.truncated_tail += 1;
+                    break;
+                }
+                Err(err) => return Err(err),
+            };
+            self.stats.packets += 1;
+
+            let datagram = match ipv4_packet(packet.linktype, packet.data).and_then(udp_datagram) {
+                Ok(datagram) if self.filter.matches(datagram.dst, datagram.port) => datagram,
+                Ok(_) | Err(Skip::NotUdp) => {
+                    self.stats.filtered += 1;
+                    continue;
+                }
+                Err(Skip::Fragment) => {
+                    self.stats.fragments += 1;
+                    continue;
+                }
+                Err(Skip::Truncated) => {
+                    self.stats.truncated += 1;
+                    continue;
+                }
+            };
+            self.stats.matched += 1;
+
+            self.pacer.wait(packet.ts_ns);
+            self.decoder
+                .on_datagram(datagram.payload, tsc_to_ns(rdtsc()), &mut out);
+        }
+
+        Ok(self.stats())
+    }
+
+    pub fn stats(&self) -> PcapStats {
+        PcapStats {
+            feed: self.decoder.stats(),
+            ..self.stats
+        }
+    }
+}
+
+impl<R: Read + Send> IngressSource for PcapIngress<R> {
+    /// Replays the capture once, then returns
+    fn run<E: Emit<Transaction>>(&mut self, out: &mut E, stop: &AtomicBool) -> io::Result<()> {
+        self.replay(out, stop)
+            .map(|_| ())
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+    }
+
+    fn retries_when_full(&self) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ingress::udp::encode_datagram;
+    use crate::ring::RingBuffer;
+    use crate::tsc::init_tsc;
+    use crate::wire::TXN_FRAME_LEN;
+    use std::time::Instant;
+
+    const GROUP: Ipv4Addr = Ipv4Addr::new(239, 1, 1, 1);
+
+    fn feed_payload(seq: u64, ids: std::ops::Range<u64>) -> Vec<u8> {
+        let txns: Vec<_> = ids
+            .map(|id| Transaction::new_unchecked(id, 1_000_000, 10, 0, 0))
+            .collect();
+        let mut buf = vec![0; 12 + txns.len() * TXN_FRAME_LEN];
+        encode_datagram(seq, &txns, &mut buf).unwrap();
+        buf
+    }
+
+    /// IPv4 + UDP headers around `payload` (checksums left zero)
+    fn ipv4_udp(dst: Ipv4Addr, port: u16, payload: &[u8]) -> Vec<u8> {
+        let total = 28 + payload.len();
+        let mut ip = vec![
+            0x45,
+            0,
+            (total >> 8) as u8,
+            total as u8,
+            0,
+            0,
+            0,
+            0,
+            64,
+            17,
+            0,
+            0,
+        ];
+        ip.extend_from_slice(&[10, 0, 0, 1]);
+        ip.extend_from_slice(&dst.octets());
+        ip.extend_from_slice(&40000u16.to_be_bytes());
+        ip.extend_from_slice(&port.to_be_bytes());
+        ip.extend_from_slice(&((8 + payload.len()) as u16).to_be_bytes());
+        ip.extend_from_slice(&[0, 0]);
+        ip.extend_from_slice(payload);
+        ip
+    }
+
+    /// Ethernet frame with one VLAN tag
+    fn ethernet(ip: &[u8]) -> Vec<u8> {
+        let mut frame = vec![0x01, 0x00, 0x5e, 0x01, 0x01, 0x01, 0, 1, 2, 3, 4, 5];
+        frame.extend_from_slice(&[0x81, 0x00, 0x00, 0x64, 0x08, 0x00]);
+        frame.extend_from_slice(ip);
+        frame
+    }
+
+    /// Feed packets 1ms apart: two for the group, one for another port, one
+    /// TCP packet and a fragment
+    fn packets() -> Vec<(u64, Vec<u8>)> {
+        let mut tcp = ipv4_udp(GROUP, 5000, &[0; 4]);
+        tcp[9] = 6;
+        let mut fragment = ipv4_udp(GROUP, 5000, &feed_payload(9, 9..10));
+        fragment[6] = 0x20;
+        vec![
+            (ipv4_udp(GROUP, 5000, &feed_payload(1, 0..3))),
+            (ipv4_udp(GROUP, 6000, &feed_payload(1, 100..102))),
+            tcp,
+            fragment,
+            (ipv4_udp(GROUP, 5000, &feed_payload(5, 4..6))),
+        ]
+        .into_iter()
+        .enumerate()
+        .map(|(i, ip)| (1_700_000_000_000_000_000 + i as u64 * 1_000_000, ip))
+        .collect()
+    }
+
+    fn classic(big: bool, nanos: bool, linktype: u32, packets: &[(u64, Vec<u8>)]) -> Vec<u8> {
+        let u32b = |v: u32| {
+            if big {
+                v.to_be_bytes()
+            } else {
+                v.to_le_bytes()
+            }
+        };
+        let u16b = |v: u16| {
+            if big {
+                v.to_be_bytes()
+            } else {
+                v.to_le_bytes()
+            }
+        };
+        let mut file = Vec::new();
+        file.extend_from_slice(&u32b(if nanos { PCAP_NANOS } else { PCAP_MICROS }));
+        file.extend_from_slice(&u16b(2));
+        file.extend_from_slice(&u16b(4));
+        file.extend_from_slice(&[0; 8]);
+        file.extend_from_slice(&u32b(65535));
+        file.extend_from_slice(&u32b(linktype));
+        for (ts_ns, data) in packets {
+            let frac = if nanos {
+                ts_ns % 1_000_000_000
+            } else {
+                ts_ns % 1_000_000_000 / 1_000
+            };
+            file.extend_from_slice(&u32b((ts_ns / 1_000_000_000) as u32));
+            file.extend_from_slice(&u32b(frac as u32));
+            file.extend_from_slice(&u32b(data.len() as u32));
+            file.extend_from_slice(&u32b(data.len() as u32));
+            file.extend_from_slice(data);
+        }
+        file
+    }
+
+    fn pcapng_block(file: &mut Vec<u8>, block_type: u32, body: &[u8]) {
+        let padded = body.len().div_ceil(4) * 4;
+        let length = (12 + padded) as u32;
+        file.extend_from_slice(&block_type.to_le_bytes());
+        file.extend_from_slice(&length.to_le_bytes());
+        file.extend_from_slice(body);
+        file.resize(file.len() + padded - body.len(), 0);
+        file.extend_from_slice(&length.to_le_bytes());
+    }
+
+    /// Little-endian pcapng, Ethernet with nanosecond timestamps
+    fn pcapng(packets: &[(u64, Vec<u8>)]) -> Vec<u8> {
+        let mut file = Vec::new();
+        let mut shb = PCAPNG_BYTE_ORDER.to_le_bytes().to_vec();
+        shb.extend_from_slice(&[1, 0, 0, 0]);
+        shb.extend_from_slice(&u64::MAX.to_le_bytes());
+        pcapng_block(&mut file, PCAPNG_SHB, &shb);
+
+        let mut idb = vec![1, 0, 0, 0, 0, 0, 1, 0];
+        // if_tsresol = 9, then end of options
+        idb.extend_from_slice(&[9, 0, 1, 0, 9, 0, 0, 0, 0, 0, 0, 0]);
+        pcapng_block(&mut file, PCAPNG_IDB, &idb);
+        // A custom block to skip
+        pcapng_block(&mut file, 0x0000_0bad, &[1, 2, 3, 4]);
+
+        for (ts_ns, ip) in packets {
+            let frame = ethernet(ip);
+            let mut epb = 0u32.to_le_bytes().to_vec();
+            epb.extend_from_slice(&((ts_ns >> 32) as u32).to_le_bytes());
+            epb.extend_from_slice(&(*ts_ns as u32).to_le_bytes());
+            epb.extend_from_slice(&(frame.len() as u32).to_le_bytes());
+            epb.extend_from_slice(&(frame.len() as u32).to_le_bytes());
+            epb.extend_from_slice(&frame);
+            pcapng_block(&mut file, PCAPNG_EPB, &epb);
+        }
+        file
+    }
+
+    fn replay(file: &[u8], filter: PcapFilter, speed: ReplaySpeed) -> (PcapStats, Vec<u64>) {
+        let ring = RingBuffer::<Transaction, 64>::new();
+        let mut ingress = PcapIngress::new(PcapReader::new(file).unwrap(), filter, speed);
+        let stats = ingress.replay(&mut &ring, &AtomicBool::new(false)).unwrap();
+        (
+            stats,
+            std::iter::from_fn(|| ring.pop())
+                .map(|txn| txn.id)
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn test_pcap_formats_yield_same_feed() {
+        init_tsc();
+        let packets = packets();
+        let ethernet_packets: Vec<_> = packets.iter().map(|(ts, ip)| (*ts, ethernet(ip))).collect();
+        let files = [
+            classic(false, false, LINKTYPE_ETHERNET, &ethernet_packets),
+            classic(true, true, LINKTYPE_RAW, &packets),
+            pcapng(&packets),
+        ];
+
+        for file in &files {
+            let (stats, ids) = replay(
+                file,
+                PcapFilter::group(GROUP, 5000),
+                ReplaySpeed::AsFastAsPossible,
+            );
+            assert_eq!(ids, [0, 1, 2, 4, 5]);
+            assert_eq!(
+                (
+                    stats.packets,
+                    stats.matched,
+                    stats.filtered,
+                    stats.fragments
+                ),
+                (5, 2, 2, 1)
+            );
+            // Sequence 4 was never seen
+            assert_eq!((stats.feed.gaps, stats.feed.missed), (1, 1));
+        }
+    }
+
+    #[test]
+    fn test_pcap_stops_at_truncated_tail() {
+        init_tsc();
+        let file = classic(false, false, LINKTYPE_RAW, &packets());
+        let (stats, ids) = replay(
+            &file[..file.len() - 1],
+            PcapFilter::group(GROUP, 5000),
+            ReplaySpeed::AsFastAsPossible,
+        );
+        assert_eq!(ids, [0, 1, 2]);
+        assert_eq!((stats.packets, stats.truncated_tail), (4, 1));
+    }
+
+    #[test]
+    fn test_pcap_timestamps() {
+        let packets = packets();
+        for file in [
+            classic(true, false, LINKTYPE_RAW, &packets),
+            pcapng(&packets),
+        ] {
+            let mut reader = PcapReader::new(&file[..]).unwrap();
+            let mut stamps = Vec::new();
+            while let Some(packet) = reader.next_packet().unwrap() {
+                stamps.push(packet.ts_ns);
+            }
+            let expected: Vec<_> = packets.iter().map(|(ts, _)| *ts).collect();
+            assert_eq!(stamps, expected);
+        }
+    }
+
+    #[test]
+    fn test_pcap_paced_by_packet_timestamps() {
+        init_tsc();
+        let file = classic(false, true, LINKTYPE_RAW, &packets());
+
+        // Matching packets are 4ms apart in the capture
+        let start = Instant::now();
+        replay(&file, PcapFilter::port(5000), ReplaySpeed::Original);
+        assert!(start.elapsed().as_micros() >= 4_000);
+    }
+
+    #[test]
+    fn test_pcap_rejects_bad_files() {
+        assert_eq!(
+            PcapReader::new(&[0u8; 2][..]).err(),
+            Some(PcapError::Truncated { offset: 0 })
+        );
+        assert_eq!(
+            PcapReader::new(&[1u8, 2, 3, 4][..]).err(),
+            Some(PcapError::BadMagic(0x0403_0201))
+        );
+
+        let file = classic(false, false, LINKTYPE_RAW, &packets());
+        let mut reader = PcapReader::new(&file[..file.len() - 1]).unwrap();
+        for _ in 0..4 {
+            reader.next_packet().unwrap().unwrap();
+        }
+        assert!(matches!(
+            reader.next_packet(),
+            Err(PcapError::Truncated { .. })
+        ));
+
+        // Packet on an interface the file never described
+        let mut file = pcapng(&packets());
+        let first_epb = file.len()
+            - packets()
+                .iter()
+                .map(|(_, ip)| 12 + (20 + 18 + ip.len()).div_ceil(4) * 4)
+                .sum::<usize>();
+        file[first_epb + 8] = 3;
+        let mut reader = PcapReader::new(&file[..]).unwrap();
+        assert!(matches!(
+            reader.next_packet(),
+            Err(PcapError::UnknownInterface { interface: 3, .. })
+        ));
+    }
+}
@@ -0,0 +1,358 @@
+/// Replay ingress: pushes the transactions of a capture file back into the
+/// ingress ring
+///
+/// Replays are deterministic: transactions are pushed in file order with
+/// their recorded contents, and a full ring is waited on rather than dropped
+/// from, so two replays of one file deliver identical streams. Pacing only
+/// changes when they arrive. Bundle records in the file are skipped.
+use crate::backoff::Backoff;
+use crate::capture::{CaptureReader, CaptureRecord};
+use crate::errors::CaptureError;
+use crate::ingress::IngressSource;
+use crate::stage::Emit;
+use crate::tsc::{rdtsc, spin_sleep_ns, tsc_to_ns};
+use crate::types::Transaction;
+use std::fs::File;
+use std::io::{self, BufReader, Read};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// How fast to replay relative to the recorded timestamps
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ReplaySpeed {
+    /// Reproduce the recorded inter-arrival gaps
+    Original,
+    /// Gaps divided by the factor (2.0 = twice as fast)
+    Multiplier(f64),
+    /// No pacing: push as soon as the ring has room
+    AsFastAsPossible,
+}
+
+/// Sleeps until each recorded timestamp comes due, relative to the first
+pub(crate) struct Pacer {
+    /// Recorded time per replayed nanosecond (None: no pacing)
+    divisor: Option<f64>,
+    /// (first recorded timestamp, replay start) once the first item is seen
+    origin: Option<(u64, u64)>,
+}
+
+impl Pacer {
+    pub(crate) fn new(speed: ReplaySpeed) -> Self {
+        let divisor = match speed {
+            ReplaySpeed::Original => Some(1.0),
+            ReplaySpeed::Multiplier(factor) => {
+                assert!(factor.is_finite() && factor > 0.0, "replay multiplier must be positive");
+                Some(factor)
+            }
+            ReplaySpeed::AsFastAsPossible => None,
+        };
+        Self { divisor, origin: None }
+    }
+
+    /// Wait for the item recorded at `ts_ns`
+    pub(crate) fn wait(&mut self, ts_ns: u64) {
+        let Some(divisor) = self.divisor else {
+            return;
+        };
+        let now = tsc_to_ns(rdtsc());
+        let (first_ts, start) = *self.origin.get_or_insert((ts_ns, now));
+        let due = start + (ts_ns.saturating_sub(first_ts) as f64 / divisor) as u64;
+        if due > now {
+            spin_sleep_ns(due - now);
+        }
+    }
+}
+
+/// `Emit` that waits for room instead of dropping, until `stop` is set
+pub(crate) struct BlockingEmit<'a, E> {
+    pub(crate) out: &'a mut E,
+    pub(crate) stop: &'a AtomicBool,
+    /// Emits that found `out` full and had to wait
+    pub(crate) stalls: u64,
+}
+
+impl<T, E: Emit<T>> Emit<T> for BlockingEmit<'_, E> {
+    fn emit(&mut self, item: T) -> Result<(), T> {
+        let Err(mut pending) = self.out.emit(item) else {
+            return Ok(());
+        };
+        self.stalls += 1;
+        let mut backoff = Backoff::new();
+        loop {
+            if self.stop.load(Ordering::Acquire) {
+                return Err(pending);
+            }
+            backoff.snooze();
+            match self.out.emit(pending) {
+                Ok(()) => return Ok(()),
+                Err(item) => pending = item,
+            }
+        }
+    }
+}
+
+/// Counters for one replay
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ReplayStats {
+    /// Records read from the file
+    pub records: u64,
+    /// Transactions pushed into the ring
+    pub pushed: u64,
+    /// Non-transaction records skipped
+    pub skipped: u64,
+    /// Pushes that found the ring full and had to wait
+    pub stalls: u64,
+    /// 1 if the file ended inside its last record (a capture still being
+    /// written); replay stops there as at the end of the file
+    pub truncated_tail: u64,
+}
+
+/// Replays one capture
+pub struct ReplayIngress<R: Read> {
+    reader: CaptureReader<R>,
+    pacer: Pacer,
+    /// Replace each transaction's `ingress_ts_ns` with the replay time
+    restamp: bool,
+    stats: ReplayStats,
+}
+
+impl ReplayIngress<BufReader<File>> {
+    /// Replay the capture file at `path`
+    pub fn open<P: AsRef<Path>>(path: P, speed: ReplaySpeed) -> Result<Self, CaptureError> {
+        let file = File::open(path)?;
+        Ok(Self::new(CaptureReader::new(BufReader::new(file))?, speed))
+    }
+}
+
+impl<R: Read> ReplayIngress<R> {
+    /// # Panics
+    /// Panics if a `Multiplier` is not finite and positive.
+    pub fn new(reader: CaptureReader<R>, speed: ReplaySpeed) -> Self {
+        Self {
+            reader,
+            pacer: Pacer::new(speed),
+            restamp: false,
+            stats: ReplayStats::default(),
+        }
+    }
+
+    /// Stamp transactions with the time they are replayed instead of keeping
+    /// the recorded `ingress_ts_ns` (for latency measurement; the output then
+    /// differs between runs)
+    pub fn restamp(mut self, restamp: bool) -> Self {
+        self.restamp = restamp;
+        self
+    }
+
+    /// Push every transaction in the file into `out`, or until `stop` is set
+    ///
+    /// # Errors
+    /// `Frame` or `Io` from the reader. A record cut off by the end of the
+    /// file is not an error: it is counted in `truncated_tail`.
+    pub fn replay<E: Emit<Transaction>>(
+        &mut self,
+        out: &mut E,
+        stop: &AtomicBool,
+    ) -> Result<ReplayStats, CaptureError> {
+        // Wait for room rather than drop, so every replay is identical
+        let mut out = BlockingEmit {
+            out,
+            stop,
+            stalls: 0,
+        };
+
+        while !stop.load(Ordering::Acquire) {
+            let record = match self.reader.next_record() {
+                Ok(Some(record)) => record,
+                Ok(None) => break,
+                Err(CaptureError::Truncated { .. }) => {
+                    self.stats.truncated_tail += 1;
+                    break;
+                }
+                Err(err) => return Err(err),
+            };
+            self.stats.records += 1;
+            let (ts_ns, mut txn) = match record {
+                CaptureRecord::Transaction { ts_ns, txn } => (ts_ns, txn),
+                CaptureRecord::Bundle { .. } => {
+                    self.stats.skipped += 1;
+                    continue;
+                }
+            };
+
+            self.pacer.wait(ts_ns);
+            if self.restamp {
+                txn.ingress_ts_ns = tsc_to_ns(rdtsc());
+            }
+            let pushed = out.emit(txn).is_ok();
+            self.stats.stalls += std::mem::take(&mut out.stalls);
+            if !pushed {
+                // Stopped while waiting
+                break;
+            }
+            self.stats.pushed += 1;
+        }
+
+        Ok(self.stats)
+    }
+
+    pub fn stats(&self) -> ReplayStats {
+        self.stats
+    }
+}
+
+impl<R: Read + Send> IngressSource for ReplayIngress<R> {
+    /// Replays the file once, then returns
+    fn run<E: Emit<Transaction>>(&mut self, out: &mut E, stop: &AtomicBool) -> io::Result<()> {
+        self.replay(out, stop)
+            .map(|_| ())
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+    }
+
+    fn retries_when_full(&self) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::capture::CaptureWriter;
+    use crate::ring::RingBuffer;
+    use crate::tsc::init_tsc;
+    use crate::types::Bundle;
+    use std::sync::Arc;
+    use std::thread;
+    use std::time::Instant;
+
+    /// 50 transactions 100µs apart, with one bundle record in the middle
+    fn capture() -> Vec<u8> {
+        let mut writer = CaptureWriter::new(Vec::new()).unwrap();
+        for id in 0..50u64 {
+            let txn = Transaction::new_unchecked(
+                id,
+                1_000_000 + id as i64,
+                1 + id as u32,
+                (id % 2) as u8,
+                id * 7,
+            );
+            writer
+                .write_transaction(1_000_000 + id * 100_000, &txn)
+                .unwrap();
+            if id == 25 {
+                writer.write_bundle(0, &Bundle::new()).unwrap();
+            }
+        }
+        writer.into_inner()
+    }
+
+    fn replay_into(ring: &RingBuffer<Transaction, 64>, speed: ReplaySpeed) -> ReplayStats {
+        let data = capture();
+        let mut replay = ReplayIngress::new(CaptureReader::new(&data[..]).unwrap(), speed);
+        replay.replay(&mut &*ring, &AtomicBool::new(false)).unwrap()
+    }
+
+    #[test]
+    fn test_replay_is_deterministic() {
+        init_tsc();
+        let runs: Vec<Vec<Transaction>> = (0..2)
+            .map(|_| {
+                let ring = RingBuffer::<Transaction, 64>::new();
+                let stats = replay_into(&ring, ReplaySpeed::AsFastAsPossible);
+                assert_eq!((stats.records, stats.pushed, stats.skipped), (51, 50, 1));
+                std::iter::from_fn(|| ring.pop()).collect()
+            })
+            .collect();
+
+        assert_eq!(runs[0].len(), 50);
+        // Recorded contents, timestamps included
+        assert_eq!(runs[0][3].ingress_ts_ns, 21);
+        assert!(runs[0]
+            .iter()
+            .zip(&runs[1])
+            .all(|(a, b)| a.to_bytes() == b.to_bytes()));
+    }
+
+    #[test]
+    fn test_replay_stops_at_truncated_tail() {
+        init_tsc();
+        let data = capture();
+        let ring = RingBuffer::<Transaction, 64>::new();
+        let mut replay = ReplayIngress::new(
+            CaptureReader::new(&data[..data.len() - 3]).unwrap(),
+            ReplaySpeed::AsFastAsPossible,
+        );
+
+        // The cut-off last record ends the replay like the end of the file
+        let stats = replay.replay(&mut &ring, &AtomicBool::new(false)).unwrap();
+        assert_eq!((stats.pushed, stats.truncated_tail), (49, 1));
+    }
+
+    #[test]
+    fn test_replay_pacing() {
+        init_tsc();
+        let ring = RingBuffer::<Transaction, 64>::new();
+
+        // 49 gaps of 100µs: 4.9ms at original speed
+        let start = Instant::now();
+        replay_into(&ring, ReplaySpeed::Original);
+        let original = start.elapsed();
+        assert!(original.as_micros() >= 4_900, "{:?}", original);
+
+        while ring.pop().is_some() {}
+        let start = Instant::now();
+        replay_into(&ring, ReplaySpeed::Multiplier(10.0));
+        let fast = start.elapsed();
+        assert!(fast.as_micros() >= 490 && fast < original, "{:?}", fast);
+    }
+
+    #[test]
+    fn test_replay_waits_for_full_ring() {
+        init_tsc();
+        let data = capture();
+        let ring = Arc::new(RingBuffer::<Transaction, 4>::new());
+        let consumer = {
+            let ring = Arc::clone(&ring);
+            thread::spawn(move || {
+                let mut ids = Vec::new();
+                while ids.len() < 50 {
+                    match ring.pop() {
+                        Some(txn) => ids.push(txn.id),
+                        None => thread::yield_now(),
+                    }
+                }
+                ids
+            })
+        };
+
+        let mut replay = ReplayIngress::new(
+            CaptureReader::new(&data[..]).unwrap(),
+            ReplaySpeed::AsFastAsPossible,
+        );
+        let stats = replay.replay(&mut &*ring, &AtomicBool::new(false)).unwrap();
+
+        assert_eq!(stats.pushed, 50);
+        assert_eq!(consumer.join().unwrap(), (0..50).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_replay_stops_while_blocked() {
+        init_tsc();
+        let ring = RingBuffer::<Transaction, 4>::new();
+        let data = capture();
+        let stop = AtomicBool::new(false);
+        let mut replay = ReplayIngress::new(
+            CaptureReader::new(&data[..]).unwrap(),
+            ReplaySpeed::AsFastAsPossible,
+        );
+
+        thread::scope(|scope| {
+            scope.spawn(|| {
+                thread::sleep(std::time::Duration::from_millis(20));
+                stop.store(true, Ordering::Release);
+            });
+            let stats = replay.replay(&mut &ring, &stop).unwrap();
+            assert_eq!((stats.pushed, stats.stalls), (4, 1));
+        });
+    }
+}
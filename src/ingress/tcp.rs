@@ -0,0 +1,445 @@
+/// TCP feed handler
+///
+/// The stream is a plain sequence of transaction frames
+/// (`Transaction::encode`); each frame header carries its payload length, so
+/// frames are found without any extra delimiter. Reads may end anywhere in a
+/// frame: `StreamDecoder` keeps the partial tail until the rest arrives.
+///
+/// Corruption inside a frame whose header is sound (CRC mismatch, invalid
+/// transaction) skips that frame. A header that does not parse means the
+/// stream has lost frame alignment, so the session is closed and the
+/// listener waits for the peer to reconnect.
+use crate::errors::WireError;
+use crate::ingress::IngressSource;
+use crate::stage::Emit;
+use crate::tsc::{rdtsc, tsc_to_ns};
+use crate::types::Transaction;
+use crate::wire::{FrameHeader, FrameKind, TXN_FRAME_LEN};
+use std::io::{self, Read};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::Duration;
+
+/// Bytes read from the socket per call
+pub const READ_BUFFER_LEN: usize = 64 * 1024;
+
+/// How often an idle listener checks its stop flag
+const ACCEPT_POLL: Duration = Duration::from_millis(1);
+
+/// Counters for one connection
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct TcpSessionStats {
+    pub bytes: u64,
+    /// Frames decoded and offered to the ring
+    pub frames: u64,
+    pub pushed: u64,
+    /// Frames lost because the ring was full
+    pub dropped: u64,
+    /// Frames skipped as corrupt, plus a bad header or partial frame that
+    /// ended the session
+    pub malformed: u64,
+}
+
+impl TcpSessionStats {
+    pub fn drop_rate(&self) -> f64 {
+        if self.frames == 0 {
+            0.0
+        } else {
+            self.dropped as f64 / self.frames as f64
+        }
+    }
+}
+
+/// One accepted connection
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TcpSession {
+    pub peer: SocketAddr,
+    pub stats: TcpSessionStats,
+    /// Framing error that closed the session (None: the peer disconnected
+    /// or the listener was stopped)
+    pub closed_by: Option<WireError>,
+}
+
+/// Socket-independent frame reassembly for a byte stream
+pub struct StreamDecoder {
+    buf: Vec<u8>,
+    /// Bytes of `buf` holding data not yet decoded
+    filled: usize,
+}
+
+impl StreamDecoder {
+    pub fn new() -> Self {
+        Self {
+            buf: vec![0; READ_BUFFER_LEN],
+            filled: 0,
+        }
+    }
+
+    /// Read once from `reader` after the buffered tail; Ok(0) is end of
+    /// stream
+    pub fn read_from<R: Read>(&mut self, reader: &mut R) -> io::Result<usize> {
+        reader.read(&mut self.buf[self.filled..])
+    }
+
+    /// Record `len` bytes appended by `read_from` and decode every complete
+    /// frame, stamping each with `ingress_ts_ns`. Returns the number pushed.
+    ///
+    /// # Errors
+    /// The header error that made the stream unreadable; frames before it
+    /// were still delivered.
+    pub fn decode<E: Emit<Transaction>>(
+        &mut self,
+        len: usize,
+        ingress_ts_ns: u64,
+        out: &mut E,
+        stats: &mut TcpSessionStats,
+    ) -> Result<usize, WireError> {
+        self.filled += len;
+        let mut pos = 0;
+        let mut pushed = 0;
+
+        let result = loop {
+            let rest = &self.buf[pos..self.filled];
+            match FrameHeader::parse(rest) {
+                Ok(header) if header.kind == FrameKind::Transaction => {}
+                Ok(header) => {
+                    break Err(WireError::UnexpectedKind {
+                        expected: FrameKind::Transaction as u8,
+                        found: header.kind as u8,
+                    })
+                }
+                Err(WireError::Truncated { .. }) => break Ok(pushed),
+                Err(err) => break Err(err),
+            }
+            if rest.len() < TXN_FRAME_LEN {
+                break Ok(pushed);
+            }
+            pos += TXN_FRAME_LEN;
+
+            let mut txn = match Transaction::decode(rest) {
+                Ok((txn, _)) => txn,
+                Err(_) => {
+                    stats.malformed += 1;
+                    continue;
+                }
+            };
+            txn.ingress_ts_ns = ingress_ts_ns;
+            stats.frames += 1;
+
+            match out.emit(txn) {
+                Ok(()) => {
+                    stats.pushed += 1;
+                    pushed += 1;
+                }
+                Err(_) => stats.dropped += 1,
+            }
+        };
+
+        // Keep the partial frame for the next read
+        self.buf.copy_within(pos..self.filled, 0);
+        self.filled -= pos;
+        result
+    }
+
+    /// Bytes of an incomplete frame waiting for the rest
+    pub fn buffered(&self) -> usize {
+        self.filled
+    }
+
+    /// Forget buffered bytes (new connection)
+    pub fn reset(&mut self) {
+        self.filled = 0;
+    }
+}
+
+impl Default for StreamDecoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Listener serving one upstream connection at a time; when it closes,
+/// the next connection (typically the same peer reconnecting) is accepted
+pub struct TcpIngress {
+    listener: TcpListener,
+    decoder: StreamDecoder,
+    /// Longest a read blocks, so `run` notices its stop flag
+    read_timeout: Duration,
+    /// Every session so far, the current one last
+    sessions: Vec<TcpSession>,
+}
+
+impl TcpIngress {
+    pub fn bind(addr: SocketAddr) -> io::Result<Self> {
+        let listener = TcpListener::bind(addr)?;
+        listener.set_nonblocking(true)?;
+        Ok(Self {
+            listener,
+            decoder: StreamDecoder::new(),
+            read_timeout: Duration::from_millis(10),
+            sessions: Vec::new(),
+        })
+    }
+
+    /// Longest a read or an idle accept blocks before checking the stop flag
+    pub fn with_read_timeout(mut self, timeout: Duration) -> Self {
+        self.read_timeout = timeout;
+        self
+    }
+
+    /// Bound address (useful after binding port 0)
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.listener.local_addr()
+    }
+
+    pub fn sessions(&self) -> &[TcpSession] {
+        &self.sessions
+    }
+
+    /// Counters summed over every session
+    pub fn totals(&self) -> TcpSessionStats {
+        self.sessions
+            .iter()
+            .fold(TcpSessionStats::default(), |mut total, session| {
+                total.bytes += session.stats.bytes;
+                total.frames += session.stats.frames;
+                total.pushed += session.stats.pushed;
+                total.dropped += session.stats.dropped;
+                total.malformed += session.stats.malformed;
+                total
+            })
+    }
+
+    /// Read one connection until it closes, fails to frame or `stop` is set
+    fn serve<E: Emit<Transaction>>(
+        &mut self,
+        mut stream: TcpStream,
+        peer: SocketAddr,
+        out: &mut E,
+        stop: &AtomicBool,
+    ) -> io::Result<()> {
+        stream.set_nonblocking(false)?;
+        stream.set_read_timeout(Some(self.read_timeout))?;
+        self.decoder.reset();
+        self.sessions.push(TcpSession {
+            peer,
+            stats: TcpSessionStats::default(),
+            closed_by: None,
+        });
+        let session = self.sessions.last_mut().unwrap();
+
+        while !stop.load(Ordering::Acquire) {
+            let len = match self.decoder.read_from(&mut stream) {
+                Ok(0) => {
+                    // A partial frame at disconnect is lost
+                    if self.decoder.buffered() > 0 {
+                        session.stats.malformed += 1;
+                    }
+                    break;
+                }
+                Ok(len) => len,
+                Err(err)
+                    if matches!(
+                        err.kind(),
+                        io::ErrorKind::WouldBlock
+                            | io::ErrorKind::TimedOut
+                            | io::ErrorKind::Interrupted
+                    ) =>
+                {
+                    continue
+                }
+                // Reset by the peer: wait for it to reconnect
+                Err(_) => break,
+            };
+
+            session.stats.bytes += len as u64;
+            let ingress_ts_ns = tsc_to_ns(rdtsc());
+            if let Err(err) = self
+                .decoder
+                .decode(len, ingress_ts_ns, out, &mut session.stats)
+            {
+                session.stats.malformed += 1;
+                session.closed_by = Some(err);
+                break;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl IngressSource for TcpIngress {
+    fn run<E: Emit<Transaction>>(&mut self, out: &mut E, stop: &AtomicBool) -> io::Result<()> {
+        while !stop.load(Ordering::Acquire) {
+            match self.listener.accept() {
+                Ok((stream, peer)) => self.serve(stream, peer, out, stop)?,
+                Err(err) if err.kind() == io::ErrorKind::WouldBlock => thread::sleep(ACCEPT_POLL),
+                Err(err)
+                    if matches!(
+                        err.kind(),
+                        io::ErrorKind::Interrupted | io::ErrorKind::ConnectionAborted
+                    ) => {}
+                Err(err) => return Err(err),
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ring::RingBuffer;
+    use crate::tsc::init_tsc;
+    use std::io::Write;
+    use std::sync::Arc;
+
+    fn stream_of(ids: std::ops::Range<u64>) -> Vec<u8> {
+        ids.flat_map(|id| Transaction::new_unchecked(id, 1_000_000, 10, 0, 0).encode())
+            .collect()
+    }
+
+    /// Hands out at most `chunk` bytes per read
+    struct Trickle<'a> {
+        data: &'a [u8],
+        chunk: usize,
+    }
+
+    impl Read for Trickle<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let len = self.chunk.min(buf.len()).min(self.data.len());
+            buf[..len].copy_from_slice(&self.data[..len]);
+            self.data = &self.data[len..];
+            Ok(len)
+        }
+    }
+
+    fn decode_all(
+        decoder: &mut StreamDecoder,
+        reader: &mut impl Read,
+        ring: &RingBuffer<Transaction, 64>,
+    ) -> (TcpSessionStats, Result<(), WireError>) {
+        let mut stats = TcpSessionStats::default();
+        loop {
+            let len = decoder.read_from(reader).unwrap();
+            if len == 0 {
+                return (stats, Ok(()));
+            }
+            if let Err(err) = decoder.decode(len, 0, &mut &*ring, &mut stats) {
+                return (stats, Err(err));
+            }
+        }
+    }
+
+    #[test]
+    fn test_stream_decoder_reassembles_partial_reads() {
+        let data = stream_of(0..10);
+        for chunk in [1, 7, TXN_FRAME_LEN - 1, TXN_FRAME_LEN + 3, 1000] {
+            let ring = RingBuffer::<Transaction, 64>::new();
+            let mut decoder = StreamDecoder::new();
+            let (stats, result) =
+                decode_all(&mut decoder, &mut Trickle { data: &data, chunk }, &ring);
+
+            assert_eq!(result, Ok(()));
+            assert_eq!((stats.frames, stats.pushed, stats.malformed), (10, 10, 0));
+            assert_eq!(decoder.buffered(), 0);
+            assert!((0..10).all(|id| ring.pop().unwrap().id == id));
+        }
+    }
+
+    #[test]
+    fn test_stream_decoder_skips_corrupt_frame_and_stops_on_bad_header() {
+        let ring = RingBuffer::<Transaction, 64>::new();
+        let mut decoder = StreamDecoder::new();
+
+        // Payload corruption: that frame is skipped, alignment is kept
+        let mut data = stream_of(0..3);
+        data[TXN_FRAME_LEN + 10] ^= 0x01;
+        let (stats, result) = decode_all(
+            &mut decoder,
+            &mut Trickle {
+                data: &data,
+                chunk: 5,
+            },
+            &ring,
+        );
+        assert_eq!(result, Ok(()));
+        assert_eq!((stats.pushed, stats.malformed), (2, 1));
+
+        // Header corruption: nothing past it can be trusted
+        let mut data = stream_of(0..3);
+        data[TXN_FRAME_LEN] = b'Z';
+        let (stats, result) = decode_all(
+            &mut decoder,
+            &mut Trickle {
+                data: &data,
+                chunk: 1000,
+            },
+            &ring,
+        );
+        assert_eq!(result, Err(WireError::BadMagic([b'Z', b'X'])));
+        assert_eq!(stats.pushed, 1);
+    }
+
+    #[test]
+    fn test_stream_decoder_counts_drops() {
+        let ring = RingBuffer::<Transaction, 64>::new();
+        let mut out = &ring;
+        let mut decoder = StreamDecoder::new();
+        let mut stats = TcpSessionStats::default();
+        let data = stream_of(0..70);
+        let len = decoder.read_from(&mut &data[..]).unwrap();
+
+        assert_eq!(decoder.decode(len, 0, &mut out, &mut stats), Ok(64));
+        assert_eq!((stats.frames, stats.pushed, stats.dropped), (70, 64, 6));
+    }
+
+    #[test]
+    fn test_tcp_ingress_reconnect() {
+        init_tsc();
+        let mut ingress = TcpIngress::bind("127.0.0.1:0".parse().unwrap()).unwrap();
+        let addr = ingress.local_addr().unwrap();
+        let ring = Arc::new(RingBuffer::<Transaction, 1024>::new());
+        let stop = Arc::new(AtomicBool::new(false));
+
+        let handle = {
+            let (ring, stop) = (Arc::clone(&ring), Arc::clone(&stop));
+            thread::spawn(move || {
+                ingress.run(&mut &*ring, &stop).unwrap();
+                ingress
+            })
+        };
+
+        // First connection ends mid-frame; the second one starts clean
+        let data = stream_of(0..20);
+        let mut first = TcpStream::connect(addr).unwrap();
+        first.write_all(&data[..5 * TXN_FRAME_LEN + 9]).unwrap();
+        drop(first);
+        let mut second = TcpStream::connect(addr).unwrap();
+        for frame in data[5 * TXN_FRAME_LEN..].chunks(TXN_FRAME_LEN * 3 + 1) {
+            second.write_all(frame).unwrap();
+        }
+        drop(second);
+
+        let mut waited = 0;
+        while ring.len() < 20 && waited < 500 {
+            thread::sleep(Duration::from_millis(2));
+            waited += 1;
+        }
+        stop.store(true, Ordering::Release);
+        let ingress = handle.join().unwrap();
+
+        let sessions = ingress.sessions();
+        assert_eq!(sessions.len(), 2);
+        assert_eq!(
+            (sessions[0].stats.pushed, sessions[0].stats.malformed),
+            (5, 1)
+        );
+        assert_eq!(
+            (sessions[1].stats.pushed, sessions[1].stats.malformed),
+            (15, 0)
+        );
+        assert_eq!(ingress.totals().bytes, (20 * TXN_FRAME_LEN + 9) as u64);
+        assert!((0..20).all(|id| ring.pop().unwrap().id == id));
+    }
+}
@@ -0,0 +1,543 @@
+/// UDP (unicast or multicast) feed handler
+///
+/// Each datagram carries a small sequencing header followed by `count`
+/// transaction frames (`Transaction::encode`), all little-endian:
+///
+/// ```text
+/// offset  size  field
+///      0     8  seq      sequence number of the first frame
+///      8     2  count    frames that follow (0 = heartbeat)
+///     10     2  reserved zero
+///     12  44×n  frames
+/// ```
+///
+/// Sequence numbers count frames, so the next datagram starts at
+/// `seq + count`. `FeedDecoder` tracks them: a jump forward is a gap, frames
+/// at or before the last one seen are duplicates and are skipped. A jump
+/// back of more than the reset window (a restarted publisher counting from
+/// 1 again) re-syncs to the new sequence instead.
+use crate::errors::WireError;
+use crate::ingress::IngressSource;
+use crate::stage::Emit;
+use crate::tsc::{rdtsc, tsc_to_ns};
+use crate::types::Transaction;
+use crate::wire::TXN_FRAME_LEN;
+use socket2::{Domain, Protocol, Socket, Type};
+use std::io;
+use std::net::{Ipv4Addr, SocketAddr, SocketAddrV4, UdpSocket};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+/// Sequencing header in front of the frames
+pub const DATAGRAM_HEADER_LEN: usize = 12;
+/// Frames per datagram that keep it within a 1500-byte Ethernet MTU
+pub const MAX_DATAGRAM_FRAMES: usize = 32;
+/// Largest datagram `UdpFeedSender` produces
+pub const MAX_DATAGRAM_LEN: usize = DATAGRAM_HEADER_LEN + MAX_DATAGRAM_FRAMES * TXN_FRAME_LEN;
+
+/// Encode `txns` as one datagram starting at sequence number `seq`; returns
+/// the bytes written.
+///
+/// # Errors
+/// `BufferTooSmall` if `buf` cannot hold the datagram.
+///
+/// # Panics
+/// Panics if `txns` holds more than `u16::MAX` transactions.
+pub fn encode_datagram(seq: u64, txns: &[Transaction], buf: &mut [u8]) -> Result<usize, WireError> {
+    assert!(
+        txns.len() <= u16::MAX as usize,
+        "too many frames for one datagram"
+    );
+    let needed = DATAGRAM_HEADER_LEN + txns.len() * TXN_FRAME_LEN;
+    if buf.len() < needed {
+        return Err(WireError::BufferTooSmall {
+            needed,
+            available: buf.len(),
+        });
+    }
+
+    buf[0..8].copy_from_slice(&seq.to_le_bytes());
+    buf[8..10].copy_from_slice(&(txns.len() as u16).to_le_bytes());
+    buf[10..12].fill(0);
+    for (slot, txn) in buf[DATAGRAM_HEADER_LEN..needed]
+        .chunks_exact_mut(TXN_FRAME_LEN)
+        .zip(txns)
+    {
+        slot.copy_from_slice(&txn.encode());
+    }
+    Ok(needed)
+}
+
+/// Counters for one feed
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct UdpIngressStats {
+    pub datagrams: u64,
+    pub bytes: u64,
+    /// Frames decoded and offered to the ring
+    pub frames: u64,
+    pub pushed: u64,
+    /// Frames lost because the ring was full
+    pub dropped: u64,
+    /// Datagrams with a bad header, length or sequence, plus frames that
+    /// failed to decode
+    pub malformed: u64,
+    /// Times the sequence jumped forward
+    pub gaps: u64,
+    /// Frames skipped over by gaps
+    pub missed: u64,
+    /// Frames already seen (retransmits, A/B feed overlap)
+    pub duplicates: u64,
+    /// Times the sequence jumped back past the reset window and was re-synced
+    pub resets: u64,
+}
+
+impl UdpIngressStats {
+    pub fn drop_rate(&self) -> f64 {
+        if self.frames == 0 {
+            0.0
+        } else {
+            self.dropped as f64 / self.frames as f64
+        }
+    }
+}
+
+/// Default backward jump, in frames, beyond which a datagram is taken as a
+/// publisher restart rather than a retransmit
+pub const DEFAULT_RESET_WINDOW: u64 = 1024;
+
+/// Socket-independent datagram decoding and gap detection
+#[derive(Debug)]
+pub struct FeedDecoder {
+    /// Sequence number of the next frame expected (None before the first)
+    next_seq: Option<u64>,
+    reset_window: u64,
+    stats: UdpIngressStats,
+}
+
+impl Default for FeedDecoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FeedDecoder {
+    pub fn new() -> Self {
+        Self {
+            next_seq: None,
+            reset_window: DEFAULT_RESET_WINDOW,
+            stats: UdpIngressStats::default(),
+        }
+    }
+
+    /// Re-sync when a datagram starts more than `frames` behind the next
+    /// expected sequence. Restarts closer than this look like retransmits
+    /// and are skipped until the publisher passes the old high-water mark.
+    pub fn with_reset_window(mut self, frames: u64) -> Self {
+        self.reset_window = frames;
+        self
+    }
+
+    /// Decode one datagram and push its new frames into `out`, stamping
+    /// each with `ingress_ts_ns`. Returns the number pushed.
+    pub fn on_datagram<E: Emit<Transaction>>(
+        &mut self,
+        datagram: &[u8],
+        ingress_ts_ns: u64,
+        out: &mut E,
+    ) -> usize {
+        self.stats.datagrams += 1;
+        self.stats.bytes += datagram.len() as u64;
+
+        if datagram.len() < DATAGRAM_HEADER_LEN {
+            self.stats.malformed += 1;
+            return 0;
+        }
+        let seq = u64::from_le_bytes(datagram[0..8].try_into().unwrap());
+        let count = u16::from_le_bytes([datagram[8], datagram[9]]) as u64;
+        if datagram.len() != DATAGRAM_HEADER_LEN + count as usize * TXN_FRAME_LEN {
+            self.stats.malformed += 1;
+            return 0;
+        }
+        // A sequence this close to u64::MAX is corrupt, not a real feed
+        let Some(end) = seq.checked_add(count) else {
+            self.stats.malformed += 1;
+            return 0;
+        };
+
+        // Frames before `next_seq` were already delivered
+        let mut skip = 0;
+        match self.next_seq {
+            Some(next) if seq > next => {
+                self.stats.gaps += 1;
+                self.stats.missed += seq - next;
+            }
+            // Too far back for a retransmit: the publisher restarted
+            Some(next) if next - seq > self.reset_window => {
+                self.stats.resets += 1;
+                self.next_seq = None;
+            }
+            Some(next) => skip = (next - seq).min(count),
+            None => {}
+        }
+        self.stats.duplicates += skip;
+        if self.next_seq.is_none_or(|next| end > next) {
+            self.next_seq = Some(end);
+        }
+
+        let mut pushed = 0;
+        for frame in datagram[DATAGRAM_HEADER_LEN..]
+            .chunks_exact(TXN_FRAME_LEN)
+            .skip(skip as usize)
+        {
+            let mut txn = match Transaction::decode(frame) {
+                Ok((txn, _)) => txn,
+                Err(_) => {
+                    self.stats.malformed += 1;
+                    continue;
+                }
+            };
+            txn.ingress_ts_ns = ingress_ts_ns;
+            self.stats.frames += 1;
+
+            // Drop on full, like the synthetic generator
+            match out.emit(txn) {
+                Ok(()) => {
+                    self.stats.pushed += 1;
+                    pushed += 1;
+                }
+                Err(_) => self.stats.dropped += 1,
+            }
+        }
+        pushed
+    }
+
+    /// Sequence number of the next frame expected
+    pub fn next_seq(&self) -> Option<u64> {
+        self.next_seq
+    }
+
+    pub fn stats(&self) -> UdpIngressStats {
+        self.stats
+    }
+}
+
+/// Where and how to receive the feed
+#[derive(Debug, Clone)]
+pub struct UdpIngressConfig {
+    /// Local address to bind (for multicast: usually `0.0.0.0:<group port>`)
+    pub bind: SocketAddr,
+    /// Multicast group to join and the local interface to join it on
+    pub multicast: Option<(Ipv4Addr, Ipv4Addr)>,
+    /// Longest `poll` blocks, so `run` notices its stop flag
+    pub read_timeout: Duration,
+    /// SO_RCVBUF override (the kernel default is often too small for bursts)
+    pub recv_buffer_bytes: Option<usize>,
+}
+
+impl UdpIngressConfig {
+    /// Receive datagrams sent to `bind`
+    pub fn unicast(bind: SocketAddr) -> Self {
+        Self {
+            bind,
+            multicast: None,
+            read_timeout: Duration::from_millis(10),
+            recv_buffer_bytes: None,
+        }
+    }
+
+    /// Join `group` on `interface` and receive datagrams sent to `group:port`
+    pub fn multicast(group: Ipv4Addr, port: u16, interface: Ipv4Addr) -> Self {
+        Self {
+            multicast: Some((group, interface)),
+            ..Self::unicast(SocketAddrV4::new(Ipv4Addr::UNSPECIFIED, port).into())
+        }
+    }
+}
+
+/// Feed handler reading datagrams from a UDP socket
+pub struct UdpIngress {
+    socket: UdpSocket,
+    decoder: FeedDecoder,
+    /// Receive buffer, allocated once at bind
+    buf: Vec<u8>,
+}
+
+impl UdpIngress {
+    /// Bind the socket (with SO_REUSEADDR, so several handlers can share a
+    /// multicast port) and join the group if configured
+    pub fn bind(config: &UdpIngressConfig) -> io::Result<Self> {
+        let socket = Socket::new(
+            Domain::for_address(config.bind),
+            Type::DGRAM,
+            Some(Protocol::UDP),
+        )?;
+        socket.set_reuse_address(true)?;
+        if let Some(bytes) = config.recv_buffer_bytes {
+            socket.set_recv_buffer_size(bytes)?;
+        }
+        socket.bind(&config.bind.into())?;
+
+        let socket: UdpSocket = socket.into();
+        if let Some((group, interface)) = config.multicast {
+            socket.join_multicast_v4(&group, &interface)?;
+        }
+        socket.set_read_timeout(Some(config.read_timeout))?;
+
+        Ok(Self {
+            socket,
+            decoder: FeedDecoder::new(),
+            buf: vec![0; u16::MAX as usize],
+        })
+    }
+
+    /// Bound address (useful after binding port 0)
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.socket.local_addr()
+    }
+
+    /// Wait up to the read timeout for one datagram and push its frames.
+    /// Returns the number pushed (0 on timeout).
+    pub fn poll<E: Emit<Transaction>>(&mut self, out: &mut E) -> io::Result<usize> {
+        let len = match self.socket.recv(&mut self.buf) {
+            Ok(len) => len,
+            Err(err)
+                if matches!(
+                    err.kind(),
+                    io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+                ) =>
+            {
+                return Ok(0)
+            }
+            Err(err) => return Err(err),
+        };
+        let ingress_ts_ns = tsc_to_ns(rdtsc());
+        Ok(self
+            .decoder
+            .on_datagram(&self.buf[..len], ingress_ts_ns, out))
+    }
+
+    pub fn stats(&self) -> UdpIngressStats {
+        self.decoder.stats()
+    }
+}
+
+impl IngressSource for UdpIngress {
+    fn run<E: Emit<Transaction>>(&mut self, out: &mut E, stop: &AtomicBool) -> io::Result<()> {
+        while !stop.load(Ordering::Acquire) {
+            self.poll(out)?;
+        }
+        Ok(())
+    }
+}
+
+/// Publishes transactions in the feed's datagram format (tests, tools,
+/// simulators)
+pub struct UdpFeedSender {
+    socket: UdpSocket,
+    dest: SocketAddr,
+    next_seq: u64,
+    buf: [u8; MAX_DATAGRAM_LEN],
+}
+
+impl UdpFeedSender {
+    /// Send to `dest` (unicast or a multicast group) from an ephemeral port.
+    /// Multicast is looped back so receivers on this host see it.
+    pub fn connect(dest: SocketAddr) -> io::Result<Self> {
+        let bind: SocketAddr = match dest {
+            SocketAddr::V4(_) => SocketAddrV4::new(Ipv4Addr::UNSPECIFIED, 0).into(),
+            SocketAddr::V6(_) => "[::]:0".parse().unwrap(),
+        };
+        let socket = UdpSocket::bind(bind)?;
+        if dest.ip().is_multicast() {
+            socket.set_multicast_loop_v4(true)?;
+        }
+        Ok(Self {
+            socket,
+            dest,
+            next_seq: 1,
+            buf: [0; MAX_DATAGRAM_LEN],
+        })
+    }
+
+    /// Send `txns` in datagrams of up to `MAX_DATAGRAM_FRAMES`, advancing
+    /// the sequence number
+    pub fn send(&mut self, txns: &[Transaction]) -> io::Result<()> {
+        for chunk in txns.chunks(MAX_DATAGRAM_FRAMES) {
+            let len = encode_datagram(self.next_seq, chunk, &mut self.buf).unwrap();
+            self.socket.send_to(&self.buf[..len], self.dest)?;
+            self.next_seq += chunk.len() as u64;
+        }
+        Ok(())
+    }
+
+    /// Skip `count` sequence numbers (simulates loss upstream)
+    pub fn skip(&mut self, count: u64) {
+        self.next_seq += count;
+    }
+
+    /// Sequence number of the next frame sent
+    pub fn next_seq(&self) -> u64 {
+        self.next_seq
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ring::RingBuffer;
+    use crate::tsc::init_tsc;
+
+    fn txns(ids: std::ops::Range<u64>) -> Vec<Transaction> {
+        ids.map(|id| Transaction::new_unchecked(id, 1_000_000, 10, (id % 2) as u8, 0))
+            .collect()
+    }
+
+    fn datagram(seq: u64, txns: &[Transaction]) -> Vec<u8> {
+        let mut buf = vec![0; DATAGRAM_HEADER_LEN + txns.len() * TXN_FRAME_LEN];
+        encode_datagram(seq, txns, &mut buf).unwrap();
+        buf
+    }
+
+    #[test]
+    fn test_decoder_sequencing() {
+        let ring = RingBuffer::<Transaction, 64>::new();
+        let mut decoder = FeedDecoder::new();
+
+        assert_eq!(
+            decoder.on_datagram(&datagram(10, &txns(0..3)), 7, &mut &ring),
+            3
+        );
+        assert_eq!(decoder.next_seq(), Some(13));
+        assert_eq!(ring.pop().unwrap().ingress_ts_ns, 7);
+
+        // Gap of two, then a retransmit overlapping what we have
+        decoder.on_datagram(&datagram(15, &txns(5..7)), 0, &mut &ring);
+        assert_eq!(
+            decoder.on_datagram(&datagram(16, &txns(6..9)), 0, &mut &ring),
+            2
+        );
+        decoder.on_datagram(&datagram(10, &txns(0..3)), 0, &mut &ring);
+        // Heartbeat
+        decoder.on_datagram(&datagram(19, &[]), 0, &mut &ring);
+
+        let stats = decoder.stats();
+        assert_eq!((stats.gaps, stats.missed, stats.duplicates), (1, 2, 4));
+        assert_eq!((stats.frames, stats.pushed, stats.malformed), (7, 7, 0));
+        assert_eq!(decoder.next_seq(), Some(19));
+    }
+
+    #[test]
+    fn test_decoder_resyncs_after_publisher_restart() {
+        let ring = RingBuffer::<Transaction, 64>::new();
+        let mut decoder = FeedDecoder::new().with_reset_window(100);
+
+        decoder.on_datagram(&datagram(500, &txns(0..3)), 0, &mut &ring);
+        // Within the window: an old retransmit, skipped
+        assert_eq!(decoder.on_datagram(&datagram(410, &txns(0..3)), 0, &mut &ring), 0);
+        // Restart from 1: delivered, and the sequence follows it
+        assert_eq!(decoder.on_datagram(&datagram(1, &txns(3..5)), 0, &mut &ring), 2);
+        assert_eq!(decoder.next_seq(), Some(3));
+        assert_eq!(decoder.on_datagram(&datagram(3, &txns(5..6)), 0, &mut &ring), 1);
+
+        let stats = decoder.stats();
+        assert_eq!((stats.resets, stats.duplicates, stats.gaps), (1, 3, 0));
+        assert_eq!((stats.pushed, decoder.next_seq()), (6, Some(4)));
+    }
+
+    #[test]
+    fn test_decoder_rejects_overflowing_sequence() {
+        let ring = RingBuffer::<Transaction, 64>::new();
+        let mut decoder = FeedDecoder::new();
+        decoder.on_datagram(&datagram(1, &txns(0..2)), 0, &mut &ring);
+
+        // seq + count would wrap: malformed, and the sequence is untouched
+        assert_eq!(decoder.on_datagram(&datagram(u64::MAX, &txns(2..4)), 0, &mut &ring), 0);
+        let stats = decoder.stats();
+        assert_eq!((stats.malformed, stats.gaps, stats.pushed), (1, 0, 2));
+        assert_eq!(decoder.next_seq(), Some(3));
+        assert_eq!(decoder.on_datagram(&datagram(3, &txns(2..3)), 0, &mut &ring), 1);
+    }
+
+    #[test]
+    fn test_decoder_rejects_malformed_and_counts_drops() {
+        let ring = RingBuffer::<Transaction, 2>::new();
+        let mut decoder = FeedDecoder::new();
+
+        // Short header, then a count that disagrees with the length
+        decoder.on_datagram(&[0; 5], 0, &mut &ring);
+        let mut bad = datagram(1, &txns(0..2));
+        bad.pop();
+        decoder.on_datagram(&bad, 0, &mut &ring);
+        assert_eq!(decoder.stats().malformed, 2);
+        assert_eq!(decoder.next_seq(), None);
+
+        // One corrupted frame is skipped, the rest still count
+        let mut corrupt = datagram(1, &txns(0..4));
+        corrupt[DATAGRAM_HEADER_LEN + 20] ^= 0xFF;
+        assert_eq!(decoder.on_datagram(&corrupt, 0, &mut &ring), 2);
+
+        let stats = decoder.stats();
+        assert_eq!(
+            (stats.malformed, stats.frames, stats.pushed, stats.dropped),
+            (3, 3, 2, 1)
+        );
+        assert_eq!(decoder.next_seq(), Some(5));
+    }
+
+    #[test]
+    fn test_udp_loopback() {
+        init_tsc();
+        let mut ingress =
+            UdpIngress::bind(&UdpIngressConfig::unicast("127.0.0.1:0".parse().unwrap())).unwrap();
+        let mut sender = UdpFeedSender::connect(ingress.local_addr().unwrap()).unwrap();
+        let ring = RingBuffer::<Transaction, 1024>::new();
+
+        sender.send(&txns(0..40)).unwrap();
+        sender.skip(5);
+        sender.send(&txns(45..50)).unwrap();
+
+        let mut received = 0;
+        for _ in 0..100 {
+            received += ingress.poll(&mut &ring).unwrap();
+            if received == 45 {
+                break;
+            }
+        }
+
+        let stats = ingress.stats();
+        assert_eq!(received, 45);
+        assert_eq!(stats.datagrams, 3);
+        assert_eq!((stats.gaps, stats.missed), (1, 5));
+        assert_eq!(ring.pop().unwrap().id, 0);
+    }
+
+    #[test]
+    fn test_udp_multicast_loopback() {
+        init_tsc();
+        let group = Ipv4Addr::new(239, 255, 42, 99);
+        let config = UdpIngressConfig::multicast(group, 0, Ipv4Addr::LOCALHOST);
+        let mut ingress = match UdpIngress::bind(&config) {
+            Ok(ingress) => ingress,
+            // No multicast route (some sandboxes): nothing to test
+            Err(_) => return,
+        };
+        let port = ingress.local_addr().unwrap().port();
+        let mut sender = UdpFeedSender::connect(SocketAddrV4::new(group, port).into()).unwrap();
+        socket2::SockRef::from(&sender.socket)
+            .set_multicast_if_v4(&Ipv4Addr::LOCALHOST)
+            .unwrap();
+        let ring = RingBuffer::<Transaction, 64>::new();
+
+        if sender.send(&txns(0..3)).is_err() {
+            return;
+        }
+        let mut received = 0;
+        for _ in 0..50 {
+            received += ingress.poll(&mut &ring).unwrap();
+            if received == 3 {
+                break;
+            }
+        }
+        assert_eq!(received, 3);
+    }
+}
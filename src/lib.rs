@@ -1,23 +1,63 @@
+pub mod async_ring;
 pub mod backoff;
 pub mod bundle;
+pub mod capture;
+pub mod config;
 pub mod errors;
+pub mod exact_book;
+pub mod flush;
 pub mod histogram;
 pub mod ingress;
+pub mod matching;
+pub mod mpmc;
 pub mod orderbook;
+pub mod pipeline;
+pub mod recorder;
 pub mod ring;
+pub mod stage;
 pub mod telemetry;
 pub mod tsc;
 pub mod types;
+pub mod wait;
+pub mod wire;
 
 // Re-export key types
+pub use async_ring::{AsyncNotifier, RingSink, RingStream};
 pub use backoff::Backoff;
-pub use bundle::{BundleBuilder, BundleFull, BUNDLE_TIMEOUT_NS};
-pub use errors::{BundleError, OrderBookError, TransactionError};
+pub use bundle::{
+    AddOutcome, BackpressureStats, BundleBuilder, BundleFull, BundleSink, Flushed, BUNDLE_TIMEOUT_NS,
+};
+pub use capture::{CaptureReader, CaptureRecord, CaptureWriter};
+pub use config::VeloxConfig;
+pub use errors::{
+    BundleError, CaptureError, ConfigError, OrderBookError, PcapError, PipelineError,
+    TransactionError, WireError,
+};
+pub use exact_book::ExactOrderBook;
+pub use flush::{AdaptiveTimeout, FlushPolicy, FlushReason, FlushTriggers};
 pub use histogram::LatencyHistogram;
-pub use ingress::{generate_burst, synthetic_ingress, SyntheticStats};
-pub use orderbook::OrderBook;
-pub use ring::RingBuffer;
+pub use ingress::model::{Arrivals, MarketGenerator, MarketModel};
+pub use ingress::pcap::{PcapFilter, PcapIngress, PcapReader, PcapStats};
+pub use ingress::replay::{ReplayIngress, ReplaySpeed, ReplayStats};
+pub use ingress::tcp::{StreamDecoder, TcpIngress, TcpSession, TcpSessionStats};
+pub use ingress::udp::{FeedDecoder, UdpFeedSender, UdpIngress, UdpIngressConfig, UdpIngressStats};
+pub use ingress::{
+    generate_burst, generate_burst_seeded, synthetic_ingress, synthetic_ingress_seeded, IngressSink,
+    IngressSource, SyntheticIngress, SyntheticStats,
+};
+pub use matching::MatchingEngine;
+pub use mpmc::MpmcRingBuffer;
+pub use orderbook::{BookQuery, OrderBook};
+pub use pipeline::{
+    BookConfig, OutputStreams, Pipeline, PipelineBuilder, PipelineStats, StageConfig,
+};
+pub use recorder::{Recordable, Recorder, RecorderConfig, RecorderStats, RecorderTap};
+pub use ring::{Reservation, RingBuffer};
+pub use stage::{run_stage, Discard, Emit, RingEmitter, Stage, StageContext, StageStats};
 pub use tsc::{
     calibrate_tsc, init_tsc, is_tsc_initialized, ns_to_tsc, rdtsc, spin_sleep_ns, tsc_to_ns,
+    Clock, ManualClock, TscClock,
 };
-pub use types::{Bundle, Transaction, BUNDLE_MAX};
+pub use types::{Bundle, Fill, OrderAction, Transaction, BUNDLE_MAX};
+pub use wait::{Notifier, Notify, WaitStrategy, Waiter};
+pub use wire::{FrameHeader, FrameKind, TXN_FRAME_LEN, WIRE_VERSION};
@@ -1,65 +1,161 @@
-use core_affinity::{set_for_current, CoreId};
-use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
-use std::sync::Arc;
+use clap::Parser;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::PathBuf;
+use std::process::ExitCode;
+use std::sync::atomic::Ordering;
 use std::thread;
 use std::time::{Duration, Instant};
 use velox_engine::*;
 
-mod telemetry;
+/// How often the main loop checks whether a replay has finished
+const RUN_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Lock-free HFT transaction pipeline
+///
+/// Settings are read from an optional TOML file (see velox.toml); flags
+/// override the file, and OTLP_ENDPOINT overrides the file's endpoint.
+#[derive(Parser, Debug)]
+#[command(version)]
+struct Cli {
+    /// TOML configuration file
+    #[arg(short, long)]
+    config: Option<PathBuf>,
+    /// Synthetic ingress rate (transactions per second)
+    #[arg(long)]
+    rate_hz: Option<f64>,
+    /// Run time in seconds (replays run until the file ends instead)
+    #[arg(long)]
+    duration_secs: Option<u64>,
+    /// Cores for ingress,orderbook,bundle,output (e.g. 0,1,2,3)
+    #[arg(long, value_delimiter = ',', num_args = 4)]
+    cores: Option<Vec<usize>>,
+    /// Do not pin stage threads to cores
+    #[arg(long)]
+    no_pin: bool,
+    /// Idle wait strategy for every consuming stage (busy_spin, backoff, park)
+    #[arg(long, value_parser = parse_wait)]
+    wait: Option<WaitStrategy>,
+    /// Flush bundles at this many transactions
+    #[arg(long)]
+    bundle_max_size: Option<usize>,
+    /// Flush bundles this long after their first transaction
+    #[arg(long)]
+    bundle_timeout_ns: Option<u64>,
+    /// OTLP gRPC endpoint
+    #[arg(long)]
+    otlp_endpoint: Option<String>,
+    /// Disable OpenTelemetry export
+    #[arg(long)]
+    no_telemetry: bool,
+    /// Replay a capture file instead of generating synthetic ingress
+    #[arg(long)]
+    replay: Option<PathBuf>,
+    /// Replay the UDP feed in a pcap or pcapng file instead
+    #[arg(long, conflicts_with = "replay")]
+    pcap: Option<PathBuf>,
+    /// Only replay pcap datagrams sent to this UDP port
+    #[arg(long, requires = "pcap")]
+    pcap_port: Option<u16>,
+    /// Only replay pcap datagrams sent to this multicast group
+    #[arg(long, requires = "pcap")]
+    pcap_group: Option<std::net::Ipv4Addr>,
+    /// Replay speed: a multiplier of the recorded pace (1 = original) or max
+    #[arg(long, value_parser = parse_replay_speed, default_value = "1")]
+    replay_speed: ReplaySpeed,
+    /// Record ingress transactions and output bundles to capture segments
+    /// in this directory
+    #[arg(long)]
+    record: Option<PathBuf>,
+    /// Validate the configuration and exit
+    #[arg(long)]
+    check: bool,
+}
 
-/// Pipeline configuration
-const INGRESS_RATE_HZ: f64 = 100_000.0; // 100k txn/sec target
-const RUN_DURATION_SECS: u64 = 300; // Run for 5 minutes (for dashboard demo)
+fn parse_replay_speed(s: &str) -> Result<ReplaySpeed, String> {
+    match s {
+        "max" => Ok(ReplaySpeed::AsFastAsPossible),
+        "1" => Ok(ReplaySpeed::Original),
+        _ => match s.parse::<f64>() {
+            Ok(factor) if factor.is_finite() && factor > 0.0 => Ok(ReplaySpeed::Multiplier(factor)),
+            _ => Err(format!("invalid replay speed {:?} (expected a positive number or max)", s)),
+        },
+    }
+}
 
-/// Statistics tracker
-struct Stats {
-    ingress_generated: AtomicU64,
-    ingress_pushed: AtomicU64,
-    ingress_dropped: AtomicU64,
-    orderbook_processed: AtomicU64,
-    orderbook_timeout: AtomicU64,
-    bundle_flushed: AtomicU64,
-    output_received: AtomicU64,
+fn parse_wait(s: &str) -> Result<WaitStrategy, String> {
+    match s {
+        "busy_spin" => Ok(WaitStrategy::BusySpin),
+        "backoff" => Ok(WaitStrategy::Backoff),
+        "park" => Ok(WaitStrategy::Park),
+        _ => Err(format!("unknown wait strategy {:?} (expected busy_spin, backoff or park)", s)),
+    }
 }
 
-impl Stats {
-    fn new() -> Self {
-        Self {
-            ingress_generated: AtomicU64::new(0),
-            ingress_pushed: AtomicU64::new(0),
-            ingress_dropped: AtomicU64::new(0),
-            orderbook_processed: AtomicU64::new(0),
-            orderbook_timeout: AtomicU64::new(0),
-            bundle_flushed: AtomicU64::new(0),
-            output_received: AtomicU64::new(0),
-        }
+/// Layer file, environment and flags over the defaults, then validate
+fn load_config(cli: &Cli) -> Result<VeloxConfig, ConfigError> {
+    let mut config = match &cli.config {
+        Some(path) => VeloxConfig::load(path)?,
+        None => VeloxConfig::default(),
+    };
+
+    if let Ok(endpoint) = std::env::var("OTLP_ENDPOINT") {
+        config.telemetry.endpoint = endpoint;
     }
 
-    fn print_summary(&self) {
-        println!("\n=== Pipeline Statistics ===");
-        println!(
-            "Ingress:   generated={} pushed={} dropped={}",
-            self.ingress_generated.load(Ordering::Relaxed),
-            self.ingress_pushed.load(Ordering::Relaxed),
-            self.ingress_dropped.load(Ordering::Relaxed),
-        );
-        println!(
-            "OrderBook: processed={} timeout={}",
-            self.orderbook_processed.load(Ordering::Relaxed),
-            self.orderbook_timeout.load(Ordering::Relaxed),
-        );
-        println!(
-            "Bundle:    flushed={}",
-            self.bundle_flushed.load(Ordering::Relaxed),
-        );
-        println!(
-            "Output:    received={}",
-            self.output_received.load(Ordering::Relaxed),
-        );
+    if let Some(rate_hz) = cli.rate_hz {
+        config.ingress.rate_hz = rate_hz;
+    }
+    if let Some(duration_secs) = cli.duration_secs {
+        config.run.duration_secs = duration_secs;
     }
+    if let Some(cores) = &cli.cores {
+        for (stage, &core) in config.stages.all_mut().into_iter().zip(cores) {
+            stage.core = core;
+        }
+    }
+    if cli.no_pin {
+        for stage in config.stages.all_mut() {
+            stage.pinned = false;
+        }
+    }
+    if let Some(wait) = cli.wait {
+        // Ingress never waits
+        for stage in config.stages.all_mut().into_iter().skip(1) {
+            stage.wait = wait;
+        }
+    }
+    if let Some(max_size) = cli.bundle_max_size {
+        config.bundle.max_size = max_size;
+    }
+    if let Some(timeout_ns) = cli.bundle_timeout_ns {
+        config.bundle.timeout_ns = timeout_ns;
+    }
+    if let Some(endpoint) = &cli.otlp_endpoint {
+        config.telemetry.endpoint = endpoint.clone();
+    }
+    if cli.no_telemetry {
+        config.telemetry.enabled = false;
+    }
+
+    config.validate()?;
+    Ok(config)
 }
 
-fn main() {
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    let config = match load_config(&cli) {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            return ExitCode::from(2);
+        }
+    };
+    if cli.check {
+        println!("Configuration OK");
+        return ExitCode::SUCCESS;
+    }
+
     // CRITICAL: Initialize TSC FIRST, before any output or thread creation
     // This prevents race conditions where threads might call rdtsc() before calibration
     init_tsc();
@@ -81,494 +177,178 @@ fn main() {
         .build()
         .expect("Failed to build Tokio runtime for telemetry");
 
-    let otlp_endpoint =
-        std::env::var("OTLP_ENDPOINT").unwrap_or_else(|_| "http://localhost:4317".to_string());
+    let otlp_endpoint = &config.telemetry.endpoint;
 
     // Enter the runtime context for initialization
     let _guard = _telemetry_rt.enter();
 
-    match telemetry::init_telemetry("velox-engine", &otlp_endpoint) {
-        Ok(_) => {
-            println!("📊 Telemetry exporter configured: {}", otlp_endpoint);
-            println!("   (Metrics will export if collector is running)");
-        }
-        Err(_) => {
-            println!("⚠ Telemetry disabled (initialization failed)");
-            println!("  To enable: docker compose up -d && OTLP_ENDPOINT=http://localhost:4317 cargo run --release");
+    if !config.telemetry.enabled {
+        println!("Telemetry disabled by configuration");
+    } else {
+        match telemetry::init_telemetry(&config.telemetry.service_name, otlp_endpoint) {
+            Ok(_) => {
+                println!("📊 Telemetry exporter configured: {}", otlp_endpoint);
+                println!("   (Metrics will export if collector is running)");
+            }
+            Err(_) => {
+                println!("⚠ Telemetry disabled (initialization failed)");
+                println!("  To enable: docker compose up -d && OTLP_ENDPOINT=http://localhost:4317 cargo run --release");
+            }
         }
     }
 
     drop(_guard); // Exit runtime context
     println!();
 
-    // Create ring buffers
-    let ingress_ring = Arc::new(RingBuffer::<Transaction, 4096>::new());
-    let bundle_ring = Arc::new(RingBuffer::<Transaction, 4096>::new());
-    let output_ring = Arc::new(RingBuffer::<Bundle, 1024>::new());
-
-    // Shared statistics
-    let stats = Arc::new(Stats::new());
-
-    // Latency histogram
-    let histogram = Arc::new(LatencyHistogram::new());
-
-    // Shutdown signal
-    let shutdown = Arc::new(AtomicBool::new(false));
-
-    // Thread handles
-    let mut handles = vec![];
-
-    // Note: _telemetry_rt stays in scope to keep Tokio runtime alive for metric exports
-
-    // Core 0: Ingress thread
-    {
-        let ring = Arc::clone(&ingress_ring);
-        let stats = Arc::clone(&stats);
-        let shutdown = Arc::clone(&shutdown);
-
-        let handle = thread::Builder::new()
-            .name("ingress".to_string())
-            .spawn(move || {
-                if let Some(core_id) = (CoreId { id: 0 }).into() {
-                    set_for_current(core_id);
-                }
-
-                ingress_worker(&ring, &stats, &shutdown);
-            })
-            .expect("Failed to spawn ingress thread");
-
-        handles.push(handle);
-    }
-
-    // Core 1: OrderBook thread
-    {
-        let input = Arc::clone(&ingress_ring);
-        let output = Arc::clone(&bundle_ring);
-        let stats = Arc::clone(&stats);
-        let shutdown = Arc::clone(&shutdown);
-
-        let handle = thread::Builder::new()
-            .name("orderbook".to_string())
-            .spawn(move || {
-                if let Some(core_id) = (CoreId { id: 1 }).into() {
-                    set_for_current(core_id);
-                }
-
-                orderbook_worker(&input, &output, &stats, &shutdown);
-            })
-            .expect("Failed to spawn orderbook thread");
-
-        handles.push(handle);
-    }
-
-    // Core 2: Bundle thread
-    {
-        let input = Arc::clone(&bundle_ring);
-        let output = Arc::clone(&output_ring);
-        let stats = Arc::clone(&stats);
-        let shutdown = Arc::clone(&shutdown);
-
-        let handle = thread::Builder::new()
-            .name("bundle".to_string())
-            .spawn(move || {
-                if let Some(core_id) = (CoreId { id: 2 }).into() {
-                    set_for_current(core_id);
-                }
-
-                bundle_worker(&input, &output, &stats, &shutdown);
-            })
-            .expect("Failed to spawn bundle thread");
-
-        handles.push(handle);
-    }
-
-    // Core 3: Output thread
-    {
-        let ring = Arc::clone(&output_ring);
-        let stats = Arc::clone(&stats);
-        let histogram = Arc::clone(&histogram);
-        let shutdown = Arc::clone(&shutdown);
-
-        let handle = thread::Builder::new()
-            .name("output".to_string())
-            .spawn(move || {
-                if let Some(core_id) = (CoreId { id: 3 }).into() {
-                    set_for_current(core_id);
-                }
-
-                output_worker(&ring, &stats, &histogram, &shutdown);
-            })
-            .expect("Failed to spawn output thread");
-
-        handles.push(handle);
-    }
-
-    // Monitor thread (prints stats periodically)
-    {
-        let stats = Arc::clone(&stats);
-        let shutdown = Arc::clone(&shutdown);
-
-        let handle = thread::Builder::new()
-            .name("monitor".to_string())
-            .spawn(move || {
-                let start = Instant::now();
-                while !shutdown.load(Ordering::Relaxed) {
-                    thread::sleep(Duration::from_secs(1));
-                    let elapsed = start.elapsed().as_secs();
-
-                    let ingress = stats.ingress_pushed.load(Ordering::Relaxed);
-                    let orderbook = stats.orderbook_processed.load(Ordering::Relaxed);
-                    let bundles = stats.bundle_flushed.load(Ordering::Relaxed);
-                    let output = stats.output_received.load(Ordering::Relaxed);
-
-                    println!(
-                        "[{:3}s] ingress={} orderbook={} bundles={} output={}",
-                        elapsed, ingress, orderbook, bundles, output
-                    );
-                }
-            })
-            .expect("Failed to spawn monitor thread");
-
-        handles.push(handle);
-    }
-
-    // Run for specified duration
-    println!("Starting pipeline for {} seconds...", RUN_DURATION_SECS);
-    println!("Target rate: {:.0} txn/sec", INGRESS_RATE_HZ);
-    println!();
-
-    thread::sleep(Duration::from_secs(RUN_DURATION_SECS));
-
-    // Signal shutdown
-    println!("\nShutting down gracefully...");
-    shutdown.store(true, Ordering::Relaxed);
-
-    // Give threads time to finish their current work
-    thread::sleep(Duration::from_millis(50));
-
-    // Drain pipeline to avoid data loss
-    println!("Draining buffers...");
-    let drained = drain_pipeline(&ingress_ring, &bundle_ring, &output_ring, &stats);
-    println!("Drained: {} transactions, {} bundles", drained.0, drained.1);
-
-    // Wait for all threads
-    println!("Joining threads...");
-    for handle in handles {
-        handle.join().expect("Thread panicked");
-    }
-
-    // Print final statistics
-    stats.print_summary();
-    histogram.print_summary();
-
-    // Shutdown telemetry and flush pending metrics
-    telemetry::shutdown_telemetry();
-
-    println!("\nPipeline shutdown complete");
-}
-
-/// Drain remaining items from pipeline buffers to avoid data loss on shutdown
-fn drain_pipeline(
-    ingress_ring: &RingBuffer<Transaction, 4096>,
-    bundle_ring: &RingBuffer<Transaction, 4096>,
-    output_ring: &RingBuffer<Bundle, 1024>,
-    stats: &Stats,
-) -> (usize, usize) {
-    let book = OrderBook::new();
-    let mut builder = BundleBuilder::new();
-
-    let mut drained_txns = 0;
-    let mut drained_bundles = 0;
-
-    // Step 1: Process remaining transactions in ingress ring through orderbook
-    while let Some(txn) = ingress_ring.pop() {
-        let delta = if txn.is_bid() {
-            txn.size as i64
-        } else {
-            -(txn.size as i64)
-        };
-
-        if txn.is_bid() {
-            let _ = book.update_bid(txn.price, delta, txn.ingress_ts_ns);
-        } else {
-            let _ = book.update_ask(txn.price, delta, txn.ingress_ts_ns);
+    let mut builder = config.pipeline_builder();
+    if let Some(path) = &cli.replay {
+        match ReplayIngress::open(path, cli.replay_speed) {
+            Ok(source) => builder = builder.ingress_source(source),
+            Err(e) => {
+                eprintln!("error: {}: {}", path.display(), e);
+                return ExitCode::from(2);
+            }
         }
-
-        stats.orderbook_processed.fetch_add(1, Ordering::Relaxed);
-        drained_txns += 1;
-
-        // Push to bundle ring
-        let _ = bundle_ring.push(txn);
-    }
-
-    // Step 2: Process remaining transactions in bundle ring
-    while let Some(txn) = bundle_ring.pop() {
-        let _ = builder.add(txn, output_ring);
-        drained_txns += 1;
     }
-
-    // Step 3: Flush partial bundle if any
-    if !builder.is_empty() {
-        if builder.force_flush(output_ring).is_ok() {
-            stats.bundle_flushed.fetch_add(1, Ordering::Relaxed);
-            drained_bundles += 1;
+    if let Some(path) = &cli.pcap {
+        let filter = PcapFilter {
+            port: cli.pcap_port,
+            group: cli.pcap_group,
+        };
+        match PcapIngress::open(path, filter, cli.replay_speed) {
+            Ok(source) => builder = builder.ingress_source(source),
+            Err(e) => {
+                eprintln!("error: {}: {}", path.display(), e);
+                return ExitCode::from(2);
+            }
         }
     }
 
-    // Step 4: Process remaining bundles in output ring
-    while let Some(_bundle) = output_ring.pop() {
-        stats.output_received.fetch_add(1, Ordering::Relaxed);
-        drained_bundles += 1;
-    }
-
-    (drained_txns, drained_bundles)
-}
-
-/// Ingress worker: generates synthetic transactions
-fn ingress_worker(ring: &RingBuffer<Transaction, 4096>, stats: &Stats, shutdown: &AtomicBool) {
-    use rand::Rng;
-
-    let mut rng = rand::thread_rng();
-    let lambda = INGRESS_RATE_HZ;
-    let mut next_id = 0u64;
-    let mut sample_counter = 0u64;
-
-    while !shutdown.load(Ordering::Relaxed) {
-        let start_tsc = rdtsc();
-        let txn = Transaction::new_unchecked(
-            next_id,
-            rng.gen_range(900000..1100000),
-            rng.gen_range(1..1000),
-            rng.gen_range(0..2) as u8,
-            tsc_to_ns(rdtsc()),
+    let mut recorders = None;
+    if let Some(dir) = &cli.record {
+        let started = Recorder::<Transaction>::start(RecorderConfig::new(dir, "ingress")).and_then(
+            |ingress| Ok((ingress, Recorder::<Bundle>::start(RecorderConfig::new(dir, "output"))?)),
         );
-
-        stats.ingress_generated.fetch_add(1, Ordering::Relaxed);
-
-        match ring.push(txn) {
-            Ok(_) => {
-                stats.ingress_pushed.fetch_add(1, Ordering::Relaxed);
-
-                // Instrument AFTER successful push
-                let latency_ns = tsc_to_ns(rdtsc()) - tsc_to_ns(start_tsc);
-                let latency_us = latency_ns as f64 / 1000.0;
-                telemetry::record_transaction_processed("ingress", txn.id, latency_us);
-
-                // Sample ring utilization every 1000 transactions
-                sample_counter += 1;
-                if sample_counter % 1000 == 0 {
-                    let utilization = (ring.len() as f64 / 4096.0) * 100.0;
-                    telemetry::record_ring_utilization("ingress_to_orderbook", utilization);
-                }
-
-                next_id += 1;
+        match started {
+            Ok(((ingress, ingress_tap), (output, output_tap))) => {
+                builder = builder.record_ingress(ingress_tap).record_output(output_tap);
+                recorders = Some((ingress, output));
             }
-            Err(_) => {
-                stats.ingress_dropped.fetch_add(1, Ordering::Relaxed);
-                telemetry::record_ingress_dropped();
+            Err(e) => {
+                eprintln!("error: {}: {}", dir.display(), e);
+                return ExitCode::FAILURE;
             }
         }
-
-        // Poisson inter-arrival delay using exponential distribution
-        let u: f64 = rng.gen();
-        // Avoid u == 0.0 which would cause -inf
-        let u = u.max(f64::EPSILON);
-        let delay_ns = ((-u.ln()) / lambda * 1_000_000_000.0) as u64;
-        if delay_ns > 0 {
-            spin_sleep_ns(delay_ns);
-        }
     }
-}
-
-/// OrderBook worker: processes transactions and updates order book
-fn orderbook_worker(
-    input: &RingBuffer<Transaction, 4096>,
-    output: &RingBuffer<Transaction, 4096>,
-    stats: &Stats,
-    shutdown: &AtomicBool,
-) {
-    let book = OrderBook::new();
-    let mut backoff = Backoff::new();
-    let mut sample_counter = 0u64;
-
-    while !shutdown.load(Ordering::Relaxed) {
-        match input.pop() {
-            Some(txn) => {
-                // Reset backoff on successful work
-                backoff.reset();
-
-                let start_tsc = rdtsc();
-
-                // Update order book
-                let delta = if txn.is_bid() {
-                    txn.size as i64
-                } else {
-                    -(txn.size as i64)
-                };
-
-                let result = if txn.is_bid() {
-                    book.update_bid(txn.price, delta, txn.ingress_ts_ns)
-                } else {
-                    book.update_ask(txn.price, delta, txn.ingress_ts_ns)
-                };
 
-                match result {
-                    Ok(_) => {
-                        stats.orderbook_processed.fetch_add(1, Ordering::Relaxed);
-
-                        // Instrument AFTER successful processing
-                        let latency_ns = tsc_to_ns(rdtsc()) - tsc_to_ns(start_tsc);
-                        let latency_us = latency_ns as f64 / 1000.0;
-                        telemetry::record_transaction_processed("orderbook", txn.id, latency_us);
+    let mut pipeline = match builder.start() {
+        Ok(pipeline) => pipeline,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            return ExitCode::FAILURE;
+        }
+    };
 
-                        // Sample ring utilization and orderbook depth every 1000 transactions
-                        sample_counter += 1;
-                        if sample_counter % 1000 == 0 {
-                            let utilization = (output.len() as f64 / 4096.0) * 100.0;
-                            telemetry::record_ring_utilization("orderbook_to_bundle", utilization);
-                            telemetry::record_orderbook_depth("bid", book.depth_bid());
-                            telemetry::record_orderbook_depth("ask", book.depth_ask());
-                        }
+    // Note: _telemetry_rt stays in scope to keep Tokio runtime alive for metric exports
 
-                        // Forward to bundle builder
-                        let _ = output.push(txn); // Drop on full
-                    }
-                    Err(_) => {
-                        stats.orderbook_timeout.fetch_add(1, Ordering::Relaxed);
-                        telemetry::record_orderbook_timeout();
-                    }
-                }
-            }
-            None => {
-                // Ring empty, adaptive backoff
-                backoff.snooze();
-            }
+    // Synthetic ingress runs for the configured duration; a replay runs
+    // until its source has delivered the whole file
+    let replay = cli.replay.as_ref().or(cli.pcap.as_ref());
+    match replay {
+        Some(path) => {
+            println!("Starting pipeline until the capture ends...");
+            println!("Replaying {} ({:?})", path.display(), cli.replay_speed);
+        }
+        None => {
+            println!("Starting pipeline for {} seconds...", config.run.duration_secs);
+            println!("Target rate: {:.0} txn/sec", config.ingress.rate_hz);
         }
     }
-}
-
-/// Bundle worker: accumulates transactions into bundles
-fn bundle_worker(
-    input: &RingBuffer<Transaction, 4096>,
-    output: &RingBuffer<Bundle, 1024>,
-    stats: &Stats,
-    shutdown: &AtomicBool,
-) {
-    let mut builder = BundleBuilder::new();
-    let mut backoff = Backoff::new();
-    let mut sample_counter = 0u64;
-
-    while !shutdown.load(Ordering::Relaxed) {
-        match input.pop() {
-            Some(txn) => {
-                // Reset backoff on successful work
-                backoff.reset();
-
-                let start_tsc = rdtsc();
-                let prev_len = builder.len();
+    println!();
 
-                if let Ok(_) = builder.add(txn, output) {
-                    // Instrument AFTER successful add
-                    let latency_ns = tsc_to_ns(rdtsc()) - tsc_to_ns(start_tsc);
-                    let latency_us = latency_ns as f64 / 1000.0;
-                    telemetry::record_transaction_processed("bundle", txn.id, latency_us);
+    // Print stats periodically until the run completes
+    let start = Instant::now();
+    let duration = Duration::from_secs(config.run.duration_secs);
+    let interval = Duration::from_secs(config.run.stats_interval_secs);
+    let mut next_report = interval;
+    loop {
+        let done = match replay {
+            Some(_) => pipeline.ingress_finished(),
+            None => start.elapsed() >= duration,
+        };
+        if done {
+            break;
+        }
+        thread::sleep(RUN_POLL_INTERVAL.min(next_report.saturating_sub(start.elapsed())));
+        if start.elapsed() < next_report {
+            continue;
+        }
+        next_report += interval;
+        let elapsed = start.elapsed().as_secs();
+        let stats = pipeline.stats();
 
-                    // Check if bundle was flushed (count reset to 0 or 1)
-                    if builder.len() <= 1 && prev_len > 1 {
-                        stats.bundle_flushed.fetch_add(1, Ordering::Relaxed);
-                        // Size-triggered flush (hit BUNDLE_MAX limit)
-                        telemetry::record_bundle_flushed(BUNDLE_MAX as u32, "size");
-                    }
+        let ingress = stats.ingress_pushed.load(Ordering::Relaxed);
+        let orderbook = stats.orderbook_processed.load(Ordering::Relaxed);
+        let trades = stats.trades_executed.load(Ordering::Relaxed);
+        let bundles = stats.bundle_flushed.load(Ordering::Relaxed);
+        let output = stats.output_received.load(Ordering::Relaxed);
 
-                    // Sample ring utilization every 1000 transactions
-                    sample_counter += 1;
-                    if sample_counter % 1000 == 0 {
-                        let utilization = (output.len() as f64 / 1024.0) * 100.0;
-                        telemetry::record_ring_utilization("bundle_to_output", utilization);
-                    }
-                }
-            }
-            None => {
-                // Check timeout flush even when idle
-                if builder.should_flush_timeout() {
-                    let bundle_size = builder.len() as u32;
-                    if builder.force_flush(output).is_ok() && bundle_size > 0 {
-                        stats.bundle_flushed.fetch_add(1, Ordering::Relaxed);
-                        telemetry::record_bundle_flushed(bundle_size, "timeout");
-                    }
-                }
-                backoff.snooze();
-            }
-        }
+        println!(
+            "[{:3}s] ingress={} orderbook={} trades={} bundles={} output={}",
+            elapsed, ingress, orderbook, trades, bundles, output
+        );
     }
 
-    // Flush remaining transactions
-    let bundle_size = builder.len() as u32;
-    if builder.force_flush(output).is_ok() && bundle_size > 0 {
-        telemetry::record_bundle_flushed(bundle_size, "shutdown");
+    // Stop stages upstream-first; each drains its input before exiting
+    println!("\nShutting down gracefully...");
+    let shutdown = pipeline.shutdown();
+    if let Err(e) = &shutdown {
+        eprintln!("error: {}", e);
     }
-    let _ = builder.force_flush(output);
-}
-
-/// Output worker: simulates bundle submission
-fn output_worker(
-    ring: &RingBuffer<Bundle, 1024>,
-    stats: &Stats,
-    histogram: &LatencyHistogram,
-    shutdown: &AtomicBool,
-) {
-    let mut backoff = Backoff::new();
-
-    while !shutdown.load(Ordering::Relaxed) {
-        match ring.pop() {
-            Some(bundle) => {
-                // Reset backoff on successful work
-                backoff.reset();
-
-                let start_tsc = rdtsc();
-                stats.output_received.fetch_add(1, Ordering::Relaxed);
-
-                // Calculate E2E latency from first transaction's timestamp
-                let now_ns = tsc_to_ns(rdtsc());
-                let first_txn_ns = bundle.transactions[0].ingress_ts_ns;
-                let e2e_latency_ns = now_ns - first_txn_ns;
-                let e2e_latency_us = e2e_latency_ns as f64 / 1000.0;
 
-                // Record E2E latency
-                telemetry::record_e2e_latency(e2e_latency_us, bundle.transactions[0].id);
-
-                // Record output stage latency
-                let stage_latency_ns = tsc_to_ns(rdtsc()) - tsc_to_ns(start_tsc);
-                let stage_latency_us = stage_latency_ns as f64 / 1000.0;
-                telemetry::record_transaction_processed(
-                    "output",
-                    bundle.transactions[0].id,
-                    stage_latency_us,
-                );
-
-                stats.output_received.fetch_add(1, Ordering::Relaxed);
+    // Print final statistics
+    pipeline.stats().print_summary();
+    pipeline.histogram().print_summary();
 
-                // Record latency for each transaction in bundle
-                let egress_ts_ns = tsc_to_ns(rdtsc());
-                for i in 0..bundle.count as usize {
-                    let latency_ns =
-                        egress_ts_ns.saturating_sub(bundle.transactions[i].ingress_ts_ns);
-                    histogram.record(latency_ns);
-                }
+    if let Some(source) = pipeline.take_ingress_source::<ReplayIngress<BufReader<File>>>() {
+        let stats = source.stats();
+        println!(
+            "Replayed {} records: {} pushed, {} skipped, {} stalls, {} truncated at end",
+            stats.records, stats.pushed, stats.skipped, stats.stalls, stats.truncated_tail
+        );
+    }
+    if let Some(source) = pipeline.take_ingress_source::<PcapIngress<BufReader<File>>>() {
+        let stats = source.stats();
+        println!(
+            "Replayed {} packets: {} matched, {} gaps ({} missed), {} duplicates, {} malformed, {} truncated at end",
+            stats.packets,
+            stats.matched,
+            stats.feed.gaps,
+            stats.feed.missed,
+            stats.feed.duplicates,
+            stats.feed.malformed,
+            stats.truncated_tail
+        );
+    }
 
-                // Simulate bundle submission (no-op for now)
-                // In production: submit to Solana RPC or Jito
-                std::hint::black_box(&bundle);
-            }
-            None => {
-                // Adaptive backoff when idle
-                backoff.snooze();
+    if let Some((ingress, output)) = recorders {
+        for (name, recorder) in [("ingress", ingress.finish()), ("output", output.finish())] {
+            match recorder {
+                Ok(stats) => println!(
+                    "Recorded {}: {} records in {} segments ({} bytes, {} dropped)",
+                    name, stats.recorded, stats.segments, stats.bytes, stats.dropped
+                ),
+                Err(e) => eprintln!("error: recording {}: {}", name, e),
             }
         }
     }
 
-    // Drain remaining bundles
-    while let Some(_) = ring.pop() {
-        stats.output_received.fetch_add(1, Ordering::Relaxed);
+    // Shutdown telemetry and flush pending metrics
+    telemetry::shutdown_telemetry();
+
+    println!("\nPipeline shutdown complete");
+    match shutdown {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(_) => ExitCode::FAILURE,
     }
 }
@@ -0,0 +1,621 @@
+use std::collections::HashMap;
+use crate::errors::{OrderBookError, TransactionError};
+use crate::exact_book::ExactOrderBook;
+use crate::types::{Fill, OrderAction, Transaction};
+
+/// Sentinel slot index for "no order" in the intrusive FIFO lists
+const NIL: u32 = u32::MAX;
+
+/// A resting order stored in the preallocated pool.
+/// Orders at the same price are doubly linked in arrival order so a cancel
+/// can unlink from the middle of a level.
+#[derive(Clone, Copy)]
+struct RestingOrder {
+    id: u64,
+    price: i64,
+    remaining: u32,
+    side: u8,
+    /// Previous order at the same level
+    prev: u32,
+    /// Next order at the same level, or next free slot when unused
+    next: u32,
+}
+
+impl RestingOrder {
+    const EMPTY: Self = Self {
+        id: 0,
+        price: 0,
+        remaining: 0,
+        side: 0,
+        prev: NIL,
+        next: NIL,
+    };
+}
+
+/// FIFO queue of resting orders at one price level
+#[derive(Clone, Copy)]
+struct LevelQueue {
+    head: u32,
+    tail: u32,
+}
+
+impl LevelQueue {
+    const EMPTY: Self = Self { head: NIL, tail: NIL };
+}
+
+/// Price-time priority matching engine.
+///
+/// Tracks individual resting orders keyed by `Transaction::id` in FIFO
+/// queues per exact price level, and keeps an `ExactOrderBook` in sync with
+/// the aggregate resting quantity so `BookQuery` analytics see the same book.
+///
+/// An incoming bid matches against asks at or below its price (and vice
+/// versa), best price first and oldest order first within a price. Every
+/// execution is reported as a `Fill` at the maker's price; any unfilled
+/// remainder rests at the order's limit price.
+///
+/// Single-threaded: owned by one pipeline stage. Order slots and level
+/// queues are allocated once at construction, so `submit` does not allocate
+/// while the id index stays within `max_orders`.
+pub struct MatchingEngine {
+    /// Aggregate resting quantity per price (bids and asks positive)
+    book: ExactOrderBook,
+    /// Preallocated order slots
+    orders: Box<[RestingOrder]>,
+    /// Head of the free-slot list
+    free_head: u32,
+    /// Per-level FIFO queues, indexed like `book`
+    bid_queues: Box<[LevelQueue]>,
+    ask_queues: Box<[LevelQueue]>,
+    /// Order id -> slot
+    index: HashMap<u64, u32>,
+    /// Resting order counts per side
+    resting_bids: u64,
+    resting_asks: u64,
+}
+
+impl MatchingEngine {
+    /// Create an engine over the price window `[min_price, min_price + (levels - 1) * tick_size]`
+    /// with room for `max_orders` resting orders.
+    ///
+    /// # Panics
+    /// Panics on an invalid price window (see `ExactOrderBook::new`) or if
+    /// `max_orders` is 0 or does not fit the slot index.
+    pub fn new(min_price: i64, tick_size: i64, levels: usize, max_orders: usize) -> Self {
+        assert!(
+            max_orders > 0 && max_orders < NIL as usize,
+            "MatchingEngine max_orders must be in 1..u32::MAX"
+        );
+
+        // Thread every slot onto the free list
+        let orders: Box<[RestingOrder]> = (0..max_orders)
+            .map(|i| RestingOrder {
+                next: if i + 1 < max_orders { (i + 1) as u32 } else { NIL },
+                ..RestingOrder::EMPTY
+            })
+            .collect();
+
+        Self {
+            book: ExactOrderBook::new(min_price, tick_size, levels),
+            orders,
+            free_head: 0,
+            bid_queues: vec![LevelQueue::EMPTY; levels].into_boxed_slice(),
+            ask_queues: vec![LevelQueue::EMPTY; levels].into_boxed_slice(),
+            index: HashMap::with_capacity(max_orders),
+            resting_bids: 0,
+            resting_asks: 0,
+        }
+    }
+
+    /// Submit a transaction according to its `OrderAction`.
+    /// `on_fill` is called once per execution, in match order.
+    ///
+    /// - `New`: match against the opposite side, then rest any remainder
+    /// - `Cancel`: remove the resting order with this id
+    /// - `Modify`: set the resting order's size; shrinking keeps time
+    ///   priority, growing moves it to the back of its level
+    /// - `Replace`: cancel the resting order, then enter it as `New` at the
+    ///   transaction's price and size (loses time priority, may cross)
+    ///
+    /// Returns the quantity filled.
+    ///
+    /// # Errors
+    /// - `PriceOutOfRange` / `OffTick`: price is not a level of this book
+    /// - `DuplicateOrderId`: a `New` order's id is already resting
+    /// - `OrderPoolFull`: no slot is free to rest a remainder
+    /// - `InvalidTransaction(UnknownAction)`: action byte is not recognised
+    /// - `InvalidTransaction(UnknownOrderId)`: cancel/modify/replace target
+    ///   is not resting
+    ///
+    /// A rejected transaction has no effect on the book. `OrderPoolFull` can
+    /// only occur when nothing matched: a remainder after any fill means a
+    /// maker was exhausted and its slot freed.
+    pub fn submit<F: FnMut(Fill)>(
+        &mut self,
+        txn: &Transaction,
+        on_fill: F,
+    ) -> Result<u32, OrderBookError> {
+        match txn.order_action()? {
+            OrderAction::New => {
+                if self.index.contains_key(&txn.id) {
+                    return Err(OrderBookError::DuplicateOrderId(txn.id));
+                }
+                self.add(txn, on_fill)
+            }
+            OrderAction::Cancel => {
+                self.cancel(txn.id, txn.ingress_ts_ns)?;
+                Ok(0)
+            }
+            OrderAction::Modify => {
+                self.modify(txn.id, txn.size, txn.ingress_ts_ns)?;
+                Ok(0)
+            }
+            OrderAction::Replace => {
+                // Validate the new price before touching the old order
+                self.book.level_index(txn.price)?;
+                self.cancel(txn.id, txn.ingress_ts_ns)?;
+                self.add(txn, on_fill)
+            }
+        }
+    }
+
+    /// Remove a resting order. Returns the quantity that was still resting.
+    ///
+    /// # Errors
+    /// - `UnknownOrderId`: no order with this id is resting
+    pub fn cancel(&mut self, id: u64, timestamp: u64) -> Result<u32, TransactionError> {
+        let slot = *self.index.get(&id).ok_or(TransactionError::UnknownOrderId(id))?;
+        let order = self.orders[slot as usize];
+
+        self.unlink(slot);
+        self.release(slot);
+        self.update_book(order.side, order.price, -(order.remaining as i64), timestamp);
+
+        Ok(order.remaining)
+    }
+
+    /// Set a resting order's remaining size.
+    /// Shrinking keeps time priority; growing re-queues at the back.
+    ///
+    /// # Errors
+    /// - `UnknownOrderId`: no order with this id is resting
+    /// - `ZeroSize`: `new_size` is 0 (use `cancel`)
+    pub fn modify(&mut self, id: u64, new_size: u32, timestamp: u64) -> Result<(), TransactionError> {
+        if new_size == 0 {
+            return Err(TransactionError::ZeroSize);
+        }
+        let slot = *self.index.get(&id).ok_or(TransactionError::UnknownOrderId(id))?;
+        let order = self.orders[slot as usize];
+
+        if new_size > order.remaining {
+            self.unlink(slot);
+            self.push_back(slot);
+        }
+        self.orders[slot as usize].remaining = new_size;
+        self.update_book(order.side, order.price, new_size as i64 - order.remaining as i64, timestamp);
+
+        Ok(())
+    }
+
+    /// Match a new order against the opposite side, then rest any remainder
+    fn add<F: FnMut(Fill)>(
+        &mut self,
+        txn: &Transaction,
+        mut on_fill: F,
+    ) -> Result<u32, OrderBookError> {
+        self.book.level_index(txn.price)?;
+
+        let mut remaining = txn.size;
+        while remaining > 0 {
+            // Empty sides report 0 / i64::MAX, which never cross
+            let (best, crosses) = if txn.is_bid() {
+                let best = self.book.best_ask();
+                (best, best <= txn.price)
+            } else {
+                let best = self.book.best_bid();
+                (best, best >= txn.price && best > 0)
+            };
+            if !crosses {
+                break;
+            }
+            let before = remaining;
+            remaining = self.match_level(txn, best, remaining, &mut on_fill);
+            if remaining == before {
+                debug_assert!(false, "best price {} has no resting orders", best);
+                break;
+            }
+        }
+
+        if remaining > 0 {
+            if self.free_head == NIL {
+                return Err(OrderBookError::OrderPoolFull);
+            }
+            self.rest(txn, remaining);
+        }
+
+        Ok(txn.size - remaining)
+    }
+
+    /// Fill against the FIFO queue at `price` on the side opposite `taker`.
+    /// Returns the taker's remaining quantity.
+    fn match_level<F: FnMut(Fill)>(
+        &mut self,
+        taker: &Transaction,
+        price: i64,
+        mut remaining: u32,
+        on_fill: &mut F,
+    ) -> u32 {
+        let idx = self.book.level_index(price).expect("best price is always inside the window");
+        let maker_side = if taker.is_bid() { 1 } else { 0 };
+
+        while remaining > 0 {
+            let queue = if maker_side == 1 { &self.ask_queues[idx] } else { &self.bid_queues[idx] };
+            let slot = queue.head;
+            if slot == NIL {
+                // Queues and book quantities move together, so an empty
+                // queue here means the level was just exhausted
+                break;
+            }
+
+            let maker = &mut self.orders[slot as usize];
+            let qty = remaining.min(maker.remaining);
+            maker.remaining -= qty;
+            remaining -= qty;
+
+            on_fill(Fill::new(maker.id, taker.id, price, qty, taker.side, taker.ingress_ts_ns));
+
+            if maker.remaining == 0 {
+                self.unlink(slot);
+                self.release(slot);
+            }
+            self.update_book(maker_side, price, -(qty as i64), taker.ingress_ts_ns);
+        }
+
+        remaining
+    }
+
+    /// Take a free slot for a remainder and append it to its level's queue
+    fn rest(&mut self, txn: &Transaction, remaining: u32) {
+        let slot = self.free_head;
+        debug_assert!(slot != NIL, "free slot checked in add");
+        self.free_head = self.orders[slot as usize].next;
+
+        self.orders[slot as usize] = RestingOrder {
+            id: txn.id,
+            price: txn.price,
+            remaining,
+            side: txn.side,
+            prev: NIL,
+            next: NIL,
+        };
+        self.index.insert(txn.id, slot);
+        self.push_back(slot);
+
+        if txn.is_bid() {
+            self.resting_bids += 1;
+        } else {
+            self.resting_asks += 1;
+        }
+        self.update_book(txn.side, txn.price, remaining as i64, txn.ingress_ts_ns);
+    }
+
+    /// FIFO queue holding the order in `slot`
+    fn queue_mut(&mut self, slot: u32) -> &mut LevelQueue {
+        let order = &self.orders[slot as usize];
+        let idx = self.book.level_index(order.price).expect("resting price is inside the window");
+        if order.side == 0 { &mut self.bid_queues[idx] } else { &mut self.ask_queues[idx] }
+    }
+
+    /// Append `slot` to the tail of its level's queue
+    fn push_back(&mut self, slot: u32) {
+        let queue = self.queue_mut(slot);
+        let tail = queue.tail;
+        if tail == NIL {
+            queue.head = slot;
+        }
+        queue.tail = slot;
+
+        if tail != NIL {
+            self.orders[tail as usize].next = slot;
+        }
+        let order = &mut self.orders[slot as usize];
+        order.prev = tail;
+        order.next = NIL;
+    }
+
+    /// Detach `slot` from its level's queue
+    fn unlink(&mut self, slot: u32) {
+        let RestingOrder { prev, next, .. } = self.orders[slot as usize];
+
+        if prev != NIL {
+            self.orders[prev as usize].next = next;
+        }
+        if next != NIL {
+            self.orders[next as usize].prev = prev;
+        }
+
+        let queue = self.queue_mut(slot);
+        if prev == NIL {
+            queue.head = next;
+        }
+        if next == NIL {
+            queue.tail = prev;
+        }
+    }
+
+    /// Return an unlinked order's slot to the free list
+    fn release(&mut self, slot: u32) {
+        let order = self.orders[slot as usize];
+        self.index.remove(&order.id);
+        if order.side == 0 {
+            self.resting_bids -= 1;
+        } else {
+            self.resting_asks -= 1;
+        }
+
+        self.orders[slot as usize] = RestingOrder {
+            next: self.free_head,
+            ..RestingOrder::EMPTY
+        };
+        self.free_head = slot;
+    }
+
+    /// Apply a quantity change to the aggregate book
+    fn update_book(&self, side: u8, price: i64, delta: i64, timestamp: u64) {
+        // Engine is the book's only writer, so updates cannot time out
+        let updated = if side == 0 {
+            self.book.update_bid(price, delta, timestamp)
+        } else {
+            self.book.update_ask(price, delta, timestamp)
+        };
+        debug_assert!(updated.is_ok(), "book update failed: {:?}", updated);
+    }
+
+    /// Remaining quantity of a resting order, if it is still in the book
+    pub fn resting_quantity(&self, id: u64) -> Option<u32> {
+        self.index
+            .get(&id)
+            .map(|&slot| self.orders[slot as usize].remaining)
+    }
+
+    /// Number of resting bid orders
+    pub fn resting_bids(&self) -> u64 {
+        self.resting_bids
+    }
+
+    /// Number of resting ask orders
+    pub fn resting_asks(&self) -> u64 {
+        self.resting_asks
+    }
+
+    /// Aggregate view of the resting book
+    pub fn book(&self) -> &ExactOrderBook {
+        &self.book
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn engine() -> MatchingEngine {
+        MatchingEngine::new(1000, 1, 1000, 64)
+    }
+
+    fn bid(id: u64, price: i64, size: u32) -> Transaction {
+        Transaction::new_unchecked(id, price, size, 0, id)
+    }
+
+    fn ask(id: u64, price: i64, size: u32) -> Transaction {
+        Transaction::new_unchecked(id, price, size, 1, id)
+    }
+
+    fn submit(engine: &mut MatchingEngine, txn: Transaction) -> Vec<Fill> {
+        let mut fills = Vec::new();
+        engine.submit(&txn, |fill| fills.push(fill)).unwrap();
+        fills
+    }
+
+    #[test]
+    fn test_non_crossing_orders_rest() {
+        let mut engine = engine();
+
+        assert!(submit(&mut engine, bid(1, 1100, 10)).is_empty());
+        assert!(submit(&mut engine, ask(2, 1200, 10)).is_empty());
+
+        assert_eq!(engine.book().best_bid(), 1100);
+        assert_eq!(engine.book().best_ask(), 1200);
+        assert_eq!(engine.resting_bids(), 1);
+        assert_eq!(engine.resting_asks(), 1);
+    }
+
+    #[test]
+    fn test_full_cross_at_maker_price() {
+        let mut engine = engine();
+
+        submit(&mut engine, ask(1, 1200, 10));
+        let fills = submit(&mut engine, bid(2, 1250, 10));
+
+        assert_eq!(fills, vec![Fill::new(1, 2, 1200, 10, 0, 2)]);
+        assert_eq!(engine.resting_quantity(1), None);
+        assert_eq!(engine.resting_quantity(2), None);
+        assert_eq!(engine.book().best_ask(), i64::MAX);
+        assert_eq!(engine.book().best_bid(), 0);
+    }
+
+    #[test]
+    fn test_partial_fill_rests_remainder() {
+        let mut engine = engine();
+
+        submit(&mut engine, bid(1, 1100, 5));
+        let fills = submit(&mut engine, ask(2, 1100, 8));
+
+        assert_eq!(fills.len(), 1);
+        assert_eq!(fills[0].size, 5);
+        assert_eq!(engine.resting_quantity(2), Some(3));
+        assert_eq!(engine.book().best_ask(), 1100);
+        assert_eq!(engine.book().ask_quantity(1100), 3);
+        assert_eq!(engine.book().bid_quantity(1100), 0);
+    }
+
+    #[test]
+    fn test_time_priority_within_level() {
+        let mut engine = engine();
+
+        submit(&mut engine, ask(1, 1200, 5));
+        submit(&mut engine, ask(2, 1200, 5));
+        submit(&mut engine, ask(3, 1200, 5));
+
+        let fills = submit(&mut engine, bid(4, 1200, 7));
+        let makers: Vec<u64> = fills.iter().map(|f| f.maker_id).collect();
+
+        assert_eq!(makers, vec![1, 2]);
+        assert_eq!(engine.resting_quantity(1), None);
+        assert_eq!(engine.resting_quantity(2), Some(3));
+        assert_eq!(engine.resting_quantity(3), Some(5));
+    }
+
+    #[test]
+    fn test_sweep_multiple_levels_best_price_first() {
+        let mut engine = engine();
+
+        submit(&mut engine, bid(1, 1100, 5));
+        submit(&mut engine, bid(2, 1120, 5));
+        submit(&mut engine, bid(3, 1110, 5));
+
+        let fills = submit(&mut engine, ask(4, 1105, 12));
+        let prices: Vec<i64> = fills.iter().map(|f| f.price).collect();
+
+        assert_eq!(prices, vec![1120, 1110]);
+        assert_eq!(engine.resting_quantity(4), Some(2));
+        assert_eq!(engine.book().best_bid(), 1100);
+        assert_eq!(engine.book().best_ask(), 1105);
+    }
+
+    #[test]
+    fn test_rejections_leave_book_untouched() {
+        let mut engine = MatchingEngine::new(1000, 1, 1000, 1);
+
+        submit(&mut engine, bid(1, 1100, 5));
+
+        assert_eq!(
+            engine.submit(&bid(1, 1100, 5), |_| {}),
+            Err(OrderBookError::DuplicateOrderId(1))
+        );
+        assert_eq!(
+            engine.submit(&bid(2, 5000, 5), |_| {}),
+            Err(OrderBookError::PriceOutOfRange { price: 5000, min: 1000, max: 1999 })
+        );
+        assert_eq!(
+            engine.submit(&bid(3, 1090, 5), |_| {}),
+            Err(OrderBookError::OrderPoolFull)
+        );
+        assert_eq!(engine.resting_quantity(1), Some(5));
+        assert_eq!(engine.book().bid_quantity(1090), 0);
+    }
+
+    fn action(id: u64, price: i64, size: u32, side: u8, action: OrderAction) -> Transaction {
+        Transaction::with_action(id, price, size, side, action, id).unwrap()
+    }
+
+    #[test]
+    fn test_cancel_from_middle_of_queue() {
+        let mut engine = engine();
+
+        submit(&mut engine, ask(1, 1200, 5));
+        submit(&mut engine, ask(2, 1200, 5));
+        submit(&mut engine, ask(3, 1200, 5));
+
+        submit(&mut engine, action(2, 0, 0, 1, OrderAction::Cancel));
+        assert_eq!(engine.resting_quantity(2), None);
+        assert_eq!(engine.book().ask_quantity(1200), 10);
+        assert_eq!(engine.resting_asks(), 2);
+
+        let fills = submit(&mut engine, bid(4, 1200, 10));
+        let makers: Vec<u64> = fills.iter().map(|f| f.maker_id).collect();
+        assert_eq!(makers, vec![1, 3]);
+    }
+
+    #[test]
+    fn test_cancel_best_level_updates_best() {
+        let mut engine = engine();
+
+        submit(&mut engine, bid(1, 1100, 5));
+        submit(&mut engine, bid(2, 1150, 5));
+        assert_eq!(engine.cancel(2, 0), Ok(5));
+
+        assert_eq!(engine.book().best_bid(), 1100);
+        assert_eq!(engine.cancel(2, 0), Err(TransactionError::UnknownOrderId(2)));
+    }
+
+    #[test]
+    fn test_modify_priority() {
+        let mut engine = engine();
+
+        submit(&mut engine, ask(1, 1200, 5));
+        submit(&mut engine, ask(2, 1200, 5));
+
+        // Shrinking keeps the front of the queue
+        submit(&mut engine, action(1, 0, 3, 1, OrderAction::Modify));
+        assert_eq!(engine.book().ask_quantity(1200), 8);
+        let fills = submit(&mut engine, bid(3, 1200, 1));
+        assert_eq!(fills[0].maker_id, 1);
+
+        // Growing moves to the back
+        submit(&mut engine, action(1, 0, 20, 1, OrderAction::Modify));
+        assert_eq!(engine.book().ask_quantity(1200), 25);
+        let fills = submit(&mut engine, bid(4, 1200, 1));
+        assert_eq!(fills[0].maker_id, 2);
+    }
+
+    #[test]
+    fn test_replace_reprices_and_can_cross() {
+        let mut engine = engine();
+
+        submit(&mut engine, bid(1, 1100, 5));
+        submit(&mut engine, ask(2, 1200, 5));
+
+        let fills = submit(&mut engine, action(1, 1200, 3, 0, OrderAction::Replace));
+        assert_eq!(fills, vec![Fill::new(2, 1, 1200, 3, 0, 1)]);
+        assert_eq!(engine.resting_quantity(1), None);
+        assert_eq!(engine.book().bid_quantity(1100), 0);
+        assert_eq!(engine.resting_quantity(2), Some(2));
+    }
+
+    #[test]
+    fn test_lifecycle_rejections() {
+        let mut engine = engine();
+
+        submit(&mut engine, bid(1, 1100, 5));
+
+        let mut unknown = bid(1, 1100, 5);
+        unknown.action = 7;
+        assert_eq!(
+            engine.submit(&unknown, |_| {}),
+            Err(OrderBookError::InvalidTransaction(TransactionError::UnknownAction(7)))
+        );
+        assert_eq!(
+            engine.submit(&action(9, 1100, 5, 0, OrderAction::Modify), |_| {}),
+            Err(OrderBookError::InvalidTransaction(TransactionError::UnknownOrderId(9)))
+        );
+
+        // Out-of-window replace leaves the original order resting
+        assert_eq!(
+            engine.submit(&action(1, 5000, 5, 0, OrderAction::Replace), |_| {}),
+            Err(OrderBookError::PriceOutOfRange { price: 5000, min: 1000, max: 1999 })
+        );
+        assert_eq!(engine.resting_quantity(1), Some(5));
+    }
+
+    #[test]
+    fn test_released_slots_are_reused() {
+        let mut engine = MatchingEngine::new(1000, 1, 1000, 1);
+
+        for i in 0..10 {
+            submit(&mut engine, ask(2 * i, 1200, 5));
+            let fills = submit(&mut engine, bid(2 * i + 1, 1200, 5));
+            assert_eq!(fills.len(), 1);
+        }
+        assert_eq!(engine.resting_asks(), 0);
+    }
+}
@@ -0,0 +1,231 @@
+use core::cell::UnsafeCell;
+use core::mem::MaybeUninit;
+use core::ptr;
+use core::sync::atomic::{AtomicU64, Ordering};
+use crate::ring::CachePadded;
+
+/// A storage slot tagged with a sequence number.
+///
+/// For the slot at index `i`, `seq == pos` means "free for the producer
+/// claiming position `pos`" and `seq == pos + 1` means "holds the value
+/// written at `pos`, ready for the consumer claiming `pos`".
+struct Slot<T> {
+    seq: AtomicU64,
+    value: UnsafeCell<MaybeUninit<T>>,
+}
+
+/// Bounded multi-producer, multi-consumer lock-free ring buffer.
+///
+/// Same layout principles as `RingBuffer` (cache-line padded indices,
+/// power-of-two capacity, mask indexing), but producers and consumers claim
+/// positions with a CAS and hand off each slot through its sequence number
+/// (Vyukov's bounded queue). Any number of threads may call `push` and `pop`
+/// concurrently, so several feed handlers can fan in to one consumer.
+///
+/// Costs one CAS per operation on the contended side; prefer `RingBuffer`
+/// when there is exactly one producer and one consumer.
+pub struct MpmcRingBuffer<T, const N: usize> {
+    /// Next position to be claimed by a producer
+    head: CachePadded<AtomicU64>,
+    /// Next position to be claimed by a consumer
+    tail: CachePadded<AtomicU64>,
+    /// Sequence-tagged storage slots
+    slots: [Slot<T>; N],
+}
+
+impl<T, const N: usize> MpmcRingBuffer<T, N> {
+    /// Create a new ring buffer
+    pub fn new() -> Self {
+        // Verify N is power of 2 at runtime for generic N
+        assert!(
+            N > 0 && (N & (N - 1)) == 0,
+            "MpmcRingBuffer size must be power of 2"
+        );
+
+        Self {
+            head: CachePadded::new(AtomicU64::new(0)),
+            tail: CachePadded::new(AtomicU64::new(0)),
+            slots: core::array::from_fn(|i| Slot {
+                seq: AtomicU64::new(i as u64),
+                value: UnsafeCell::new(MaybeUninit::uninit()),
+            }),
+        }
+    }
+
+    /// Push a value into the ring buffer.
+    /// Returns Err(value) if buffer is full (backpressure - caller should handle).
+    ///
+    /// Safe to call from any number of threads.
+    pub fn push(&self, value: T) -> Result<(), T> {
+        let mut pos = self.head.value.load(Ordering::Relaxed);
+
+        loop {
+            let slot = &self.slots[(pos as usize) & (N - 1)];
+            // Acquire: synchronize with the consumer that last freed this slot
+            let seq = slot.seq.load(Ordering::Acquire);
+            let diff = seq.wrapping_sub(pos) as i64;
+
+            if diff == 0 {
+                // Slot is free for this position; try to claim it
+                match self.head.value.compare_exchange_weak(
+                    pos,
+                    pos.wrapping_add(1),
+                    Ordering::Relaxed,
+                    Ordering::Relaxed,
+                ) {
+                    Ok(_) => {
+                        // Safe: the CAS gave this thread exclusive ownership of the slot
+                        unsafe {
+                            ptr::write(slot.value.get(), MaybeUninit::new(value));
+                        }
+                        // Release: publish the value to the consumer of `pos`
+                        slot.seq.store(pos.wrapping_add(1), Ordering::Release);
+                        return Ok(());
+                    }
+                    Err(current) => pos = current,
+                }
+            } else if diff < 0 {
+                // Slot still holds the value from one lap ago: buffer is full
+                return Err(value);
+            } else {
+                // Another producer claimed this position; reload
+                pos = self.head.value.load(Ordering::Relaxed);
+            }
+        }
+    }
+
+    /// Pop a value from the ring buffer.
+    /// Returns None if buffer is empty.
+    ///
+    /// Safe to call from any number of threads.
+    pub fn pop(&self) -> Option<T> {
+        let mut pos = self.tail.value.load(Ordering::Relaxed);
+
+        loop {
+            let slot = &self.slots[(pos as usize) & (N - 1)];
+            // Acquire: synchronize with the producer's Release store
+            let seq = slot.seq.load(Ordering::Acquire);
+            let diff = seq.wrapping_sub(pos.wrapping_add(1)) as i64;
+
+            if diff == 0 {
+                match self.tail.value.compare_exchange_weak(
+                    pos,
+                    pos.wrapping_add(1),
+                    Ordering::Relaxed,
+                    Ordering::Relaxed,
+                ) {
+                    Ok(_) => {
+                        // Safe: the CAS gave this thread exclusive ownership of the slot
+                        let value = unsafe { ptr::read(slot.value.get()).assume_init() };
+                        // Release: hand the slot to the producer one lap ahead
+                        slot.seq.store(pos.wrapping_add(N as u64), Ordering::Release);
+                        return Some(value);
+                    }
+                    Err(current) => pos = current,
+                }
+            } else if diff < 0 {
+                // Slot not yet written for this position: buffer is empty
+                return None;
+            } else {
+                // Another consumer claimed this position; reload
+                pos = self.tail.value.load(Ordering::Relaxed);
+            }
+        }
+    }
+
+    /// Check if buffer is empty (may be stale immediately)
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Check if buffer is full (may be stale immediately)
+    pub fn is_full(&self) -> bool {
+        self.len() >= N
+    }
+
+    /// Get approximate length (may be stale)
+    pub fn len(&self) -> usize {
+        let tail = self.tail.value.load(Ordering::Acquire);
+        let head = self.head.value.load(Ordering::Acquire);
+        // Indices are read separately, so clamp transient inversions
+        (head.wrapping_sub(tail) as i64).clamp(0, N as i64) as usize
+    }
+}
+
+impl<T, const N: usize> Default for MpmcRingBuffer<T, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Safety: slots are handed between threads only through the sequence protocol
+unsafe impl<T: Send, const N: usize> Send for MpmcRingBuffer<T, N> {}
+unsafe impl<T: Send, const N: usize> Sync for MpmcRingBuffer<T, N> {}
+
+impl<T, const N: usize> Drop for MpmcRingBuffer<T, N> {
+    fn drop(&mut self) {
+        while self.pop().is_some() {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mpmc_basic() {
+        let ring = MpmcRingBuffer::<u64, 4>::new();
+
+        assert!(ring.is_empty());
+        assert!(ring.push(1).is_ok());
+        assert!(ring.push(2).is_ok());
+        assert_eq!(ring.len(), 2);
+
+        assert_eq!(ring.pop(), Some(1));
+        assert_eq!(ring.pop(), Some(2));
+        assert_eq!(ring.pop(), None);
+        assert!(ring.is_empty());
+    }
+
+    #[test]
+    fn test_mpmc_full() {
+        let ring = MpmcRingBuffer::<u64, 4>::new();
+
+        for i in 0..4 {
+            assert!(ring.push(i).is_ok());
+        }
+        assert!(ring.is_full());
+        assert_eq!(ring.push(4), Err(4));
+
+        assert_eq!(ring.pop(), Some(0));
+        assert!(ring.push(4).is_ok());
+    }
+
+    #[test]
+    fn test_mpmc_wrap_around() {
+        let ring = MpmcRingBuffer::<u64, 4>::new();
+
+        for lap in 0..10 {
+            for i in 0..4 {
+                assert!(ring.push(lap * 4 + i).is_ok());
+            }
+            for i in 0..4 {
+                assert_eq!(ring.pop(), Some(lap * 4 + i));
+            }
+        }
+    }
+
+    #[test]
+    fn test_mpmc_drop_releases_values() {
+        use std::rc::Rc;
+
+        let value = Rc::new(());
+        {
+            let ring = MpmcRingBuffer::<Rc<()>, 4>::new();
+            ring.push(Rc::clone(&value)).unwrap();
+            ring.push(Rc::clone(&value)).unwrap();
+            assert_eq!(Rc::strong_count(&value), 3);
+        }
+        assert_eq!(Rc::strong_count(&value), 1);
+    }
+}
@@ -29,6 +29,41 @@ impl<T> CachePadded<T> {
     }
 }
 
+/// Read-only market-data queries shared by every order book type.
+///
+/// Prices use the same sentinels across implementations: `best_bid` is 0 and
+/// `best_ask` is `i64::MAX` when that side is empty, so analytics code can be
+/// written once against either book.
+pub trait BookQuery {
+    /// Best (highest) bid price, or 0 if there are no bids
+    fn best_bid(&self) -> i64;
+
+    /// Best (lowest) ask price, or `i64::MAX` if there are no asks
+    fn best_ask(&self) -> i64;
+
+    /// Net quantity resting at a bid price
+    fn bid_quantity(&self, price: i64) -> i64;
+
+    /// Net quantity resting at an ask price
+    fn ask_quantity(&self, price: i64) -> i64;
+
+    /// Count of bid levels with non-zero quantity
+    fn depth_bid(&self) -> u64;
+
+    /// Count of ask levels with non-zero quantity
+    fn depth_ask(&self) -> u64;
+
+    /// Spread (best_ask - best_bid), or 0 if either side is empty
+    fn spread(&self) -> i64 {
+        let bid = self.best_bid();
+        let ask = self.best_ask();
+        if ask == i64::MAX || bid == 0 {
+            return 0;
+        }
+        ask - bid
+    }
+}
+
 /// A single price level in the order book
 #[repr(C, align(64))]
 struct PriceLevel {
@@ -67,6 +102,7 @@ impl PriceLevel {
 /// **Good for**: High-frequency analytics, volume tracking, MEV detection
 /// **NOT for**: Order matching, precise P&L, limit order books
 ///
+/// Use `ExactOrderBook` when prices must map to distinct levels.
 /// See `ORDERBOOK_LIMITATIONS.md` for detailed explanation.
 pub struct OrderBook {
     /// Bid side (buy orders)
@@ -83,6 +119,7 @@ impl OrderBook {
     /// Create a new order book
     pub fn new() -> Self {
         // Initialize arrays with default values
+        #[allow(clippy::declare_interior_mutable_const)]
         const INIT: PriceLevel = PriceLevel {
             quantity: AtomicI64::new(0),
             timestamp: AtomicU64::new(0),
@@ -282,6 +319,32 @@ impl OrderBook {
     }
 }
 
+impl BookQuery for OrderBook {
+    fn best_bid(&self) -> i64 {
+        OrderBook::best_bid(self)
+    }
+
+    fn best_ask(&self) -> i64 {
+        OrderBook::best_ask(self)
+    }
+
+    fn bid_quantity(&self, price: i64) -> i64 {
+        OrderBook::bid_quantity(self, price)
+    }
+
+    fn ask_quantity(&self, price: i64) -> i64 {
+        OrderBook::ask_quantity(self, price)
+    }
+
+    fn depth_bid(&self) -> u64 {
+        OrderBook::depth_bid(self)
+    }
+
+    fn depth_ask(&self) -> u64 {
+        OrderBook::depth_ask(self)
+    }
+}
+
 // Safety: OrderBook can be shared between threads
 unsafe impl Send for OrderBook {}
 unsafe impl Sync for OrderBook {}
@@ -0,0 +1,1565 @@
+/// Embeddable four-stage pipeline: ingress → orderbook → bundle → output
+///
+/// `PipelineBuilder` declares each stage's core pinning and wait strategy,
+/// the ring capacities between stages (const generics, so rings stay
+/// power-of-two and are allocated once, on the heap) and the matching engine's price window. Custom `Stage`s
+/// (risk checks, enrichment) can be inserted in front of the orderbook.
+/// `start` spawns one thread per stage, each consuming stage running under
+/// `run_stage`; `shutdown` stops them upstream-first so every stage drains
+/// its input before exiting and nothing is lost in flight. With
+/// `async_output` the output stage is left to the caller, as `RingStream`s
+/// over the output and trade rings.
+///
+/// Usage:
+/// ```no_run
+/// # use velox_engine::{init_tsc, PipelineBuilder, StageConfig, WaitStrategy};
+/// init_tsc();
+/// let mut pipeline = PipelineBuilder::new()
+///     .ring_capacities::<8192, 8192, 1024, 4096>()
+///     .ingress_rate_hz(50_000.0)
+///     .orderbook(StageConfig::pinned(1).with_wait(WaitStrategy::BusySpin))
+///     .start()
+///     .expect("spawn pipeline");
+///
+/// std::thread::sleep(std::time::Duration::from_secs(1));
+/// pipeline.shutdown().expect("clean shutdown");
+/// pipeline.stats().print_summary();
+/// ```
+use crate::async_ring::{AsyncNotifier, RingStream};
+use crate::backoff::Backoff;
+use crate::bundle::{BackpressureStats, BundleBuilder};
+use crate::errors::PipelineError;
+use crate::flush::{FlushPolicy, FlushTriggers};
+use crate::histogram::LatencyHistogram;
+use crate::ingress::model::{MarketGenerator, MarketModel};
+use crate::ingress::{IngressSink, IngressSource, SyntheticIngress};
+use crate::matching::MatchingEngine;
+use crate::recorder::RecorderTap;
+use crate::ring::RingBuffer;
+use crate::stage::{run_stage, Discard, Emit, RingEmitter, Stage, StageContext, StageStats};
+use crate::telemetry;
+use crate::tsc::{rdtsc, tsc_to_ns};
+use crate::types::{Bundle, Fill, Transaction, BUNDLE_MAX};
+use crate::wait::{Notifier, Notify, WaitStrategy};
+use core_affinity::{set_for_current, CoreId};
+use std::any::Any;
+use std::io;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+/// Default ring capacities (ingress→orderbook, orderbook→bundle,
+/// bundle→output, orderbook→output trades)
+pub const DEFAULT_INGRESS_RING: usize = 4096;
+pub const DEFAULT_BUNDLE_RING: usize = 4096;
+pub const DEFAULT_OUTPUT_RING: usize = 1024;
+pub const DEFAULT_TRADE_RING: usize = 4096;
+
+/// Default synthetic ingress rate (transactions per second)
+pub const DEFAULT_INGRESS_RATE_HZ: f64 = 100_000.0;
+
+/// Per-stage thread placement and idle behaviour
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StageConfig {
+    /// Core to pin the stage thread to (None = let the OS schedule it)
+    pub core: Option<usize>,
+    /// How the stage waits when its input ring is empty
+    pub wait: WaitStrategy,
+}
+
+impl StageConfig {
+    /// Pin to `core`, parking when idle
+    pub fn pinned(core: usize) -> Self {
+        Self {
+            core: Some(core),
+            wait: WaitStrategy::Park,
+        }
+    }
+
+    /// No pinning, parking when idle
+    pub fn unpinned() -> Self {
+        Self {
+            core: None,
+            wait: WaitStrategy::Park,
+        }
+    }
+
+    /// Override the idle wait strategy
+    pub fn with_wait(mut self, wait: WaitStrategy) -> Self {
+        self.wait = wait;
+        self
+    }
+}
+
+/// Largest `BookConfig::levels` accepted (two level arrays per side come to
+/// about 200 MB at this size)
+pub const MAX_BOOK_LEVELS: usize = 1 << 22;
+
+/// Largest `BookConfig::max_orders` accepted
+pub const MAX_BOOK_ORDERS: usize = 1 << 22;
+
+/// Matching engine sizing (see `MatchingEngine::new`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BookConfig {
+    pub min_price: i64,
+    pub tick_size: i64,
+    pub levels: usize,
+    pub max_orders: usize,
+}
+
+impl Default for BookConfig {
+    /// Covers the synthetic $90-$110 price range at 1-tick resolution
+    fn default() -> Self {
+        Self {
+            min_price: 900_000,
+            tick_size: 1,
+            levels: 200_000,
+            max_orders: 65_536,
+        }
+    }
+}
+
+/// Pipeline counters, shared with the stage threads
+pub struct PipelineStats {
+    pub ingress_generated: AtomicU64,
+    pub ingress_pushed: AtomicU64,
+    pub ingress_dropped: AtomicU64,
+    pub orderbook_processed: AtomicU64,
+    pub orderbook_rejected: AtomicU64,
+    pub trades_executed: AtomicU64,
+    pub trades_dropped: AtomicU64,
+    pub trades_received: AtomicU64,
+    pub bundle_flushed: AtomicU64,
+    /// Bundles held because the output ring was full
+    pub bundle_delayed: AtomicU64,
+    /// Held bundles discarded (overflow full, or output gone at shutdown)
+    pub bundle_dropped: AtomicU64,
+    /// Transactions in dropped bundles
+    pub bundle_dropped_txns: AtomicU64,
+    pub output_received: AtomicU64,
+}
+
+impl PipelineStats {
+    fn new() -> Self {
+        Self {
+            ingress_generated: AtomicU64::new(0),
+            ingress_pushed: AtomicU64::new(0),
+            ingress_dropped: AtomicU64::new(0),
+            orderbook_processed: AtomicU64::new(0),
+            orderbook_rejected: AtomicU64::new(0),
+            trades_executed: AtomicU64::new(0),
+            trades_dropped: AtomicU64::new(0),
+            trades_received: AtomicU64::new(0),
+            bundle_flushed: AtomicU64::new(0),
+            bundle_delayed: AtomicU64::new(0),
+            bundle_dropped: AtomicU64::new(0),
+            bundle_dropped_txns: AtomicU64::new(0),
+            output_received: AtomicU64::new(0),
+        }
+    }
+
+    /// Print all counters
+    pub fn print_summary(&self) {
+        println!("\n=== Pipeline Statistics ===");
+        println!(
+            "Ingress:   generated={} pushed={} dropped={}",
+            self.ingress_generated.load(Ordering::Relaxed),
+            self.ingress_pushed.load(Ordering::Relaxed),
+            self.ingress_dropped.load(Ordering::Relaxed),
+        );
+        println!(
+            "OrderBook: processed={} rejected={}",
+            self.orderbook_processed.load(Ordering::Relaxed),
+            self.orderbook_rejected.load(Ordering::Relaxed),
+        );
+        println!(
+            "Trades:    executed={} dropped={} received={}",
+            self.trades_executed.load(Ordering::Relaxed),
+            self.trades_dropped.load(Ordering::Relaxed),
+            self.trades_received.load(Ordering::Relaxed),
+        );
+        println!(
+            "Bundle:    flushed={} delayed={} dropped={} (txns={})",
+            self.bundle_flushed.load(Ordering::Relaxed),
+            self.bundle_delayed.load(Ordering::Relaxed),
+            self.bundle_dropped.load(Ordering::Relaxed),
+            self.bundle_dropped_txns.load(Ordering::Relaxed),
+        );
+        println!(
+            "Output:    received={}",
+            self.output_received.load(Ordering::Relaxed),
+        );
+    }
+}
+
+/// Spawn-time body of one stage thread: (shared state, stage index, wait)
+type StageJob<const I: usize, const B: usize, const O: usize, const T: usize> =
+    Box<dyn FnOnce(&Shared<I, B, O, T>, usize, WaitStrategy) + Send>;
+
+/// Spawn-time body of an ingress source thread, given the ingress recorder
+type SourceJob<const I: usize, const B: usize, const O: usize, const T: usize> =
+    Box<dyn FnOnce(&Shared<I, B, O, T>, Option<RecorderTap<Transaction>>) + Send>;
+
+/// Builder for a `Pipeline`; const parameters are the ring capacities
+pub struct PipelineBuilder<
+    const INGRESS: usize = DEFAULT_INGRESS_RING,
+    const BUNDLE: usize = DEFAULT_BUNDLE_RING,
+    const OUTPUT: usize = DEFAULT_OUTPUT_RING,
+    const TRADES: usize = DEFAULT_TRADE_RING,
+> {
+    ingress_rate_hz: f64,
+    market_model: MarketModel,
+    /// Seed for the synthetic generator (None: from entropy)
+    ingress_seed: Option<u64>,
+    book: BookConfig,
+    flush_policy: FlushTriggers,
+    bundle_overflow: usize,
+    stages: [StageConfig; 4],
+    /// External feed replacing the synthetic generator
+    source: Option<SourceJob<INGRESS, BUNDLE, OUTPUT, TRADES>>,
+    /// Recorders fed with pushed ingress transactions and delivered bundles
+    ingress_tap: Option<RecorderTap<Transaction>>,
+    output_tap: Option<RecorderTap<Bundle>>,
+    /// Hand the output and trade rings to the caller instead of spawning
+    /// the output stage
+    async_output: bool,
+    /// User stages between ingress and the orderbook, in insertion order
+    custom: Vec<(&'static str, StageConfig, StageJob<INGRESS, BUNDLE, OUTPUT, TRADES>)>,
+}
+
+impl PipelineBuilder {
+    /// Default topology: stages pinned to cores 0-3, parking when idle
+    pub fn new() -> Self {
+        Self {
+            ingress_rate_hz: DEFAULT_INGRESS_RATE_HZ,
+            market_model: MarketModel::default(),
+            ingress_seed: None,
+            book: BookConfig::default(),
+            flush_policy: FlushTriggers::default(),
+            bundle_overflow: 0,
+            stages: [
+                StageConfig::pinned(0),
+                StageConfig::pinned(1),
+                StageConfig::pinned(2),
+                StageConfig::pinned(3),
+            ],
+            source: None,
+            ingress_tap: None,
+            output_tap: None,
+            async_output: false,
+            custom: Vec::new(),
+        }
+    }
+}
+
+impl Default for PipelineBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const INGRESS: usize, const BUNDLE: usize, const OUTPUT: usize, const TRADES: usize>
+    PipelineBuilder<INGRESS, BUNDLE, OUTPUT, TRADES>
+{
+    /// Change ring capacities (each must be a power of 2).
+    ///
+    /// # Panics
+    /// Panics if custom stages or an ingress source were already added:
+    /// call this first.
+    pub fn ring_capacities<const I: usize, const B: usize, const O: usize, const T: usize>(
+        self,
+    ) -> PipelineBuilder<I, B, O, T> {
+        assert!(
+            self.custom.is_empty() && self.source.is_none(),
+            "set ring capacities before adding custom stages or an ingress source"
+        );
+        PipelineBuilder {
+            ingress_rate_hz: self.ingress_rate_hz,
+            market_model: self.market_model,
+            ingress_seed: self.ingress_seed,
+            book: self.book,
+            flush_policy: self.flush_policy,
+            bundle_overflow: self.bundle_overflow,
+            stages: self.stages,
+            source: None,
+            ingress_tap: self.ingress_tap,
+            output_tap: self.output_tap,
+            async_output: self.async_output,
+            custom: Vec::new(),
+        }
+    }
+
+    /// Synthetic ingress rate in transactions per second
+    pub fn ingress_rate_hz(mut self, rate_hz: f64) -> Self {
+        self.ingress_rate_hz = rate_hz;
+        self
+    }
+
+    /// Order flow of the synthetic generator (keep its prices inside the
+    /// book's range)
+    pub fn market_model(mut self, model: MarketModel) -> Self {
+        self.market_model = model;
+        self
+    }
+
+    /// Seed the synthetic generator so every run offers the same
+    /// transactions (which of them the ring drops still depends on timing)
+    pub fn ingress_seed(mut self, seed: u64) -> Self {
+        self.ingress_seed = Some(seed);
+        self
+    }
+
+    /// Matching engine sizing
+    pub fn book(mut self, book: BookConfig) -> Self {
+        self.book = book;
+        self
+    }
+
+    /// Bundle flush policy (size cap at most BUNDLE_MAX)
+    pub fn flush_policy(mut self, policy: FlushTriggers) -> Self {
+        self.flush_policy = policy;
+        self
+    }
+
+    /// Bundles that may queue behind the bundle stage's retry slot while
+    /// the output ring is full before the oldest is dropped (default 0)
+    pub fn bundle_overflow(mut self, limit: usize) -> Self {
+        self.bundle_overflow = limit;
+        self
+    }
+
+    /// Feed the ingress ring from `source` (UDP, TCP, ...) instead of the
+    /// synthetic generator. Ingress counters and telemetry are kept the same
+    /// way; an I/O error from the source ends ingress and is returned by
+    /// `shutdown`. `Pipeline::take_ingress_source` hands the source back
+    /// afterwards for its own counters.
+    pub fn ingress_source<S: IngressSource + 'static>(mut self, source: S) -> Self {
+        let job: SourceJob<INGRESS, BUNDLE, OUTPUT, TRADES> = Box::new(move |shared, tap| {
+            let mut source = source;
+            let ring = &shared.txn_rings[0];
+            let mut out = IngressEmitter {
+                out: shared.emitter(ring, 0),
+                counters: IngressCounters::new(ring, &shared.stats, tap),
+                retries: source.retries_when_full(),
+            };
+            let result = source.run(&mut out, &shared.stop[0]);
+            *shared.ingress_error.lock().unwrap() = result.err();
+            *shared.ingress_source.lock().unwrap() = Some(Box::new(source));
+            shared.ingress_finished.store(true, Ordering::Release);
+        });
+        self.source = Some(job);
+        self
+    }
+
+    /// Record every transaction pushed into the ingress ring (see
+    /// `Recorder`); taps that fall behind drop copies, never transactions
+    pub fn record_ingress(mut self, tap: RecorderTap<Transaction>) -> Self {
+        self.ingress_tap = Some(tap);
+        self
+    }
+
+    /// Record every bundle the output stage receives
+    pub fn record_output(mut self, tap: RecorderTap<Bundle>) -> Self {
+        self.output_tap = Some(tap);
+        self
+    }
+
+    /// Consume bundles and fills from async tasks instead of the output
+    /// stage: no output thread is spawned and `Pipeline::take_output_streams`
+    /// returns `RingStream`s over the output and trade rings, which end once
+    /// `shutdown` has drained the stages feeding them. The output stage's
+    /// counters, latency histogram and `record_output` tap stay unused.
+    pub fn async_output(mut self) -> Self {
+        self.async_output = true;
+        self
+    }
+
+    /// Ingress stage placement (its wait strategy is unused: it never waits)
+    pub fn ingress(mut self, config: StageConfig) -> Self {
+        self.stages[0] = config;
+        self
+    }
+
+    /// Orderbook (matching) stage placement and wait strategy
+    pub fn orderbook(mut self, config: StageConfig) -> Self {
+        self.stages[1] = config;
+        self
+    }
+
+    /// Bundle stage placement and wait strategy
+    pub fn bundle(mut self, config: StageConfig) -> Self {
+        self.stages[2] = config;
+        self
+    }
+
+    /// Output stage placement and wait strategy
+    pub fn output(mut self, config: StageConfig) -> Self {
+        self.stages[3] = config;
+        self
+    }
+
+    /// Insert a custom stage (risk check, enrichment, ...) in front of the
+    /// orderbook. Custom stages run in insertion order, each on its own
+    /// thread, linked by rings of the ingress capacity. Transactions the
+    /// stage does not emit never reach the matching engine.
+    pub fn stage<S>(mut self, stage: S, config: StageConfig) -> Self
+    where
+        S: Stage<Input = Transaction, Output = Transaction> + 'static,
+    {
+        let name = stage.name();
+        let job: StageJob<INGRESS, BUNDLE, OUTPUT, TRADES> = Box::new(move |shared, index, wait| {
+            let mut stage = stage;
+            let mut out = shared.emitter(&shared.txn_rings[index], index);
+            run_stage(&mut stage, &shared.txn_rings[index - 1], &mut out, shared.context(index, wait));
+        });
+        self.custom.push((name, config, job));
+        self
+    }
+
+    /// Allocate rings and spawn the stage threads
+    ///
+    /// # Errors
+    /// `InvalidConfig` if the ingress rate is not finite and positive, the
+    /// market model or book sizing is invalid (see `MarketModel::validate`,
+    /// `MatchingEngine::new` and `MAX_BOOK_LEVELS`/`MAX_BOOK_ORDERS`) or the flush policy's max size is outside
+    /// `1..=BUNDLE_MAX`, or if `record_output` is combined with
+    /// `async_output`; `Spawn` if a stage thread cannot be started.
+    pub fn start(self) -> Result<Pipeline<INGRESS, BUNDLE, OUTPUT, TRADES>, PipelineError> {
+        let invalid = |reason: &str| Err(PipelineError::InvalidConfig(reason.to_string()));
+        if !(self.ingress_rate_hz.is_finite() && self.ingress_rate_hz > 0.0) {
+            return invalid("ingress rate must be finite and positive");
+        }
+        if let Err(err) = self.market_model.validate() {
+            return invalid(&err.to_string());
+        }
+        if let Err(reason) = check_book(&self.book) {
+            return invalid(reason);
+        }
+        if !(1..=BUNDLE_MAX).contains(&self.flush_policy.max_size()) {
+            return invalid("bundle max_size must be in 1..=BUNDLE_MAX");
+        }
+        if self.async_output && self.output_tap.is_some() {
+            return invalid("record_output needs the output stage, not async_output");
+        }
+
+        let rate_hz = self.ingress_rate_hz;
+        let book = self.book;
+        let flush_policy = self.flush_policy;
+        let overflow = self.bundle_overflow;
+        let [ingress, orderbook, bundle, output] = self.stages;
+
+        let mut jobs: Vec<(&'static str, StageConfig, StageJob<INGRESS, BUNDLE, OUTPUT, TRADES>)> =
+            Vec::with_capacity(self.custom.len() + 4);
+        let ingress_tap = self.ingress_tap;
+        let output_tap = self.output_tap;
+        let source: StageJob<INGRESS, BUNDLE, OUTPUT, TRADES> = match self.source {
+            Some(source) => Box::new(move |shared, _, _| source(shared, ingress_tap)),
+            None => {
+                let market = match self.ingress_seed {
+                    Some(seed) => MarketGenerator::new(self.market_model, rate_hz, seed),
+                    None => MarketGenerator::from_entropy(self.market_model, rate_hz),
+                };
+                let ingress = SyntheticIngress::new(market);
+                Box::new(move |shared, _, _| ingress_worker(shared, ingress, ingress_tap))
+            }
+        };
+        jobs.push(("ingress", ingress, source));
+        // One ring into each custom stage, plus the orderbook's input
+        let txn_rings = 1 + self.custom.len();
+        jobs.extend(self.custom);
+        jobs.push((
+            "orderbook",
+            orderbook,
+            Box::new(move |shared, index, wait| orderbook_worker(shared, index, wait, book)),
+        ));
+        jobs.push((
+            "bundle",
+            bundle,
+            Box::new(move |shared, index, wait| {
+                bundle_worker(shared, index, wait, flush_policy, overflow)
+            }),
+        ));
+        let async_output = self.async_output.then(|| AsyncOutput {
+            bundles: Arc::new(AsyncNotifier::new()),
+            trades: Arc::new(AsyncNotifier::new()),
+        });
+        if async_output.is_none() {
+            jobs.push((
+                "output",
+                output,
+                Box::new(move |shared, index, wait| output_worker(shared, index, wait, output_tap)),
+            ));
+        }
+
+        let count = jobs.len();
+        let shared = Arc::new(Shared {
+            txn_rings: (0..txn_rings).map(|_| RingBuffer::boxed()).collect(),
+            bundle_ring: RingBuffer::boxed(),
+            output_ring: Arc::from(RingBuffer::boxed()),
+            trade_ring: Arc::from(RingBuffer::boxed()),
+            async_output,
+            names: jobs.iter().map(|(name, _, _)| *name).collect(),
+            notifiers: (0..count).map(|_| Notifier::new()).collect(),
+            stop: (0..count).map(|_| AtomicBool::new(false)).collect(),
+            stage_stats: (0..count).map(|_| StageStats::new()).collect(),
+            stats: PipelineStats::new(),
+            histogram: LatencyHistogram::new(),
+            ingress_source: Mutex::new(None),
+            ingress_error: Mutex::new(None),
+            ingress_finished: AtomicBool::new(false),
+        });
+
+        let output_streams = shared.async_output.as_ref().map(|wakers| OutputStreams {
+            bundles: RingStream::new(Arc::clone(&shared.output_ring), Arc::clone(&wakers.bundles)),
+            trades: RingStream::new(Arc::clone(&shared.trade_ring), Arc::clone(&wakers.trades)),
+        });
+        let mut pipeline = Pipeline {
+            shared,
+            handles: Vec::with_capacity(count),
+            output_streams,
+        };
+
+        for (index, (name, config, job)) in jobs.into_iter().enumerate() {
+            let shared = Arc::clone(&pipeline.shared);
+
+            let spawned = thread::Builder::new()
+                .name(name.to_string())
+                .spawn(move || {
+                    if let Some(id) = config.core {
+                        set_for_current(CoreId { id });
+                    }
+                    job(&shared, index, config.wait);
+                });
+
+            match spawned {
+                Ok(handle) => pipeline.handles.push(handle),
+                // Dropping `pipeline` stops the stages already running
+                Err(e) => return Err(PipelineError::Spawn(e.kind())),
+            }
+        }
+
+        Ok(pipeline)
+    }
+}
+
+/// Preconditions of `MatchingEngine::new`, plus the size limits, checked
+/// before any thread runs it
+fn check_book(book: &BookConfig) -> Result<(), &'static str> {
+    if book.min_price <= 0 {
+        return Err("book min_price must be positive");
+    }
+    if book.tick_size <= 0 {
+        return Err("book tick_size must be positive");
+    }
+    if !(1..=MAX_BOOK_LEVELS).contains(&book.levels) {
+        return Err("book levels must be in 1..=MAX_BOOK_LEVELS");
+    }
+    if !(1..=MAX_BOOK_ORDERS).contains(&book.max_orders) {
+        return Err("book max_orders must be in 1..=MAX_BOOK_ORDERS");
+    }
+    i64::try_from(book.levels - 1)
+        .ok()
+        .and_then(|span| span.checked_mul(book.tick_size))
+        .and_then(|span| book.min_price.checked_add(span))
+        .map(|_| ())
+        .ok_or("book price window overflows i64")
+}
+
+/// State shared between the pipeline handle and its stage threads.
+///
+/// Stages are indexed in pipeline order: ingress (0), custom stages,
+/// orderbook, bundle, output. Per-stage vectors use the same index.
+struct Shared<const INGRESS: usize, const BUNDLE: usize, const OUTPUT: usize, const TRADES: usize> {
+    /// Transaction rings: `txn_rings[i - 1]` feeds stage i, up to the orderbook.
+    /// Rings are built on the heap: large capacities would overflow the
+    /// caller's stack on their way into the `Arc`.
+    txn_rings: Vec<Box<RingBuffer<Transaction, INGRESS>>>,
+    bundle_ring: Box<RingBuffer<Transaction, BUNDLE>>,
+    /// Shared with the caller's streams under `async_output`
+    output_ring: Arc<RingBuffer<Bundle, OUTPUT>>,
+    trade_ring: Arc<RingBuffer<Fill, TRADES>>,
+    /// Wakers of the caller's streams (None: the output stage consumes)
+    async_output: Option<AsyncOutput>,
+    names: Vec<&'static str>,
+    /// Wakes the consumer of stage i's input (index 0 unused: ingress has none)
+    notifiers: Vec<Notifier>,
+    /// Per-stage stop flags, raised upstream-first by `Pipeline::shutdown`
+    stop: Vec<AtomicBool>,
+    stage_stats: Vec<StageStats>,
+    stats: PipelineStats,
+    histogram: LatencyHistogram,
+    /// External source, parked here when its thread finishes
+    ingress_source: Mutex<Option<Box<dyn Any + Send>>>,
+    /// Error the external source stopped with, until `shutdown` reports it
+    ingress_error: Mutex<Option<io::Error>>,
+    /// Set once the external source's `run` has returned
+    ingress_finished: AtomicBool,
+}
+
+impl<const INGRESS: usize, const BUNDLE: usize, const OUTPUT: usize, const TRADES: usize>
+    Shared<INGRESS, BUNDLE, OUTPUT, TRADES>
+{
+    /// Driver wiring for stage `index`
+    fn context(&self, index: usize, wait: WaitStrategy) -> StageContext<'_> {
+        StageContext::new(wait, &self.notifiers[index], &self.stop[index], &self.stage_stats[index])
+    }
+
+    /// Wakes whoever consumes the output ring
+    fn output_ready(&self) -> &(dyn Notify + Sync) {
+        match &self.async_output {
+            Some(wakers) => &*wakers.bundles,
+            // The output stage is last
+            None => self.notifiers.last().unwrap(),
+        }
+    }
+
+    /// Wakes whoever consumes the trade ring
+    fn trades_ready(&self) -> &(dyn Notify + Sync) {
+        match &self.async_output {
+            Some(wakers) => &*wakers.trades,
+            None => self.notifiers.last().unwrap(),
+        }
+    }
+
+    /// Emitter from stage `index` into `ring`, the next stage's input
+    fn emitter<'a, T, const N: usize>(
+        &'a self,
+        ring: &'a RingBuffer<T, N>,
+        index: usize,
+    ) -> RingEmitter<'a, T, N> {
+        RingEmitter::new(ring, &self.notifiers[index + 1], &self.stage_stats[index])
+    }
+}
+
+/// Readiness of the caller's output streams under `async_output`
+struct AsyncOutput {
+    bundles: Arc<AsyncNotifier>,
+    trades: Arc<AsyncNotifier>,
+}
+
+/// The pipeline's output under `PipelineBuilder::async_output`: each stream
+/// must be polled by a single task
+pub struct OutputStreams<const OUTPUT: usize, const TRADES: usize> {
+    /// Bundles from the bundle stage
+    pub bundles: RingStream<Bundle, OUTPUT>,
+    /// Fills from the matching engine (dropped and counted in
+    /// `trades_dropped` while this stream falls behind)
+    pub trades: RingStream<Fill, TRADES>,
+}
+
+/// A running pipeline. Dropping it performs the same shutdown as `shutdown`.
+pub struct Pipeline<
+    const INGRESS: usize = DEFAULT_INGRESS_RING,
+    const BUNDLE: usize = DEFAULT_BUNDLE_RING,
+    const OUTPUT: usize = DEFAULT_OUTPUT_RING,
+    const TRADES: usize = DEFAULT_TRADE_RING,
+> {
+    shared: Arc<Shared<INGRESS, BUNDLE, OUTPUT, TRADES>>,
+    /// Stage threads in pipeline order
+    handles: Vec<JoinHandle<()>>,
+    /// Until taken by `take_output_streams`
+    output_streams: Option<OutputStreams<OUTPUT, TRADES>>,
+}
+
+impl<const INGRESS: usize, const BUNDLE: usize, const OUTPUT: usize, const TRADES: usize>
+    Pipeline<INGRESS, BUNDLE, OUTPUT, TRADES>
+{
+    /// Live counters
+    pub fn stats(&self) -> &PipelineStats {
+        &self.shared.stats
+    }
+
+    /// Driver counters for the stage called `name` (built-in or custom)
+    pub fn stage_stats(&self, name: &str) -> Option<&StageStats> {
+        let index = self.shared.names.iter().position(|n| *n == name)?;
+        Some(&self.shared.stage_stats[index])
+    }
+
+    /// Ingress → egress latency histogram
+    pub fn histogram(&self) -> &LatencyHistogram {
+        &self.shared.histogram
+    }
+
+    /// Stop every stage upstream-first, draining each one's input, and join.
+    /// Stats and histogram stay readable afterwards; later calls are no-ops.
+    ///
+    /// Returns the first failure in pipeline order: the external ingress
+    /// source's I/O error, or a stage that panicked.
+    pub fn shutdown(&mut self) -> Result<(), PipelineError> {
+        let mut result = Ok(());
+
+        // Stage i only stops after its producer (stage i-1) has been joined,
+        // so once it sees its flag its input ring can no longer grow.
+        for (stage, handle) in self.handles.drain(..).enumerate() {
+            self.shared.stop[stage].store(true, Ordering::Release);
+            self.shared.notifiers[stage].notify();
+
+            if handle.join().is_err() && result.is_ok() {
+                result = Err(PipelineError::StagePanicked(self.shared.names[stage]));
+            }
+            if let Some(err) = self.shared.ingress_error.lock().unwrap().take() {
+                if result.is_ok() {
+                    result = Err(PipelineError::IngressFailed {
+                        kind: err.kind(),
+                        message: err.to_string(),
+                    });
+                }
+            }
+        }
+        // Every producer is joined: the streams end once drained
+        if let Some(wakers) = &self.shared.async_output {
+            wakers.bundles.close();
+            wakers.trades.close();
+        }
+
+        result
+    }
+
+    /// The output and trade streams, once, if the pipeline was built with
+    /// `async_output`
+    pub fn take_output_streams(&mut self) -> Option<OutputStreams<OUTPUT, TRADES>> {
+        self.output_streams.take()
+    }
+
+    /// True once the external ingress source has returned on its own (a
+    /// replay reaching the end of its file, or an I/O error) or been stopped
+    /// by `shutdown`. Always false for the synthetic generator, which runs
+    /// until shutdown.
+    pub fn ingress_finished(&self) -> bool {
+        self.shared.ingress_finished.load(Ordering::Acquire)
+    }
+
+    /// Hand back the external ingress source once its thread has finished
+    /// (after `shutdown`, or once a finite source such as a replay returns),
+    /// to read its own counters: TCP sessions, UDP gaps, replay totals.
+    /// None while it is running, for the synthetic generator, or if `S` is
+    /// not the source's type.
+    pub fn take_ingress_source<S: IngressSource + 'static>(&mut self) -> Option<S> {
+        let mut slot = self.shared.ingress_source.lock().unwrap();
+        match slot.take()?.downcast::<S>() {
+            Ok(source) => Some(*source),
+            Err(other) => {
+                *slot = Some(other);
+                None
+            }
+        }
+    }
+}
+
+impl<const INGRESS: usize, const BUNDLE: usize, const OUTPUT: usize, const TRADES: usize> Drop
+    for Pipeline<INGRESS, BUNDLE, OUTPUT, TRADES>
+{
+    fn drop(&mut self) {
+        let _ = self.shutdown();
+    }
+}
+
+/// Ring utilization in percent, for telemetry
+fn utilization<T, const N: usize>(ring: &RingBuffer<T, N>) -> f64 {
+    (ring.len() as f64 / N as f64) * 100.0
+}
+
+/// Pipeline ingress counters, telemetry and recording, shared by the
+/// synthetic generator and external sources
+struct IngressCounters<'a, const N: usize> {
+    ring: &'a RingBuffer<Transaction, N>,
+    stats: &'a PipelineStats,
+    tap: Option<RecorderTap<Transaction>>,
+    pushed: u64,
+}
+
+impl<'a, const N: usize> IngressCounters<'a, N> {
+    fn new(
+        ring: &'a RingBuffer<Transaction, N>,
+        stats: &'a PipelineStats,
+        tap: Option<RecorderTap<Transaction>>,
+    ) -> Self {
+        Self {
+            ring,
+            stats,
+            tap,
+            pushed: 0,
+        }
+    }
+
+    fn count_pushed(&mut self, txn: &Transaction) {
+        self.stats.ingress_generated.fetch_add(1, Ordering::Relaxed);
+        self.stats.ingress_pushed.fetch_add(1, Ordering::Relaxed);
+        if let Some(tap) = &mut self.tap {
+            tap.record(txn);
+        }
+        // Sample ring utilization every 1000 transactions
+        self.pushed += 1;
+        if self.pushed.is_multiple_of(1000) {
+            telemetry::record_ring_utilization("ingress_to_orderbook", utilization(self.ring));
+        }
+    }
+
+    fn count_dropped(&mut self) {
+        self.stats.ingress_generated.fetch_add(1, Ordering::Relaxed);
+        self.stats.ingress_dropped.fetch_add(1, Ordering::Relaxed);
+        telemetry::record_ingress_dropped();
+    }
+}
+
+impl<const N: usize> IngressSink for IngressCounters<'_, N> {
+    fn pushed(&mut self, txn: &Transaction, latency_ns: u64) {
+        self.count_pushed(txn);
+        telemetry::record_transaction_processed("ingress", txn.id, latency_ns as f64 / 1000.0);
+    }
+
+    fn dropped(&mut self, _txn: &Transaction) {
+        self.count_dropped();
+    }
+}
+
+/// Ingress-side `Emit` for an `IngressSource`: the counters and telemetry
+/// the synthetic generator reports through `IngressCounters`
+struct IngressEmitter<'a, const N: usize> {
+    out: RingEmitter<'a, Transaction, N>,
+    counters: IngressCounters<'a, N>,
+    /// The source retries refused transactions: a refusal is not a drop
+    retries: bool,
+}
+
+impl<const N: usize> Emit<Transaction> for IngressEmitter<'_, N> {
+    fn emit(&mut self, txn: Transaction) -> Result<(), Transaction> {
+        match self.out.emit(txn) {
+            Ok(()) => {
+                self.counters.count_pushed(&txn);
+                Ok(())
+            }
+            Err(txn) if self.retries => Err(txn),
+            Err(txn) => {
+                self.counters.count_dropped();
+                Err(txn)
+            }
+        }
+    }
+}
+
+/// Ingress worker: runs the synthetic generator until the pipeline stops
+fn ingress_worker<const I: usize, const B: usize, const O: usize, const T: usize>(
+    shared: &Shared<I, B, O, T>,
+    mut ingress: SyntheticIngress,
+    tap: Option<RecorderTap<Transaction>>,
+) {
+    let ring = &shared.txn_rings[0];
+    let mut counters = IngressCounters::new(ring, &shared.stats, tap);
+    ingress.run_with(&mut shared.emitter(ring, 0), &shared.stop[0], &mut counters);
+}
+
+/// Matches transactions with price-time priority, emits fills to the trade
+/// ring and forwards accepted orders to bundling
+struct OrderBookStage<'a, const T: usize> {
+    engine: MatchingEngine,
+    trades: &'a RingBuffer<Fill, T>,
+    /// Wakes the consumer of fills
+    trades_ready: &'a (dyn Notify + Sync),
+    stats: &'a PipelineStats,
+    sample_counter: u64,
+}
+
+impl<const T: usize> Stage for OrderBookStage<'_, T> {
+    type Input = Transaction;
+    type Output = Transaction;
+
+    fn name(&self) -> &'static str {
+        "orderbook"
+    }
+
+    fn process<E: Emit<Transaction>>(&mut self, txn: Transaction, out: &mut E) {
+        let (trades, trades_ready, stats) = (self.trades, self.trades_ready, self.stats);
+
+        // Match against resting orders; any remainder rests in the book
+        let result = self.engine.submit(&txn, |fill| match trades.push(fill) {
+            Ok(_) => {
+                trades_ready.notify();
+                stats.trades_executed.fetch_add(1, Ordering::Relaxed);
+            }
+            Err(_) => {
+                stats.trades_dropped.fetch_add(1, Ordering::Relaxed);
+            }
+        });
+
+        match result {
+            Ok(_) => {
+                stats.orderbook_processed.fetch_add(1, Ordering::Relaxed);
+
+                // Sample trade ring and orderbook depth every 1000 transactions
+                self.sample_counter += 1;
+                if self.sample_counter.is_multiple_of(1000) {
+                    telemetry::record_ring_utilization("orderbook_to_trades", utilization(trades));
+                    telemetry::record_orderbook_depth("bid", self.engine.resting_bids());
+                    telemetry::record_orderbook_depth("ask", self.engine.resting_asks());
+                }
+
+                // Forward to bundle builder (drop on full)
+                let _ = out.emit(txn);
+            }
+            Err(_) => {
+                stats.orderbook_rejected.fetch_add(1, Ordering::Relaxed);
+                telemetry::record_orderbook_rejected();
+            }
+        }
+    }
+}
+
+fn orderbook_worker<const I: usize, const B: usize, const O: usize, const T: usize>(
+    shared: &Shared<I, B, O, T>,
+    index: usize,
+    wait: WaitStrategy,
+    book: BookConfig,
+) {
+    let mut stage = OrderBookStage {
+        engine: MatchingEngine::new(book.min_price, book.tick_size, book.levels, book.max_orders),
+        trades: &shared.trade_ring,
+        trades_ready: shared.trades_ready(),
+        stats: &shared.stats,
+        sample_counter: 0,
+    };
+    let mut out = shared.emitter(&shared.bundle_ring, index);
+    let ctx = shared.context(index, wait).with_input_label("ingress_to_orderbook");
+    run_stage(&mut stage, &shared.txn_rings[index - 1], &mut out, ctx);
+}
+
+/// How long the bundle stage keeps retrying held bundles at shutdown
+/// before dropping them (the output stage is still draining meanwhile)
+const SHUTDOWN_RETRY_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Accumulates transactions into bundles and pushes them to the output ring
+/// itself (`BundleBuilder` owns the flush decision and holds bundles the
+/// ring refuses)
+struct BundleStage<'a, const O: usize> {
+    builder: BundleBuilder,
+    output: &'a RingBuffer<Bundle, O>,
+    output_ready: &'a (dyn Notify + Sync),
+    stats: &'a PipelineStats,
+    /// Builder counters already reflected in `stats` and telemetry
+    published: BackpressureStats,
+}
+
+impl<const O: usize> BundleStage<'_, O> {
+    fn flush(&mut self, reason: &str) -> bool {
+        let bundle_size = self.builder.len();
+        // A full ring holds the bundle: it is flushed either way
+        let _ = self.builder.force_flush(self.output);
+        self.publish_backpressure();
+        if bundle_size > 0 {
+            self.flushed(bundle_size, reason);
+            return true;
+        }
+        false
+    }
+
+    /// Account for a bundle the builder closed
+    fn flushed(&self, bundle_size: usize, reason: &str) {
+        self.output_ready.notify();
+        self.stats.bundle_flushed.fetch_add(1, Ordering::Relaxed);
+        telemetry::record_bundle_flushed(bundle_size as u32, reason);
+    }
+
+    /// Mirror the builder's delayed/dropped counters
+    fn publish_backpressure(&mut self) {
+        let now = self.builder.backpressure();
+        if now == self.published {
+            return;
+        }
+        if now.retried > self.published.retried {
+            self.output_ready.notify();
+        }
+        telemetry::record_bundles_delayed(now.delayed - self.published.delayed);
+        telemetry::record_bundles_dropped(now.dropped - self.published.dropped);
+        self.stats.bundle_delayed.store(now.delayed, Ordering::Relaxed);
+        self.stats.bundle_dropped.store(now.dropped, Ordering::Relaxed);
+        self.stats.bundle_dropped_txns.store(now.dropped_transactions, Ordering::Relaxed);
+        self.published = now;
+    }
+}
+
+impl<const O: usize> Stage for BundleStage<'_, O> {
+    type Input = Transaction;
+    type Output = ();
+
+    fn name(&self) -> &'static str {
+        "bundle"
+    }
+
+    fn process<E: Emit<()>>(&mut self, txn: Transaction, _out: &mut E) {
+        let outcome = self.builder.add(txn, self.output);
+        for flushed in outcome.flushes() {
+            self.flushed(flushed.size, flushed.reason.as_str());
+        }
+        self.publish_backpressure();
+    }
+
+    /// Retry held bundles and check timeout flush even when idle
+    fn on_idle<E: Emit<()>>(&mut self, _out: &mut E) -> bool {
+        let retried = self.builder.held() > 0 && self.builder.retry(self.output) > 0;
+        self.publish_backpressure();
+        let reason = self.builder.policy().timeout_reason();
+        let flushed = self.builder.should_flush_timeout() && self.flush(reason.as_str());
+        retried || flushed
+    }
+
+    /// Held bundles need retrying as soon as the output ring drains
+    fn has_pending(&self) -> bool {
+        self.builder.held() > 0
+    }
+
+    /// Parks never outlast the bundle timeout, so idle flushes stay on time
+    fn idle_timeout(&self) -> Option<Duration> {
+        Some(Duration::from_nanos(self.builder.policy().min_timeout_ns()))
+    }
+
+    /// Flush remaining transactions, then wait (bounded) for the output
+    /// stage to take everything held
+    fn flush<E: Emit<()>>(&mut self, _out: &mut E) {
+        self.flush("shutdown");
+
+        let deadline = Instant::now() + SHUTDOWN_RETRY_TIMEOUT;
+        let mut backoff = Backoff::new();
+        while self.builder.held() > 0 && Instant::now() < deadline {
+            if self.builder.retry(self.output) > 0 {
+                self.output_ready.notify();
+                backoff.reset();
+            } else {
+                backoff.snooze();
+            }
+        }
+        self.builder.drop_held();
+        self.publish_backpressure();
+    }
+}
+
+fn bundle_worker<const I: usize, const B: usize, const O: usize, const T: usize>(
+    shared: &Shared<I, B, O, T>,
+    index: usize,
+    wait: WaitStrategy,
+    flush_policy: FlushTriggers,
+    overflow: usize,
+) {
+    let mut stage = BundleStage {
+        builder: BundleBuilder::with_policy(flush_policy).with_overflow(overflow),
+        output: &shared.output_ring,
+        output_ready: shared.output_ready(),
+        stats: &shared.stats,
+        published: BackpressureStats::default(),
+    };
+    let ctx = shared.context(index, wait).with_input_label("orderbook_to_bundle");
+    run_stage(&mut stage, &shared.bundle_ring, &mut Discard, ctx);
+}
+
+/// Consumes fills and bundles, recording end-to-end latency and
+/// simulating bundle submission
+struct OutputStage<'a, const T: usize> {
+    trades: &'a RingBuffer<Fill, T>,
+    stats: &'a PipelineStats,
+    histogram: &'a LatencyHistogram,
+    tap: Option<RecorderTap<Bundle>>,
+}
+
+impl<const T: usize> OutputStage<'_, T> {
+    /// Consume executed trades from the matching stage
+    fn drain_trades(&mut self) -> bool {
+        let mut trades_seen = false;
+        while let Some(fill) = self.trades.pop() {
+            self.stats.trades_received.fetch_add(1, Ordering::Relaxed);
+            std::hint::black_box(&fill);
+            trades_seen = true;
+        }
+        trades_seen
+    }
+}
+
+impl<const T: usize> Stage for OutputStage<'_, T> {
+    type Input = Bundle;
+    type Output = ();
+
+    fn name(&self) -> &'static str {
+        "output"
+    }
+
+    fn process<E: Emit<()>>(&mut self, bundle: Bundle, _out: &mut E) {
+        self.drain_trades();
+        self.stats.output_received.fetch_add(1, Ordering::Relaxed);
+        if let Some(tap) = &mut self.tap {
+            tap.record(&bundle);
+        }
+
+        // Calculate E2E latency from first transaction's timestamp
+        let now_ns = tsc_to_ns(rdtsc());
+        let first_txn_ns = bundle.transactions[0].ingress_ts_ns;
+        let e2e_latency_us = now_ns.saturating_sub(first_txn_ns) as f64 / 1000.0;
+
+        // Record E2E latency
+        telemetry::record_e2e_latency(e2e_latency_us, bundle.transactions[0].id);
+
+        // Record latency for each transaction in bundle
+        let egress_ts_ns = tsc_to_ns(rdtsc());
+        for txn in bundle.active_transactions() {
+            self.histogram.record(egress_ts_ns.saturating_sub(txn.ingress_ts_ns));
+        }
+
+        // Simulate bundle submission (no-op for now)
+        // In production: submit to Solana RPC or Jito
+        std::hint::black_box(&bundle);
+    }
+
+    fn on_idle<E: Emit<()>>(&mut self, _out: &mut E) -> bool {
+        self.drain_trades()
+    }
+
+    /// Fills are a side input: wake for them as well as for bundles
+    fn has_pending(&self) -> bool {
+        !self.trades.is_empty()
+    }
+
+    fn flush<E: Emit<()>>(&mut self, _out: &mut E) {
+        self.drain_trades();
+    }
+}
+
+fn output_worker<const I: usize, const B: usize, const O: usize, const T: usize>(
+    shared: &Shared<I, B, O, T>,
+    index: usize,
+    wait: WaitStrategy,
+    tap: Option<RecorderTap<Bundle>>,
+) {
+    let mut stage = OutputStage {
+        trades: &shared.trade_ring,
+        stats: &shared.stats,
+        histogram: &shared.histogram,
+        tap,
+    };
+    let ctx = shared.context(index, wait).with_input_label("bundle_to_output");
+    run_stage(&mut stage, &shared.output_ring, &mut Discard, ctx);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bundle::BUNDLE_TIMEOUT_NS;
+    use crate::tsc::init_tsc;
+
+    fn test_builder() -> PipelineBuilder<1024, 1024, 256, 1024> {
+        PipelineBuilder::new()
+            .ring_capacities::<1024, 1024, 256, 1024>()
+            .ingress_rate_hz(20_000.0)
+            .ingress(StageConfig::unpinned())
+            .orderbook(StageConfig::unpinned())
+            .bundle(StageConfig::unpinned())
+            .output(StageConfig::unpinned())
+    }
+
+    #[test]
+    fn test_pipeline_start_and_shutdown_drains() {
+        init_tsc();
+        let mut pipeline = test_builder().start().unwrap();
+
+        thread::sleep(Duration::from_millis(200));
+        // The synthetic generator only stops at shutdown
+        assert!(!pipeline.ingress_finished());
+
+        pipeline.shutdown().unwrap();
+        // Second shutdown is a no-op
+        pipeline.shutdown().unwrap();
+        let stats = pipeline.stats();
+
+        let pushed = stats.ingress_pushed.load(Ordering::Relaxed);
+        let processed = stats.orderbook_processed.load(Ordering::Relaxed);
+        let rejected = stats.orderbook_rejected.load(Ordering::Relaxed);
+        assert!(pushed > 0);
+        // Every pushed transaction reached the matching stage
+        assert_eq!(processed + rejected, pushed);
+        // Every fill produced was consumed by the output stage
+        assert_eq!(
+            stats.trades_received.load(Ordering::Relaxed),
+            stats.trades_executed.load(Ordering::Relaxed)
+        );
+        // Every ring was drained
+        assert!(pipeline.shared.txn_rings[0].is_empty());
+        assert!(pipeline.shared.bundle_ring.is_empty());
+        assert!(pipeline.shared.output_ring.is_empty());
+        assert!(pipeline.shared.trade_ring.is_empty());
+        assert!(stats.output_received.load(Ordering::Relaxed) > 0);
+        // Every flush was counted exactly once: delivered or dropped
+        assert_eq!(
+            stats.bundle_flushed.load(Ordering::Relaxed),
+            stats.output_received.load(Ordering::Relaxed) + stats.bundle_dropped.load(Ordering::Relaxed)
+        );
+    }
+
+    #[test]
+    fn test_pipeline_bundle_backpressure_accounted() {
+        init_tsc();
+        // Two-slot output ring: the bundle stage routinely finds it full
+        let mut pipeline = test_builder()
+            .ring_capacities::<1024, 1024, 2, 1024>()
+            .ingress_rate_hz(500_000.0)
+            .flush_policy(FlushTriggers::new(1, BUNDLE_TIMEOUT_NS))
+            .bundle_overflow(1)
+            .start()
+            .unwrap();
+
+        thread::sleep(Duration::from_millis(200));
+        pipeline.shutdown().unwrap();
+        let stats = pipeline.stats();
+
+        assert!(pipeline.shared.output_ring.is_empty());
+        assert_eq!(
+            stats.bundle_flushed.load(Ordering::Relaxed),
+            stats.output_received.load(Ordering::Relaxed) + stats.bundle_dropped.load(Ordering::Relaxed)
+        );
+        // Single-transaction bundles: one dropped transaction per dropped bundle
+        assert_eq!(
+            stats.bundle_dropped.load(Ordering::Relaxed),
+            stats.bundle_dropped_txns.load(Ordering::Relaxed)
+        );
+    }
+
+    /// Pre-trade risk check: only even sizes reach the matching engine
+    struct EvenSizes;
+
+    impl Stage for EvenSizes {
+        type Input = Transaction;
+        type Output = Transaction;
+
+        fn name(&self) -> &'static str {
+            "even_sizes"
+        }
+
+        fn process<E: Emit<Transaction>>(&mut self, txn: Transaction, out: &mut E) {
+            if txn.size.is_multiple_of(2) {
+                let _ = out.emit(txn);
+            }
+        }
+    }
+
+    #[test]
+    fn test_pipeline_custom_stage() {
+        init_tsc();
+        let mut pipeline = test_builder()
+            .stage(EvenSizes, StageConfig::unpinned())
+            .start()
+            .unwrap();
+
+        thread::sleep(Duration::from_millis(200));
+        pipeline.shutdown().unwrap();
+
+        let stats = pipeline.stats();
+        let risk = pipeline.stage_stats("even_sizes").unwrap();
+        let pushed = stats.ingress_pushed.load(Ordering::Relaxed);
+        let forwarded = risk.emitted.load(Ordering::Relaxed);
+        assert!(pushed > 0);
+        // The custom stage saw everything and filtered some of it
+        assert_eq!(risk.processed.load(Ordering::Relaxed), pushed);
+        assert!(forwarded < pushed);
+        // Only what it forwarded reached the matching engine
+        let matched = stats.orderbook_processed.load(Ordering::Relaxed)
+            + stats.orderbook_rejected.load(Ordering::Relaxed);
+        assert_eq!(matched, forwarded);
+        assert_eq!(pipeline.stage_stats("orderbook").unwrap().processed.load(Ordering::Relaxed), matched);
+        assert!(pipeline.shared.txn_rings.iter().all(|ring| ring.is_empty()));
+    }
+
+    #[test]
+    fn test_pipeline_tcp_ingress_source() {
+        use crate::ingress::replay::ReplayIngress;
+        use crate::ingress::tcp::TcpIngress;
+        use std::io::Write;
+        use std::net::TcpStream;
+
+        init_tsc();
+        let source = TcpIngress::bind("127.0.0.1:0".parse().unwrap()).unwrap();
+        let addr = source.local_addr().unwrap();
+        let mut pipeline = test_builder().ingress_source(source).start().unwrap();
+
+        let frames: Vec<u8> = (0..500)
+            .flat_map(|id| {
+                let price = 1_000_000 + (id % 7) as i64 * 100;
+                Transaction::new_unchecked(id, price, 10, (id % 2) as u8, 0).encode()
+            })
+            .collect();
+        TcpStream::connect(addr).unwrap().write_all(&frames).unwrap();
+
+        let stats = pipeline.stats();
+        let mut waited = 0;
+        while stats.ingress_pushed.load(Ordering::Relaxed) < 500 && waited < 500 {
+            thread::sleep(Duration::from_millis(2));
+            waited += 1;
+        }
+        pipeline.shutdown().unwrap();
+
+        let stats = pipeline.stats();
+        assert_eq!(stats.ingress_generated.load(Ordering::Relaxed), 500);
+        assert_eq!(stats.ingress_pushed.load(Ordering::Relaxed), 500);
+        let matched = stats.orderbook_processed.load(Ordering::Relaxed)
+            + stats.orderbook_rejected.load(Ordering::Relaxed);
+        assert_eq!(matched, 500);
+
+        // The source comes back with its per-session counters
+        assert!(pipeline.take_ingress_source::<ReplayIngress<&[u8]>>().is_none());
+        let source = pipeline.take_ingress_source::<TcpIngress>().unwrap();
+        assert_eq!(source.sessions().len(), 1);
+        assert_eq!(source.totals().pushed, 500);
+    }
+
+    #[test]
+    fn test_pipeline_reports_ingress_source_failure() {
+        use crate::capture::{CaptureReader, CaptureWriter};
+        use crate::ingress::replay::{ReplayIngress, ReplaySpeed};
+        use std::io::Cursor;
+
+        init_tsc();
+        let mut writer = CaptureWriter::new(Vec::new()).unwrap();
+        for id in 0..20u64 {
+            let txn = Transaction::new_unchecked(id, 1_000_000, 10, (id % 2) as u8, id);
+            writer.write_transaction(id, &txn).unwrap();
+        }
+        let capture = writer.into_inner();
+        let replay = |data: Vec<u8>| {
+            let reader = CaptureReader::new(Cursor::new(data)).unwrap();
+            let source = ReplayIngress::new(reader, ReplaySpeed::AsFastAsPossible);
+            let mut pipeline = test_builder().ingress_source(source).start().unwrap();
+            // A finite source finishes on its own, before any shutdown
+            let deadline = Instant::now() + Duration::from_secs(5);
+            while !pipeline.ingress_finished() {
+                assert!(Instant::now() < deadline, "replay did not finish");
+                thread::sleep(Duration::from_millis(1));
+            }
+            let result = pipeline.shutdown();
+            let source = pipeline.take_ingress_source::<ReplayIngress<Cursor<Vec<u8>>>>();
+            (result, source.unwrap().stats())
+        };
+
+        // A capture cut off mid-record (still being written) replays cleanly
+        let (result, stats) = replay(capture[..capture.len() - 3].to_vec());
+        assert_eq!(result, Ok(()));
+        assert_eq!((stats.pushed, stats.truncated_tail), (19, 1));
+
+        // A corrupt record is an error, returned rather than panicking
+        let mut corrupt = capture;
+        let last = corrupt.len() - 10;
+        corrupt[last] ^= 0xFF;
+        let (result, stats) = replay(corrupt);
+        assert!(matches!(
+            result,
+            Err(PipelineError::IngressFailed { kind: io::ErrorKind::InvalidData, .. })
+        ));
+        assert_eq!(stats.pushed, 19);
+    }
+
+    #[test]
+    fn test_pipeline_replay_is_deterministic() {
+        use crate::capture::{CaptureReader, CaptureWriter};
+        use crate::ingress::replay::{ReplayIngress, ReplaySpeed};
+
+        init_tsc();
+        // Crossing orders around one price: plenty of fills and rejections
+        let mut writer = CaptureWriter::new(Vec::new()).unwrap();
+        for id in 0..3000u64 {
+            let price = 1_000_000 + (id % 11) as i64 * 100 - 500;
+            let txn = Transaction::new_unchecked(id, price, 1 + (id % 5) as u32, (id % 2) as u8, id);
+            writer.write_transaction(id * 1_000, &txn).unwrap();
+        }
+        let capture = writer.into_inner();
+
+        let run = || {
+            let reader = CaptureReader::new(std::io::Cursor::new(capture.clone())).unwrap();
+            let source = ReplayIngress::new(reader, ReplaySpeed::AsFastAsPossible);
+            let mut pipeline = test_builder().ingress_source(source).start().unwrap();
+            let mut waited = 0;
+            while pipeline.stats().ingress_pushed.load(Ordering::Relaxed) < 3000 && waited < 1000 {
+                thread::sleep(Duration::from_millis(2));
+                waited += 1;
+            }
+            pipeline.shutdown().unwrap();
+
+            let stats = pipeline.stats();
+            // Replay waits on a full ring instead of dropping
+            assert_eq!(stats.ingress_generated.load(Ordering::Relaxed), 3000);
+            assert_eq!(stats.ingress_dropped.load(Ordering::Relaxed), 0);
+            [
+                stats.ingress_pushed.load(Ordering::Relaxed),
+                stats.orderbook_processed.load(Ordering::Relaxed),
+                stats.orderbook_rejected.load(Ordering::Relaxed),
+                stats.trades_executed.load(Ordering::Relaxed),
+            ]
+        };
+
+        let first = run();
+        assert!(first[3] > 0);
+        assert_eq!(run(), first);
+    }
+
+    #[test]
+    fn test_pipeline_records_ingress_and_output() {
+        use crate::capture::{CaptureReader, CaptureRecord};
+        use crate::recorder::{segment_paths, Recorder, RecorderConfig};
+        use std::fs::{self, File};
+
+        init_tsc();
+        let dir = std::env::temp_dir().join(format!("velox-pipeline-record-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let (ingress_recorder, ingress_tap) =
+            Recorder::<Transaction>::start(RecorderConfig::new(&dir, "ingress")).unwrap();
+        let (output_recorder, output_tap) =
+            Recorder::<Bundle>::start(RecorderConfig::new(&dir, "output")).unwrap();
+
+        let mut pipeline = test_builder()
+            .record_ingress(ingress_tap)
+            .record_output(output_tap)
+            .start()
+            .unwrap();
+        thread::sleep(Duration::from_millis(100));
+        pipeline.shutdown().unwrap();
+        let ingress = ingress_recorder.finish().unwrap();
+        let output = output_recorder.finish().unwrap();
+
+        // Every pushed transaction and delivered bundle was recorded or counted
+        let stats = pipeline.stats();
+        assert!(ingress.recorded > 0);
+        assert_eq!(ingress.recorded + ingress.dropped, stats.ingress_pushed.load(Ordering::Relaxed));
+        assert_eq!(output.recorded + output.dropped, stats.output_received.load(Ordering::Relaxed));
+
+        let count = |prefix: &str| -> (u64, u64) {
+            let mut counts = (0, 0);
+            for path in segment_paths(&dir, prefix).unwrap() {
+                for record in CaptureReader::new(File::open(path).unwrap()).unwrap() {
+                    match record.unwrap() {
+                        CaptureRecord::Transaction { .. } => counts.0 += 1,
+                        CaptureRecord::Bundle { .. } => counts.1 += 1,
+                    }
+                }
+            }
+            counts
+        };
+        assert_eq!(count("ingress"), (ingress.recorded, 0));
+        assert_eq!(count("output"), (0, output.recorded));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_pipeline_rejects_invalid_settings() {
+        for rate_hz in [0.0, -1.0, f64::NAN, f64::INFINITY] {
+            let err = test_builder().ingress_rate_hz(rate_hz).start().err();
+            assert_eq!(
+                err,
+                Some(PipelineError::InvalidConfig("ingress rate must be finite and positive".into()))
+            );
+        }
+        let err = test_builder().flush_policy(FlushTriggers::new(0, 1_000)).start().err();
+        assert!(matches!(err, Some(PipelineError::InvalidConfig(_))));
+    }
+
+    #[test]
+    fn test_pipeline_large_rings_skip_the_stack() {
+        init_tsc();
+        // A 4096-bundle output ring is larger than the whole 256 KiB stack
+        let mut pipeline = thread::Builder::new()
+            .stack_size(256 * 1024)
+            .spawn(|| {
+                PipelineBuilder::new()
+                    .ring_capacities::<1024, 1024, 4096, 1024>()
+                    .ingress(StageConfig::unpinned())
+                    .orderbook(StageConfig::unpinned())
+                    .bundle(StageConfig::unpinned())
+                    .output(StageConfig::unpinned())
+                    .start()
+                    .unwrap()
+            })
+            .unwrap()
+            .join()
+            .unwrap();
+        pipeline.shutdown().unwrap();
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_pipeline_async_output_streams() {
+        use futures_util::StreamExt;
+
+        init_tsc();
+        let mut pipeline = test_builder().async_output().start().unwrap();
+        let streams = pipeline.take_output_streams().unwrap();
+        assert!(pipeline.take_output_streams().is_none());
+        // No output thread
+        assert!(pipeline.stage_stats("output").is_none());
+
+        let bundles = tokio::spawn(streams.bundles.count());
+        let fills = tokio::spawn(streams.trades.count());
+        let pipeline = tokio::task::spawn_blocking(move || {
+            thread::sleep(Duration::from_millis(200));
+            pipeline.shutdown().unwrap();
+            pipeline
+        })
+        .await
+        .unwrap();
+
+        // Both streams end after shutdown, having seen everything published
+        let stats = pipeline.stats();
+        let flushed = stats.bundle_flushed.load(Ordering::Relaxed);
+        let dropped = stats.bundle_dropped.load(Ordering::Relaxed);
+        assert!(flushed > 0);
+        assert_eq!(bundles.await.unwrap() as u64, flushed - dropped);
+        assert_eq!(fills.await.unwrap() as u64, stats.trades_executed.load(Ordering::Relaxed));
+        assert_eq!(stats.output_received.load(Ordering::Relaxed), 0);
+    }
+
+    #[test]
+    fn test_pipeline_async_output_excludes_output_tap() {
+        use crate::recorder::{Recorder, RecorderConfig};
+
+        let dir = std::env::temp_dir().join(format!("velox-async-tap-{}", std::process::id()));
+        let (recorder, tap) =
+            Recorder::<Bundle>::start(RecorderConfig::new(&dir, "output")).unwrap();
+        let err = test_builder().async_output().record_output(tap).start().err();
+        assert!(matches!(err, Some(PipelineError::InvalidConfig(_))));
+        recorder.finish().unwrap();
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_pipeline_rejects_invalid_book() {
+        let book = BookConfig::default();
+        for invalid in [
+            BookConfig { min_price: 0, ..book },
+            BookConfig { tick_size: -1, ..book },
+            BookConfig { levels: 0, ..book },
+            BookConfig { max_orders: 0, ..book },
+            BookConfig { max_orders: MAX_BOOK_ORDERS + 1, ..book },
+            BookConfig { levels: MAX_BOOK_LEVELS + 1, ..book },
+            BookConfig { levels: MAX_BOOK_LEVELS, tick_size: i64::MAX / 2, ..book },
+        ] {
+            // Rejected up front, not by a panicking orderbook thread
+            let err = test_builder().book(invalid).start().err();
+            assert!(matches!(err, Some(PipelineError::InvalidConfig(_))), "{:?}", invalid);
+        }
+    }
+
+    #[test]
+    fn test_pipeline_rejects_invalid_market_model() {
+        let model = MarketModel {
+            buy_ratio: 1.5,
+            ..MarketModel::default()
+        };
+        let err = test_builder().market_model(model).start().err();
+        assert!(matches!(err, Some(PipelineError::InvalidConfig(_))));
+    }
+
+    #[test]
+    fn test_pipeline_drop_stops_threads() {
+        init_tsc();
+        for wait in [WaitStrategy::BusySpin, WaitStrategy::Backoff, WaitStrategy::Park] {
+            let pipeline = test_builder()
+                .orderbook(StageConfig::unpinned().with_wait(wait))
+                .bundle(StageConfig::unpinned().with_wait(wait))
+                .output(StageConfig::unpinned().with_wait(wait))
+                .start()
+                .unwrap();
+            thread::sleep(Duration::from_millis(20));
+            drop(pipeline);
+        }
+    }
+}
@@ -0,0 +1,467 @@
+/// Recorder: copies a pipeline ring's traffic to capture files off the hot
+/// path
+///
+/// A `RecorderTap` sits on the producing side of a ring (ingress
+/// transactions, output bundles). `record` stamps the item and pushes a
+/// copy into a bounded SPSC queue, and never blocks: when the queue is full
+/// the copy is counted as dropped. A writer thread drains the queue into
+/// append-only segment files in the `capture` format, starting a new segment
+/// once the current one reaches a size or age limit. Each segment is a
+/// complete capture that `CaptureReader` and `ReplayIngress` read directly.
+use crate::backoff::Backoff;
+use crate::capture::CaptureWriter;
+use crate::ring::RingBuffer;
+use crate::tsc::{rdtsc, tsc_to_ns};
+use crate::types::{Bundle, Transaction};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+/// Default tap queue capacity (items waiting for the writer thread)
+pub const DEFAULT_RECORDER_QUEUE: usize = 1024;
+
+/// File extension of capture segments
+pub const SEGMENT_EXTENSION: &str = "vxcp";
+
+/// Items a recorder can write: each is one capture record
+pub trait Recordable: Copy + Send + 'static {
+    fn write_record<W: Write>(&self, ts_ns: u64, writer: &mut CaptureWriter<W>) -> io::Result<()>;
+}
+
+impl Recordable for Transaction {
+    fn write_record<W: Write>(&self, ts_ns: u64, writer: &mut CaptureWriter<W>) -> io::Result<()> {
+        writer.write_transaction(ts_ns, self)
+    }
+}
+
+impl<const N: usize> Recordable for Bundle<N> {
+    fn write_record<W: Write>(&self, ts_ns: u64, writer: &mut CaptureWriter<W>) -> io::Result<()> {
+        writer.write_bundle(ts_ns, self)
+    }
+}
+
+/// Where segments go and when to rotate
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecorderConfig {
+    /// Directory for segment files (created if missing)
+    pub dir: PathBuf,
+    /// Segment names: `<prefix>-<index>.vxcp`, index zero-padded, starting
+    /// after the highest index already in `dir` so earlier runs are kept
+    pub prefix: String,
+    /// Start a new segment once this many bytes are written
+    pub max_segment_bytes: u64,
+    /// Start a new segment once the current one is this old
+    pub max_segment_age: Duration,
+}
+
+impl RecorderConfig {
+    /// 64 MiB or one minute per segment
+    pub fn new<P: Into<PathBuf>>(dir: P, prefix: &str) -> Self {
+        Self {
+            dir: dir.into(),
+            prefix: prefix.to_string(),
+            max_segment_bytes: 64 * 1024 * 1024,
+            max_segment_age: Duration::from_secs(60),
+        }
+    }
+
+    pub fn max_segment_bytes(mut self, bytes: u64) -> Self {
+        self.max_segment_bytes = bytes;
+        self
+    }
+
+    pub fn max_segment_age(mut self, age: Duration) -> Self {
+        self.max_segment_age = age;
+        self
+    }
+
+    fn segment_path(&self, index: u64) -> PathBuf {
+        self.dir.join(format!(
+            "{}-{:06}.{}",
+            self.prefix, index, SEGMENT_EXTENSION
+        ))
+    }
+}
+
+/// Recorder counters
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct RecorderStats {
+    /// Records written to segments
+    pub recorded: u64,
+    /// Items the tap could not queue (writer behind)
+    pub dropped: u64,
+    /// Segments opened
+    pub segments: u64,
+    /// Bytes written over all segments
+    pub bytes: u64,
+}
+
+struct RecorderShared<T, const N: usize> {
+    /// Boxed: a queue of bundles is large
+    queue: Box<RingBuffer<(u64, T), N>>,
+    /// Index of this recorder's first segment
+    first_segment: u64,
+    stop: AtomicBool,
+    recorded: AtomicU64,
+    dropped: AtomicU64,
+    segments: AtomicU64,
+    bytes: AtomicU64,
+}
+
+/// Producer side of a recorder; exactly one exists per recorder (the queue
+/// is single-producer)
+pub struct RecorderTap<T: Recordable, const N: usize = DEFAULT_RECORDER_QUEUE> {
+    shared: Arc<RecorderShared<T, N>>,
+}
+
+impl<T: Recordable, const N: usize> RecorderTap<T, N> {
+    /// Queue a copy of `item`, stamped with the current time; drops it (and
+    /// counts the drop) if the writer is behind
+    #[inline]
+    pub fn record(&mut self, item: &T) {
+        let ts_ns = tsc_to_ns(rdtsc());
+        if self.shared.queue.push((ts_ns, *item)).is_err() {
+            self.shared.dropped.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+}
+
+/// Handle to the writer thread
+pub struct Recorder<T: Recordable, const N: usize = DEFAULT_RECORDER_QUEUE> {
+    config: RecorderConfig,
+    shared: Arc<RecorderShared<T, N>>,
+    handle: Option<JoinHandle<io::Result<()>>>,
+}
+
+impl<T: Recordable, const N: usize> Recorder<T, N> {
+    /// Create the directory, open the first segment and spawn the writer;
+    /// returns the recorder and its tap. Segments already in the directory
+    /// are never overwritten: numbering continues after the highest one.
+    pub fn start(config: RecorderConfig) -> io::Result<(Self, RecorderTap<T, N>)> {
+        fs::create_dir_all(&config.dir)?;
+        let shared = Arc::new(RecorderShared {
+            queue: RingBuffer::boxed(),
+            first_segment: next_segment_index(&config)?,
+            stop: AtomicBool::new(false),
+            recorded: AtomicU64::new(0),
+            dropped: AtomicU64::new(0),
+            segments: AtomicU64::new(0),
+            bytes: AtomicU64::new(0),
+        });
+        let segment = Segment::open(&config, &shared)?;
+
+        let handle = {
+            let (config, shared) = (config.clone(), Arc::clone(&shared));
+            thread::Builder::new()
+                .name("recorder".to_string())
+                .spawn(move || write_loop(&config, &shared, segment))?
+        };
+
+        let tap = RecorderTap {
+            shared: Arc::clone(&shared),
+        };
+        Ok((
+            Self {
+                config,
+                shared,
+                handle: Some(handle),
+            },
+            tap,
+        ))
+    }
+
+    pub fn stats(&self) -> RecorderStats {
+        RecorderStats {
+            recorded: self.shared.recorded.load(Ordering::Relaxed),
+            dropped: self.shared.dropped.load(Ordering::Relaxed),
+            segments: self.shared.segments.load(Ordering::Relaxed),
+            bytes: self.shared.bytes.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Segment files opened so far, oldest first
+    pub fn segments(&self) -> Vec<PathBuf> {
+        let first = self.shared.first_segment;
+        let count = self.shared.segments.load(Ordering::Relaxed);
+        (first..first + count)
+            .map(|index| self.config.segment_path(index))
+            .collect()
+    }
+
+    /// Write everything already queued, close the last segment and stop the
+    /// writer. Stop the producer first to capture everything it sent.
+    ///
+    /// # Errors
+    /// The I/O error that stopped the writer, if any (records queued after
+    /// it are counted as dropped).
+    pub fn finish(mut self) -> io::Result<RecorderStats> {
+        self.stop()?;
+        Ok(self.stats())
+    }
+
+    fn stop(&mut self) -> io::Result<()> {
+        let Some(handle) = self.handle.take() else {
+            return Ok(());
+        };
+        self.shared.stop.store(true, Ordering::Release);
+        match handle.join() {
+            Ok(result) => result,
+            Err(_) => Err(io::Error::other("recorder thread panicked")),
+        }
+    }
+}
+
+impl<T: Recordable, const N: usize> Drop for Recorder<T, N> {
+    fn drop(&mut self) {
+        let _ = self.stop();
+    }
+}
+
+/// The segment being written
+struct Segment {
+    writer: CaptureWriter<BufWriter<File>>,
+    opened: Instant,
+}
+
+impl Segment {
+    fn open<T, const N: usize>(
+        config: &RecorderConfig,
+        shared: &RecorderShared<T, N>,
+    ) -> io::Result<Self> {
+        let opened = shared.segments.load(Ordering::Relaxed);
+        let path = config.segment_path(shared.first_segment + opened);
+        // create_new: fail rather than truncate a segment another run wrote
+        let file = OpenOptions::new().write(true).create_new(true).open(&path)?;
+        let writer = CaptureWriter::new(BufWriter::new(file))?;
+
+        shared.segments.store(opened + 1, Ordering::Relaxed);
+        shared
+            .bytes
+            .fetch_add(writer.bytes_written(), Ordering::Relaxed);
+        Ok(Self {
+            writer,
+            opened: Instant::now(),
+        })
+    }
+
+    fn due(&self, config: &RecorderConfig) -> bool {
+        self.writer.bytes_written() >= config.max_segment_bytes
+            || self.opened.elapsed() >= config.max_segment_age
+    }
+}
+
+/// Writer thread: drain the queue, rotating segments, until stopped
+fn write_loop<T: Recordable, const N: usize>(
+    config: &RecorderConfig,
+    shared: &RecorderShared<T, N>,
+    mut segment: Segment,
+) -> io::Result<()> {
+    let result = (|| {
+        let mut backoff = Backoff::new();
+        loop {
+            // Read the flag before draining so nothing queued before stop is missed
+            let stopping = shared.stop.load(Ordering::Acquire);
+            let mut wrote = false;
+
+            while let Some((ts_ns, item)) = shared.queue.pop() {
+                if segment.due(config) {
+                    segment.writer.flush()?;
+                    segment = Segment::open(config, shared)?;
+                }
+                let before = segment.writer.bytes_written();
+                item.write_record(ts_ns, &mut segment.writer)?;
+                shared.recorded.fetch_add(1, Ordering::Relaxed);
+                shared
+                    .bytes
+                    .fetch_add(segment.writer.bytes_written() - before, Ordering::Relaxed);
+                wrote = true;
+            }
+
+            if stopping {
+                return segment.writer.flush();
+            }
+            if wrote {
+                backoff.reset();
+            } else {
+                // Idle: make what was written visible to readers
+                segment.writer.flush()?;
+                backoff.snooze();
+            }
+        }
+    })();
+
+    if result.is_err() {
+        // Nothing more reaches disk: count what was queued, and what the tap
+        // sends until `finish`, as dropped
+        while !shared.stop.load(Ordering::Acquire) {
+            while shared.queue.pop().is_some() {
+                shared.dropped.fetch_add(1, Ordering::Relaxed);
+            }
+            thread::sleep(Duration::from_millis(1));
+        }
+        while shared.queue.pop().is_some() {
+            shared.dropped.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+    result
+}
+
+/// Index after the highest existing segment for the config's prefix, or 0
+fn next_segment_index(config: &RecorderConfig) -> io::Result<u64> {
+    let last = segment_paths(&config.dir, &config.prefix)?
+        .iter()
+        .filter_map(|path| {
+            let stem = path.file_stem()?.to_str()?;
+            stem.strip_prefix(config.prefix.as_str())?
+                .strip_prefix('-')?
+                .parse::<u64>()
+                .ok()
+        })
+        .max();
+    Ok(last.map_or(0, |index| index + 1))
+}
+
+/// Segment files for `prefix` in `dir`, oldest first
+pub fn segment_paths<P: AsRef<Path>>(dir: P, prefix: &str) -> io::Result<Vec<PathBuf>> {
+    let mut paths: Vec<PathBuf> = fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| {
+            path.extension().is_some_and(|ext| ext == SEGMENT_EXTENSION)
+                && path
+                    .file_name()
+                    .and_then(|name| name.to_str())
+                    .is_some_and(|name| name.starts_with(&format!("{}-", prefix)))
+        })
+        .collect();
+    // Zero-padded indices sort lexically
+    paths.sort();
+    Ok(paths)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::capture::{CaptureReader, CaptureRecord};
+    use crate::ingress::replay::{ReplayIngress, ReplaySpeed};
+    use crate::tsc::init_tsc;
+
+    fn test_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("velox-recorder-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    fn txn(id: u64) -> Transaction {
+        Transaction::new_unchecked(id, 1_000_000, 10, (id % 2) as u8, id)
+    }
+
+    #[test]
+    fn test_recorder_rotates_by_size_and_replays() {
+        init_tsc();
+        let dir = test_dir("size");
+        // Header plus 10 records of 52 bytes per segment
+        let config = RecorderConfig::new(&dir, "ingress").max_segment_bytes(8 + 10 * 52);
+        let (recorder, mut tap) = Recorder::<Transaction, 64>::start(config).unwrap();
+
+        for id in 0..35 {
+            tap.record(&txn(id));
+            // Stay within the queue
+            if id % 16 == 15 {
+                thread::sleep(Duration::from_millis(5));
+            }
+        }
+        let stats = recorder.finish().unwrap();
+
+        assert_eq!((stats.recorded, stats.dropped, stats.segments), (35, 0, 4));
+        let paths = segment_paths(&dir, "ingress").unwrap();
+        assert_eq!(paths.len(), 4);
+        let total: u64 = paths
+            .iter()
+            .map(|path| fs::metadata(path).unwrap().len())
+            .sum();
+        assert_eq!(total, stats.bytes);
+
+        // Segments concatenate back to the original stream
+        let mut ids = Vec::new();
+        for path in &paths {
+            let ring = RingBuffer::<Transaction, 64>::new();
+            let mut replay = ReplayIngress::open(path, ReplaySpeed::AsFastAsPossible).unwrap();
+            replay.replay(&mut &ring, &AtomicBool::new(false)).unwrap();
+            ids.extend(std::iter::from_fn(|| ring.pop()).map(|txn| txn.id));
+        }
+        assert_eq!(ids, (0..35).collect::<Vec<_>>());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_recorder_rotates_by_age_and_records_bundles() {
+        init_tsc();
+        let dir = test_dir("age");
+        let config = RecorderConfig::new(&dir, "output").max_segment_age(Duration::from_millis(20));
+        let (recorder, mut tap) = Recorder::<Bundle, 16>::start(config).unwrap();
+
+        tap.record(&Bundle::new());
+        thread::sleep(Duration::from_millis(60));
+        tap.record(&Bundle::new());
+        let stats = recorder.finish().unwrap();
+
+        // The second record found the first segment too old
+        assert_eq!((stats.recorded, stats.segments), (2, 2));
+        let last = segment_paths(&dir, "output").unwrap().pop().unwrap();
+        let records: Vec<_> = CaptureReader::new(File::open(last).unwrap())
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert!(matches!(records[..], [CaptureRecord::Bundle { .. }]));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_recorder_continues_after_existing_segments() {
+        init_tsc();
+        let dir = test_dir("rerun");
+        for run in 0..2u64 {
+            let (recorder, mut tap) =
+                Recorder::<Transaction, 16>::start(RecorderConfig::new(&dir, "ingress")).unwrap();
+            tap.record(&txn(run));
+            assert_eq!(recorder.segments(), vec![dir.join(format!("ingress-{:06}.vxcp", run))]);
+            recorder.finish().unwrap();
+        }
+
+        // The second run left the first run's capture intact
+        let ids: Vec<u64> = segment_paths(&dir, "ingress")
+            .unwrap()
+            .iter()
+            .flat_map(|path| CaptureReader::new(File::open(path).unwrap()).unwrap())
+            .map(|record| match record.unwrap() {
+                CaptureRecord::Transaction { txn, .. } => txn.id,
+                other => panic!("unexpected record {:?}", other),
+            })
+            .collect();
+        assert_eq!(ids, [0, 1]);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_recorder_tap_drops_when_queue_full() {
+        init_tsc();
+        let dir = test_dir("full");
+        let (recorder, mut tap) =
+            Recorder::<Transaction, 4>::start(RecorderConfig::new(&dir, "burst")).unwrap();
+
+        for id in 0..10_000 {
+            tap.record(&txn(id));
+        }
+        let stats = recorder.finish().unwrap();
+
+        // Every item is either written or counted
+        assert_eq!(stats.recorded + stats.dropped, 10_000);
+        assert!(stats.dropped > 0);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
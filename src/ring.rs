@@ -6,12 +6,12 @@ use static_assertions::const_assert;
 
 /// Cache-line padded wrapper to prevent false sharing
 #[repr(C, align(64))]
-struct CachePadded<T> {
-    value: T,
+pub(crate) struct CachePadded<T> {
+    pub(crate) value: T,
 }
 
 impl<T> CachePadded<T> {
-    fn new(value: T) -> Self {
+    pub(crate) fn new(value: T) -> Self {
         Self { value }
     }
 }
@@ -19,6 +19,10 @@ impl<T> CachePadded<T> {
 /// Single-producer, single-consumer lock-free ring buffer.
 /// Uses Release/Acquire memory ordering for ARM64 compatibility.
 ///
+/// Each side keeps a private cached copy of the other side's index and only
+/// re-reads the shared atomic when the cache says the ring is full (producer)
+/// or empty (consumer), so the common case touches no remote cache line.
+///
 /// # Safety
 /// - Only one producer thread may call `push`
 /// - Only one consumer thread may call `pop`
@@ -28,6 +32,10 @@ pub struct RingBuffer<T, const N: usize> {
     head: CachePadded<AtomicU64>,
     /// Consumer writes here (increments on pop)
     tail: CachePadded<AtomicU64>,
+    /// Producer-local snapshot of `tail` (only the producer touches this)
+    cached_tail: CachePadded<UnsafeCell<u64>>,
+    /// Consumer-local snapshot of `head` (only the consumer touches this)
+    cached_head: CachePadded<UnsafeCell<u64>>,
     /// Storage slots (uninitialized until written)
     slots: [UnsafeCell<MaybeUninit<T>>; N],
 }
@@ -49,6 +57,8 @@ impl<T, const N: usize> RingBuffer<T, N> {
         Self {
             head: CachePadded::new(AtomicU64::new(0)),
             tail: CachePadded::new(AtomicU64::new(0)),
+            cached_tail: CachePadded::new(UnsafeCell::new(0)),
+            cached_head: CachePadded::new(UnsafeCell::new(0)),
             slots: unsafe {
                 // Create uninitialized array
                 MaybeUninit::uninit().assume_init()
@@ -56,6 +66,62 @@ impl<T, const N: usize> RingBuffer<T, N> {
         }
     }
 
+    /// Create a ring directly on the heap. Unlike `Box::new(Self::new())`
+    /// the slots never pass through the stack, so large element types or
+    /// capacities cannot overflow it.
+    pub fn boxed() -> Box<Self> {
+        assert!(
+            N > 0 && (N & (N - 1)) == 0,
+            "RingBuffer size must be power of 2"
+        );
+
+        let mut ring = Box::<Self>::new_uninit();
+        let ptr = ring.as_mut_ptr();
+        // SAFETY: every field except `slots` is initialized in place; slots
+        // are `MaybeUninit` and may stay uninitialized, exactly as in `new`
+        unsafe {
+            ptr::addr_of_mut!((*ptr).head).write(CachePadded::new(AtomicU64::new(0)));
+            ptr::addr_of_mut!((*ptr).tail).write(CachePadded::new(AtomicU64::new(0)));
+            ptr::addr_of_mut!((*ptr).cached_tail).write(CachePadded::new(UnsafeCell::new(0)));
+            ptr::addr_of_mut!((*ptr).cached_head).write(CachePadded::new(UnsafeCell::new(0)));
+            ring.assume_init()
+        }
+    }
+
+    /// Free slots as seen by the producer, refreshing the cached tail only
+    /// when fewer than `wanted` slots appear free.
+    #[inline]
+    fn producer_free(&self, head: u64, wanted: usize) -> usize {
+        // Safe: only the producer reads or writes cached_tail
+        let cached_tail = unsafe { &mut *self.cached_tail.value.get() };
+
+        let free = N - head.wrapping_sub(*cached_tail) as usize;
+        if free >= wanted {
+            return free;
+        }
+
+        // Load with Acquire - synchronize with consumer's Release store to tail
+        *cached_tail = self.tail.value.load(Ordering::Acquire);
+        N - head.wrapping_sub(*cached_tail) as usize
+    }
+
+    /// Filled slots as seen by the consumer, refreshing the cached head only
+    /// when fewer than `wanted` slots appear filled.
+    #[inline]
+    fn consumer_available(&self, tail: u64, wanted: usize) -> usize {
+        // Safe: only the consumer reads or writes cached_head
+        let cached_head = unsafe { &mut *self.cached_head.value.get() };
+
+        let available = cached_head.wrapping_sub(tail) as usize;
+        if available >= wanted {
+            return available;
+        }
+
+        // Load with Acquire - synchronize with producer's Release store to head
+        *cached_head = self.head.value.load(Ordering::Acquire);
+        cached_head.wrapping_sub(tail) as usize
+    }
+
     /// Push a value into the ring buffer.
     /// Returns Err(value) if buffer is full (backpressure - caller should handle).
     ///
@@ -64,11 +130,9 @@ impl<T, const N: usize> RingBuffer<T, N> {
     pub fn push(&self, value: T) -> Result<(), T> {
         // Load with Relaxed - only producer modifies head
         let head = self.head.value.load(Ordering::Relaxed);
-        // Load with Acquire - synchronize with consumer's Release store to tail
-        let tail = self.tail.value.load(Ordering::Acquire);
 
-        // Check if buffer is full
-        if head.wrapping_sub(tail) >= N as u64 {
+        // Check if buffer is full (touches shared tail only if cache says so)
+        if self.producer_free(head, 1) == 0 {
             return Err(value);
         }
 
@@ -94,11 +158,9 @@ impl<T, const N: usize> RingBuffer<T, N> {
     pub fn pop(&self) -> Option<T> {
         // Load with Relaxed - only consumer modifies tail
         let tail = self.tail.value.load(Ordering::Relaxed);
-        // Load with Acquire - synchronize with producer's Release store to head
-        let head = self.head.value.load(Ordering::Acquire);
 
-        // Check if buffer is empty
-        if tail == head {
+        // Check if buffer is empty (touches shared head only if cache says so)
+        if self.consumer_available(tail, 1) == 0 {
             return None;
         }
 
@@ -116,6 +178,92 @@ impl<T, const N: usize> RingBuffer<T, N> {
         Some(value)
     }
 
+    /// Push as many values from `values` as fit, publishing head once.
+    /// Returns the number of values pushed (0 if the buffer is full).
+    ///
+    /// # Safety
+    /// Only one thread (producer) may call this method.
+    pub fn push_slice(&self, values: &[T]) -> usize
+    where
+        T: Copy,
+    {
+        let head = self.head.value.load(Ordering::Relaxed);
+        let count = values.len().min(self.producer_free(head, values.len()));
+
+        for (i, value) in values[..count].iter().enumerate() {
+            let idx = (head.wrapping_add(i as u64) as usize) & (N - 1);
+            unsafe {
+                let slot = &mut *self.slots[idx].get();
+                ptr::write(slot, MaybeUninit::new(*value));
+            }
+        }
+
+        if count > 0 {
+            // Release: all slot writes complete before the single head update
+            self.head
+                .value
+                .store(head.wrapping_add(count as u64), Ordering::Release);
+        }
+        count
+    }
+
+    /// Pop up to `out.len()` values into `out`, publishing tail once.
+    /// Returns the number of values written to the front of `out`.
+    ///
+    /// # Safety
+    /// Only one thread (consumer) may call this method.
+    pub fn pop_into(&self, out: &mut [T]) -> usize {
+        let tail = self.tail.value.load(Ordering::Relaxed);
+        let count = out.len().min(self.consumer_available(tail, out.len()));
+
+        for (i, dst) in out[..count].iter_mut().enumerate() {
+            let idx = (tail.wrapping_add(i as u64) as usize) & (N - 1);
+            *dst = unsafe {
+                let slot = &*self.slots[idx].get();
+                ptr::read(slot).assume_init()
+            };
+        }
+
+        if count > 0 {
+            // Release: all slot reads complete before the single tail update
+            self.tail
+                .value
+                .store(tail.wrapping_add(count as u64), Ordering::Release);
+        }
+        count
+    }
+
+    /// Reserve up to `max` free slots for in-place writes.
+    ///
+    /// The reservation is contiguous in memory, so it stops at the physical
+    /// end of the buffer; it may be shorter than `max` (or empty when full).
+    /// Nothing is visible to the consumer until `Reservation::commit`.
+    ///
+    /// # Safety
+    /// Only one thread (producer) may call this method, and only one
+    /// reservation may be live at a time: two live reservations hand out
+    /// aliasing `&mut` views of the same free slots. Drop or commit the
+    /// previous `Reservation` before reserving again.
+    pub unsafe fn reserve(&self, max: usize) -> Reservation<'_, T, N> {
+        let head = self.head.value.load(Ordering::Relaxed);
+        let start = (head as usize) & (N - 1);
+        let wanted = max.min(N - start);
+        let len = wanted.min(self.producer_free(head, wanted));
+
+        // Safe: slots [start, start + len) are free and owned by the producer.
+        // UnsafeCell<MaybeUninit<T>> is repr(transparent) over MaybeUninit<T>.
+        let slots = unsafe {
+            let base = self.slots[start..].as_ptr() as *mut MaybeUninit<T>;
+            core::slice::from_raw_parts_mut(base, len)
+        };
+
+        Reservation {
+            ring: self,
+            head,
+            slots,
+        }
+    }
+
     /// Check if buffer is empty (may be stale immediately)
     pub fn is_empty(&self) -> bool {
         let tail = self.tail.value.load(Ordering::Relaxed);
@@ -138,6 +286,53 @@ impl<T, const N: usize> RingBuffer<T, N> {
     }
 }
 
+impl<T, const N: usize> Default for RingBuffer<T, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Producer-side slot reservation returned by `RingBuffer::reserve`.
+///
+/// Write values in place through `slots`, then `commit` the initialized
+/// prefix with a single head update. Dropping without committing publishes
+/// nothing.
+pub struct Reservation<'a, T, const N: usize> {
+    ring: &'a RingBuffer<T, N>,
+    head: u64,
+    slots: &'a mut [MaybeUninit<T>],
+}
+
+impl<T, const N: usize> Reservation<'_, T, N> {
+    /// Number of reserved slots
+    pub fn len(&self) -> usize {
+        self.slots.len()
+    }
+
+    /// True if no slots could be reserved (buffer full)
+    pub fn is_empty(&self) -> bool {
+        self.slots.is_empty()
+    }
+
+    /// Reserved slots, in ring order
+    pub fn slots(&mut self) -> &mut [MaybeUninit<T>] {
+        self.slots
+    }
+
+    /// Publish the first `count` reserved slots to the consumer.
+    ///
+    /// # Safety
+    /// `count <= self.len()` and slots `[0, count)` must have been initialized.
+    pub unsafe fn commit(self, count: usize) {
+        debug_assert!(count <= self.slots.len());
+        // Release: in-place writes complete before head increment
+        self.ring
+            .head
+            .value
+            .store(self.head.wrapping_add(count as u64), Ordering::Release);
+    }
+}
+
 // Safety: RingBuffer can be shared between threads (SPSC pattern)
 unsafe impl<T: Send, const N: usize> Send for RingBuffer<T, N> {}
 unsafe impl<T: Send, const N: usize> Sync for RingBuffer<T, N> {}
@@ -229,4 +424,103 @@ mod tests {
             assert_eq!(ring.pop(), Some(i));
         }
     }
+
+    #[test]
+    fn test_boxed_large_ring() {
+        // 8 MiB of slots: would not fit on a test thread's stack
+        let ring = RingBuffer::<[u64; 128], 8192>::boxed();
+        assert!(ring.is_empty());
+
+        for i in 0..8192 {
+            assert!(ring.push([i; 128]).is_ok());
+        }
+        assert!(ring.push([0; 128]).is_err());
+        assert_eq!(ring.pop(), Some([0; 128]));
+        assert_eq!(ring.pop(), Some([1; 128]));
+    }
+
+    #[test]
+    fn test_push_slice_pop_into() {
+        let ring = RingBuffer::<u64, 8>::new();
+
+        // Only as many as fit are pushed
+        assert_eq!(ring.push_slice(&[0, 1, 2, 3, 4, 5, 6, 7, 8, 9]), 8);
+        assert!(ring.is_full());
+        assert_eq!(ring.push_slice(&[99]), 0);
+
+        let mut out = [0u64; 5];
+        assert_eq!(ring.pop_into(&mut out), 5);
+        assert_eq!(out, [0, 1, 2, 3, 4]);
+
+        // Wraps around the physical end of the buffer
+        assert_eq!(ring.push_slice(&[8, 9, 10, 11]), 4);
+        let mut out = [0u64; 16];
+        assert_eq!(ring.pop_into(&mut out), 7);
+        assert_eq!(&out[..7], &[5, 6, 7, 8, 9, 10, 11]);
+        assert_eq!(ring.pop_into(&mut out), 0);
+    }
+
+    #[test]
+    fn test_reserve_commit() {
+        let ring = RingBuffer::<u64, 8>::new();
+        ring.push_slice(&[0; 6]);
+        let mut out = [0u64; 6];
+        ring.pop_into(&mut out);
+
+        // head is at physical index 6: reservation stops at the buffer end
+        // Safety: single thread, one reservation live at a time
+        let mut res = unsafe { ring.reserve(4) };
+        assert_eq!(res.len(), 2);
+        for (i, slot) in res.slots().iter_mut().enumerate() {
+            slot.write(100 + i as u64);
+        }
+        unsafe { res.commit(2) };
+
+        // Uncommitted reservations publish nothing
+        {
+            let mut res = unsafe { ring.reserve(3) };
+            assert_eq!(res.len(), 3);
+            res.slots()[0].write(999);
+        }
+        assert_eq!(ring.len(), 2);
+
+        // Partial commit publishes only the initialized prefix
+        let mut res = unsafe { ring.reserve(3) };
+        res.slots()[0].write(200);
+        unsafe { res.commit(1) };
+
+        assert_eq!(ring.pop(), Some(100));
+        assert_eq!(ring.pop(), Some(101));
+        assert_eq!(ring.pop(), Some(200));
+        assert_eq!(ring.pop(), None);
+    }
+
+    #[test]
+    fn test_reserve_full() {
+        let ring = RingBuffer::<u64, 4>::new();
+        ring.push_slice(&[1, 2, 3, 4]);
+        assert!(unsafe { ring.reserve(4) }.is_empty());
+    }
+
+    #[test]
+    fn test_cached_indices_refresh() {
+        let ring = RingBuffer::<u64, 4>::new();
+
+        // Producer fills using a stale cached tail of 0
+        for i in 0..4 {
+            assert!(ring.push(i).is_ok());
+        }
+        assert_eq!(ring.push(4), Err(4));
+
+        // Consumer frees two slots; producer must refresh to see them
+        assert_eq!(ring.pop(), Some(0));
+        assert_eq!(ring.pop(), Some(1));
+        assert_eq!(ring.push_slice(&[4, 5, 6]), 2);
+
+        // Consumer's cached head is stale after the producer wrapped
+        let mut out = [0u64; 8];
+        assert_eq!(ring.pop_into(&mut out), 4);
+        assert_eq!(&out[..4], &[2, 3, 4, 5]);
+        assert_eq!(ring.pop(), None);
+    }
 }
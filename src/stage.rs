@@ -0,0 +1,417 @@
+/// Pluggable pipeline stages
+///
+/// A `Stage` is the per-item logic of a pipeline thread: it receives one
+/// input at a time and emits zero or more outputs. `run_stage` supplies
+/// everything around it: popping the input ring, waiting when it is empty
+/// (`WaitStrategy`), pushing to the downstream ring and waking its consumer,
+/// per-stage counters, stage-latency telemetry and the stop/drain protocol.
+///
+/// Usage:
+/// ```
+/// # use velox_engine::{init_tsc, run_stage, Emit, Notifier, RingBuffer, RingEmitter, Stage,
+/// #     StageContext, StageStats, Transaction, WaitStrategy};
+/// # use std::sync::atomic::AtomicBool;
+/// /// Pre-trade risk check: drop oversized orders
+/// struct MaxSize(u32);
+///
+/// impl Stage for MaxSize {
+///     type Input = Transaction;
+///     type Output = Transaction;
+///
+///     fn name(&self) -> &'static str {
+///         "max_size"
+///     }
+///
+///     fn process<E: Emit<Transaction>>(&mut self, txn: Transaction, out: &mut E) {
+///         if txn.size <= self.0 {
+///             let _ = out.emit(txn);
+///         }
+///     }
+/// }
+///
+/// # init_tsc();
+/// let input = RingBuffer::<Transaction, 16>::new();
+/// let output = RingBuffer::<Transaction, 16>::new();
+/// let (input_ready, output_ready) = (Notifier::new(), Notifier::new());
+/// let (stop, stats) = (AtomicBool::new(true), StageStats::new());
+///
+/// input.push(Transaction::new_unchecked(1, 1_000_000, 10, 0, 0)).unwrap();
+/// input.push(Transaction::new_unchecked(2, 1_000_000, 900, 0, 0)).unwrap();
+///
+/// // `stop` is already set, so this drains the input and returns
+/// let ctx = StageContext::new(WaitStrategy::BusySpin, &input_ready, &stop, &stats);
+/// let mut out = RingEmitter::new(&output, &output_ready, &stats);
+/// run_stage(&mut MaxSize(100), &input, &mut out, ctx);
+///
+/// assert_eq!(output.pop().unwrap().id, 1);
+/// assert!(output.pop().is_none());
+/// ```
+use crate::ring::RingBuffer;
+use crate::telemetry;
+use crate::tsc::{rdtsc, tsc_to_ns};
+use crate::wait::{Notifier, WaitStrategy, Waiter};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::time::Duration;
+
+/// Inputs processed between ring utilization samples
+const UTILIZATION_SAMPLE_INTERVAL: u64 = 1000;
+
+/// Downstream handle a stage emits into
+pub trait Emit<T> {
+    /// Forward `item`; returns it back if the downstream ring is full
+    fn emit(&mut self, item: T) -> Result<(), T>;
+}
+
+/// Per-item logic of one pipeline thread; `run_stage` drives it
+pub trait Stage: Send {
+    type Input;
+    type Output;
+
+    /// Stage name for telemetry and thread naming
+    fn name(&self) -> &'static str;
+
+    /// Handle one input, emitting any number of outputs
+    fn process<E: Emit<Self::Output>>(&mut self, input: Self::Input, out: &mut E);
+
+    /// Called whenever the input ring is empty, before waiting.
+    /// Returns true if it did work, which keeps the stage from parking.
+    fn on_idle<E: Emit<Self::Output>>(&mut self, _out: &mut E) -> bool {
+        false
+    }
+
+    /// True if work is pending outside the input ring (e.g. a side input),
+    /// so a parked stage re-checks instead of sleeping through it
+    fn has_pending(&self) -> bool {
+        false
+    }
+
+    /// Longest the stage may stay parked, for time-based work in `on_idle`
+    fn idle_timeout(&self) -> Option<Duration> {
+        None
+    }
+
+    /// Emit anything buffered; called once the input is drained at shutdown
+    fn flush<E: Emit<Self::Output>>(&mut self, _out: &mut E) {}
+
+    /// Last call on the stage thread, after `flush`
+    fn on_shutdown(&mut self) {}
+}
+
+/// Counters maintained by `run_stage` and `RingEmitter`
+pub struct StageStats {
+    /// Inputs handed to `process`
+    pub processed: AtomicU64,
+    /// Outputs pushed downstream
+    pub emitted: AtomicU64,
+    /// Outputs rejected by a full downstream ring
+    pub dropped: AtomicU64,
+}
+
+impl StageStats {
+    pub fn new() -> Self {
+        Self {
+            processed: AtomicU64::new(0),
+            emitted: AtomicU64::new(0),
+            dropped: AtomicU64::new(0),
+        }
+    }
+}
+
+impl Default for StageStats {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// `Emit` into an SPSC ring, waking its consumer after each push
+pub struct RingEmitter<'a, T, const N: usize> {
+    ring: &'a RingBuffer<T, N>,
+    /// Notifier the downstream stage waits on
+    notifier: &'a Notifier,
+    stats: &'a StageStats,
+}
+
+impl<'a, T, const N: usize> RingEmitter<'a, T, N> {
+    pub fn new(ring: &'a RingBuffer<T, N>, notifier: &'a Notifier, stats: &'a StageStats) -> Self {
+        Self {
+            ring,
+            notifier,
+            stats,
+        }
+    }
+}
+
+impl<T, const N: usize> Emit<T> for RingEmitter<'_, T, N> {
+    #[inline]
+    fn emit(&mut self, item: T) -> Result<(), T> {
+        match self.ring.push(item) {
+            Ok(()) => {
+                self.notifier.notify();
+                self.stats.emitted.fetch_add(1, Ordering::Relaxed);
+                Ok(())
+            }
+            Err(item) => {
+                self.stats.dropped.fetch_add(1, Ordering::Relaxed);
+                Err(item)
+            }
+        }
+    }
+}
+
+/// `Emit` straight into a ring with no wakeup or counters (consumers that
+/// busy-spin or poll, tests)
+impl<T, const N: usize> Emit<T> for &RingBuffer<T, N> {
+    #[inline]
+    fn emit(&mut self, item: T) -> Result<(), T> {
+        self.push(item)
+    }
+}
+
+/// `Emit` for terminal stages: accepts and drops everything
+pub struct Discard;
+
+impl<T> Emit<T> for Discard {
+    #[inline]
+    fn emit(&mut self, _item: T) -> Result<(), T> {
+        Ok(())
+    }
+}
+
+/// Wait and shutdown wiring for one `run_stage` call
+pub struct StageContext<'a> {
+    wait: WaitStrategy,
+    /// Notifier producers of the input ring poke after pushing
+    notifier: &'a Notifier,
+    /// Raised once the input's producer has exited
+    stop: &'a AtomicBool,
+    stats: &'a StageStats,
+    /// Telemetry label for input ring utilization (None = not sampled)
+    input_label: Option<&'static str>,
+}
+
+impl<'a> StageContext<'a> {
+    pub fn new(
+        wait: WaitStrategy,
+        notifier: &'a Notifier,
+        stop: &'a AtomicBool,
+        stats: &'a StageStats,
+    ) -> Self {
+        Self {
+            wait,
+            notifier,
+            stop,
+            stats,
+            input_label: None,
+        }
+    }
+
+    /// Sample input ring utilization under `label` every 1000 inputs
+    pub fn with_input_label(mut self, label: &'static str) -> Self {
+        self.input_label = Some(label);
+        self
+    }
+}
+
+/// Run `stage` until `ctx`'s stop flag is raised and `input` is drained,
+/// then flush it and call `on_shutdown`.
+///
+/// The stop flag must only be raised after the input's producer has
+/// stopped pushing; otherwise items published after the final check are
+/// left in the ring.
+pub fn run_stage<S, E, const N: usize>(
+    stage: &mut S,
+    input: &RingBuffer<S::Input, N>,
+    out: &mut E,
+    ctx: StageContext<'_>,
+) where
+    S: Stage,
+    E: Emit<S::Output>,
+{
+    let name = stage.name();
+    let mut waiter = Waiter::new(ctx.wait, ctx.notifier);
+    if let Some(timeout) = stage.idle_timeout() {
+        waiter = waiter.with_park_timeout(timeout);
+    }
+    let mut sample_counter = 0u64;
+
+    loop {
+        let Some(item) = input.pop() else {
+            if stage.on_idle(out) {
+                waiter.reset();
+                continue;
+            }
+
+            // Producer is stopped before we are: empty now means done
+            if ctx.stop.load(Ordering::Acquire) && input.is_empty() {
+                break;
+            }
+            waiter.wait(|| !input.is_empty() || stage.has_pending());
+            continue;
+        };
+
+        // Reset wait state on successful work
+        waiter.reset();
+
+        let start_tsc = rdtsc();
+        stage.process(item, out);
+        ctx.stats.processed.fetch_add(1, Ordering::Relaxed);
+
+        // Instrument AFTER processing
+        let latency_ns = tsc_to_ns(rdtsc()) - tsc_to_ns(start_tsc);
+        telemetry::record_stage_processed(name, latency_ns as f64 / 1000.0);
+
+        sample_counter += 1;
+        if let Some(label) = ctx.input_label {
+            if sample_counter.is_multiple_of(UTILIZATION_SAMPLE_INTERVAL) {
+                let utilization = (input.len() as f64 / N as f64) * 100.0;
+                telemetry::record_ring_utilization(label, utilization);
+            }
+        }
+    }
+
+    stage.flush(out);
+    stage.on_shutdown();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tsc::init_tsc;
+    use std::sync::Arc;
+    use std::thread;
+
+    /// Emits each input twice; counts lifecycle calls
+    struct Doubler {
+        flushed: bool,
+        shut_down: bool,
+    }
+
+    impl Stage for Doubler {
+        type Input = u64;
+        type Output = u64;
+
+        fn name(&self) -> &'static str {
+            "doubler"
+        }
+
+        fn process<E: Emit<u64>>(&mut self, input: u64, out: &mut E) {
+            let _ = out.emit(input);
+            let _ = out.emit(input);
+        }
+
+        fn flush<E: Emit<u64>>(&mut self, out: &mut E) {
+            self.flushed = true;
+            let _ = out.emit(u64::MAX);
+        }
+
+        fn on_shutdown(&mut self) {
+            assert!(self.flushed, "flush runs before on_shutdown");
+            self.shut_down = true;
+        }
+    }
+
+    #[test]
+    fn test_run_stage_drains_and_flushes() {
+        init_tsc();
+        let input = RingBuffer::<u64, 16>::new();
+        let output = RingBuffer::<u64, 64>::new();
+        let (input_ready, output_ready) = (Notifier::new(), Notifier::new());
+        let stop = AtomicBool::new(true);
+        let stats = StageStats::new();
+
+        for i in 0..10 {
+            input.push(i).unwrap();
+        }
+
+        let mut stage = Doubler {
+            flushed: false,
+            shut_down: false,
+        };
+        let ctx = StageContext::new(WaitStrategy::BusySpin, &input_ready, &stop, &stats);
+        run_stage(&mut stage, &input, &mut RingEmitter::new(&output, &output_ready, &stats), ctx);
+
+        assert!(stage.shut_down);
+        assert!(input.is_empty());
+        assert_eq!(stats.processed.load(Ordering::Relaxed), 10);
+        assert_eq!(stats.emitted.load(Ordering::Relaxed), 21);
+        for i in 0..10 {
+            assert_eq!(output.pop(), Some(i));
+            assert_eq!(output.pop(), Some(i));
+        }
+        assert_eq!(output.pop(), Some(u64::MAX));
+    }
+
+    #[test]
+    fn test_run_stage_counts_downstream_drops() {
+        init_tsc();
+        let input = RingBuffer::<u64, 16>::new();
+        let output = RingBuffer::<u64, 4>::new();
+        let (input_ready, output_ready) = (Notifier::new(), Notifier::new());
+        let stop = AtomicBool::new(true);
+        let stats = StageStats::new();
+
+        for i in 0..8 {
+            input.push(i).unwrap();
+        }
+
+        let mut stage = Doubler {
+            flushed: false,
+            shut_down: false,
+        };
+        let ctx = StageContext::new(WaitStrategy::Backoff, &input_ready, &stop, &stats);
+        run_stage(&mut stage, &input, &mut RingEmitter::new(&output, &output_ready, &stats), ctx);
+
+        // 16 outputs + 1 flush into a 4-slot ring nobody drains
+        assert_eq!(stats.emitted.load(Ordering::Relaxed), 4);
+        assert_eq!(stats.dropped.load(Ordering::Relaxed), 13);
+    }
+
+    #[test]
+    fn test_run_stage_cross_thread_with_park() {
+        init_tsc();
+        const COUNT: u64 = 10_000;
+        let input = Arc::new(RingBuffer::<u64, 64>::new());
+        let notifier = Arc::new(Notifier::new());
+        let stop = Arc::new(AtomicBool::new(false));
+        let stats = Arc::new(StageStats::new());
+
+        /// Terminal stage summing its input
+        struct Sum(u64);
+
+        impl Stage for Sum {
+            type Input = u64;
+            type Output = ();
+
+            fn name(&self) -> &'static str {
+                "sum"
+            }
+
+            fn process<E: Emit<()>>(&mut self, input: u64, _out: &mut E) {
+                self.0 += input;
+            }
+        }
+
+        let consumer = {
+            let (input, notifier) = (Arc::clone(&input), Arc::clone(&notifier));
+            let (stop, stats) = (Arc::clone(&stop), Arc::clone(&stats));
+            thread::spawn(move || {
+                let mut stage = Sum(0);
+                let ctx = StageContext::new(WaitStrategy::Park, &notifier, &stop, &stats);
+                run_stage(&mut stage, &input, &mut Discard, ctx);
+                stage.0
+            })
+        };
+
+        for i in 0..COUNT {
+            while input.push(i).is_err() {
+                std::hint::spin_loop();
+            }
+            notifier.notify();
+        }
+        stop.store(true, Ordering::Release);
+        notifier.notify();
+
+        assert_eq!(consumer.join().unwrap(), (0..COUNT).sum::<u64>());
+        assert_eq!(stats.processed.load(Ordering::Relaxed), COUNT);
+    }
+}
@@ -34,7 +34,10 @@ pub struct TelemetryHandles {
     pub transactions_total: Counter<u64>,
     pub bundles_total: Counter<u64>,
     pub orderbook_timeouts_total: Counter<u64>,
+    pub orderbook_rejected_total: Counter<u64>,
     pub ingress_dropped_total: Counter<u64>,
+    pub bundles_delayed_total: Counter<u64>,
+    pub bundles_dropped_total: Counter<u64>,
 
     // Histograms
     pub stage_latency_us: Histogram<f64>,
@@ -60,7 +63,11 @@ pub struct TelemetryHandles {
 ///
 /// # Example
 /// ```no_run
+/// # use velox_engine::telemetry::init_telemetry;
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
 /// init_telemetry("velox-engine", "http://localhost:4317")?;
+/// # Ok(())
+/// # }
 /// ```
 pub fn init_telemetry(service_name: &str, otlp_endpoint: &str) -> Result<(), Box<dyn Error>> {
     // Create resource with service name
@@ -109,12 +116,30 @@ pub fn init_telemetry(service_name: &str, otlp_endpoint: &str) -> Result<(), Box
         .with_unit("timeouts")
         .build();
 
+    let orderbook_rejected_total = meter
+        .u64_counter("orderbook_rejected_total")
+        .with_description("Total orders rejected by the matching engine")
+        .with_unit("orders")
+        .build();
+
     let ingress_dropped_total = meter
         .u64_counter("ingress_dropped_total")
         .with_description("Total transactions dropped by ingress (ring full)")
         .with_unit("transactions")
         .build();
 
+    let bundles_delayed_total = meter
+        .u64_counter("bundles_delayed_total")
+        .with_description("Total bundles held for retry (output ring full)")
+        .with_unit("bundles")
+        .build();
+
+    let bundles_dropped_total = meter
+        .u64_counter("bundles_dropped_total")
+        .with_description("Total held bundles dropped (overflow full or shutdown)")
+        .with_unit("bundles")
+        .build();
+
     let stage_latency_us = meter
         .f64_histogram("stage_latency_us")
         .with_description("Per-stage processing latency in microseconds")
@@ -153,7 +178,10 @@ pub fn init_telemetry(service_name: &str, otlp_endpoint: &str) -> Result<(), Box
         transactions_total,
         bundles_total,
         orderbook_timeouts_total,
+        orderbook_rejected_total,
         ingress_dropped_total,
+        bundles_delayed_total,
+        bundles_dropped_total,
         stage_latency_us,
         e2e_latency_us,
         ring_buffer_utilization,
@@ -171,6 +199,9 @@ pub fn init_telemetry(service_name: &str, otlp_endpoint: &str) -> Result<(), Box
 
 /// Get global telemetry handles
 ///
+/// The `record_*` helpers are no-ops until `init_telemetry()` succeeds, so
+/// library users that never initialize telemetry pay only a load.
+///
 /// # Panics
 /// Panics if telemetry not initialized via `init_telemetry()`
 pub fn telemetry() -> &'static TelemetryHandles {
@@ -204,7 +235,19 @@ pub fn shutdown_telemetry() {
 /// * `latency_us` - Stage processing latency in microseconds
 #[inline]
 pub fn record_transaction_processed(stage: &str, _txn_id: u64, latency_us: f64) {
-    let handles = telemetry();
+    record_stage_processed(stage, latency_us);
+}
+
+/// Record one input processed by a stage, without transaction correlation
+///
+/// # Arguments
+/// * `stage` - Stage name, including custom `Stage` implementations
+/// * `latency_us` - Stage processing latency in microseconds
+#[inline]
+pub fn record_stage_processed(stage: &str, latency_us: f64) {
+    let Some(handles) = TELEMETRY.get() else {
+        return;
+    };
 
     // Increment counter
     handles
@@ -224,7 +267,9 @@ pub fn record_transaction_processed(stage: &str, _txn_id: u64, latency_us: f64)
 /// * `txn_id` - Transaction ID for correlation
 #[inline]
 pub fn record_e2e_latency(latency_us: f64, _txn_id: u64) {
-    let handles = telemetry();
+    let Some(handles) = TELEMETRY.get() else {
+        return;
+    };
     handles.e2e_latency_us.record(latency_us, &[]);
 }
 
@@ -232,10 +277,12 @@ pub fn record_e2e_latency(latency_us: f64, _txn_id: u64) {
 ///
 /// # Arguments
 /// * `bundle_size` - Number of transactions in bundle
-/// * `reason` - Flush reason: "size" or "timeout"
+/// * `reason` - Flush reason: `FlushReason::as_str()` or "shutdown"
 #[inline]
 pub fn record_bundle_flushed(bundle_size: u32, reason: &str) {
-    let handles = telemetry();
+    let Some(handles) = TELEMETRY.get() else {
+        return;
+    };
     handles.bundles_total.add(
         1,
         &[
@@ -248,17 +295,48 @@ pub fn record_bundle_flushed(bundle_size: u32, reason: &str) {
 /// Record orderbook update timeout (CAS contention)
 #[inline]
 pub fn record_orderbook_timeout() {
-    let handles = telemetry();
+    let Some(handles) = TELEMETRY.get() else {
+        return;
+    };
     handles.orderbook_timeouts_total.add(1, &[]);
 }
 
+/// Record order rejected by the matching engine
+#[inline]
+pub fn record_orderbook_rejected() {
+    let Some(handles) = TELEMETRY.get() else {
+        return;
+    };
+    handles.orderbook_rejected_total.add(1, &[]);
+}
+
 /// Record ingress drop (ring buffer full)
 #[inline]
 pub fn record_ingress_dropped() {
-    let handles = telemetry();
+    let Some(handles) = TELEMETRY.get() else {
+        return;
+    };
     handles.ingress_dropped_total.add(1, &[]);
 }
 
+/// Record bundles held for retry because the output ring was full
+#[inline]
+pub fn record_bundles_delayed(count: u64) {
+    let Some(handles) = TELEMETRY.get() else {
+        return;
+    };
+    handles.bundles_delayed_total.add(count, &[]);
+}
+
+/// Record held bundles that were dropped
+#[inline]
+pub fn record_bundles_dropped(count: u64) {
+    let Some(handles) = TELEMETRY.get() else {
+        return;
+    };
+    handles.bundles_dropped_total.add(count, &[]);
+}
+
 /// Record ring buffer utilization percentage
 ///
 /// # Arguments
@@ -266,7 +344,9 @@ pub fn record_ingress_dropped() {
 /// * `utilization_pct` - Utilization as percentage (0.0 - 100.0)
 #[inline]
 pub fn record_ring_utilization(stage: &str, utilization_pct: f64) {
-    let handles = telemetry();
+    let Some(handles) = TELEMETRY.get() else {
+        return;
+    };
     handles.ring_buffer_utilization.record(
         utilization_pct,
         &[KeyValue::new("stage", stage.to_string())],
@@ -280,7 +360,9 @@ pub fn record_ring_utilization(stage: &str, utilization_pct: f64) {
 /// * `depth` - Number of active orders on this side
 #[inline]
 pub fn record_orderbook_depth(side: &str, depth: u64) {
-    let handles = telemetry();
+    let Some(handles) = TELEMETRY.get() else {
+        return;
+    };
     handles
         .orderbook_depth
         .record(depth, &[KeyValue::new("side", side.to_string())]);
@@ -292,6 +374,14 @@ mod tests {
 
     #[test]
     fn test_telemetry_init() {
+        // The OTLP exporter spawns onto Tokio, so enter a runtime first
+        let rt = tokio::runtime::Builder::new_multi_thread()
+            .worker_threads(1)
+            .enable_all()
+            .build()
+            .unwrap();
+        let _guard = rt.enter();
+
         // Test that we can initialize telemetry
         let result = init_telemetry("test-service", "http://localhost:4317");
 
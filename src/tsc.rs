@@ -46,8 +46,7 @@ pub fn calibrate_tsc() -> f64 {
     let end_tsc = rdtsc();
     let elapsed_ns = start.elapsed().as_nanos() as u64;
 
-    let tsc_per_ns = (end_tsc - start_tsc) as f64 / elapsed_ns as f64;
-    tsc_per_ns
+    (end_tsc - start_tsc) as f64 / elapsed_ns as f64
 }
 
 /// Initialize TSC calibration (call once at startup, before any threads)
@@ -59,10 +58,7 @@ pub fn calibrate_tsc() -> f64 {
 /// # Thread Safety
 /// Safe to call multiple times (idempotent), but calibration only happens once.
 pub fn init_tsc() {
-    TSC_PER_NS.get_or_init(|| {
-        let factor = calibrate_tsc();
-        factor
-    });
+    TSC_PER_NS.get_or_init(calibrate_tsc);
 }
 
 /// Check if TSC has been initialized
@@ -99,6 +95,69 @@ pub fn spin_sleep_ns(ns: u64) {
     }
 }
 
+/// Time source for ingress generators: the TSC in production, a manual
+/// clock where runs must be reproducible
+pub trait Clock {
+    /// Current time in nanoseconds
+    fn now_ns(&mut self) -> u64;
+
+    /// Wait until `ns` nanoseconds have passed
+    fn sleep_ns(&mut self, ns: u64);
+}
+
+impl<C: Clock + ?Sized> Clock for &mut C {
+    fn now_ns(&mut self) -> u64 {
+        (**self).now_ns()
+    }
+
+    fn sleep_ns(&mut self, ns: u64) {
+        (**self).sleep_ns(ns)
+    }
+}
+
+/// `tsc_to_ns(rdtsc())` and `spin_sleep_ns` (call `init_tsc` first)
+#[derive(Debug, Default, Clone, Copy)]
+pub struct TscClock;
+
+impl Clock for TscClock {
+    #[inline]
+    fn now_ns(&mut self) -> u64 {
+        tsc_to_ns(rdtsc())
+    }
+
+    #[inline]
+    fn sleep_ns(&mut self, ns: u64) {
+        spin_sleep_ns(ns);
+    }
+}
+
+/// Simulated clock: only moves when slept on or advanced, so a generator
+/// driven by it produces the same timestamps on every run, instantly
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ManualClock {
+    now_ns: u64,
+}
+
+impl ManualClock {
+    pub fn new(start_ns: u64) -> Self {
+        Self { now_ns: start_ns }
+    }
+
+    pub fn advance(&mut self, ns: u64) {
+        self.now_ns += ns;
+    }
+}
+
+impl Clock for ManualClock {
+    fn now_ns(&mut self) -> u64 {
+        self.now_ns
+    }
+
+    fn sleep_ns(&mut self, ns: u64) {
+        self.advance(ns);
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -124,4 +183,12 @@ mod tests {
         assert!(elapsed_ns > 5_000_000 && elapsed_ns < 20_000_000,
                 "Expected elapsed_ns to be between 5ms and 20ms, got {}ns", elapsed_ns);
     }
+
+    #[test]
+    fn test_manual_clock() {
+        let mut clock = ManualClock::new(1_000);
+        clock.sleep_ns(500);
+        clock.advance(250);
+        assert_eq!(clock.now_ns(), 1_750);
+    }
 }
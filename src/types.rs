@@ -1,11 +1,40 @@
 use core::fmt;
-use core::ptr;
 use static_assertions::{const_assert, const_assert_eq};
 use crate::errors::{TransactionError, BundleError};
 
 /// Fixed bundle size for compile-time allocation
 pub const BUNDLE_MAX: usize = 16;
 
+/// Lifecycle action carried by a `Transaction`.
+/// Stored on the wire as a single byte; `New` is 0 so zeroed padding in
+/// older messages decodes as a plain new order.
+#[repr(u8)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum OrderAction {
+    /// Add a new order (match, then rest any remainder)
+    New = 0,
+    /// Remove the resting order with this id
+    Cancel = 1,
+    /// Amend the resting order's size in place (price is ignored)
+    Modify = 2,
+    /// Cancel the resting order and re-enter it at a new price and size
+    Replace = 3,
+}
+
+impl TryFrom<u8> for OrderAction {
+    type Error = TransactionError;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(Self::New),
+            1 => Ok(Self::Cancel),
+            2 => Ok(Self::Modify),
+            3 => Ok(Self::Replace),
+            other => Err(TransactionError::UnknownAction(other)),
+        }
+    }
+}
+
 /// Transaction represents a single order on the order book.
 /// Zero-heap, repr(C) for cache predictability.
 #[repr(C)]
@@ -15,7 +44,8 @@ pub struct Transaction {
     pub price: i64,        // Fixed-point: divide by 10000 for decimal (4 places)
     pub size: u32,
     pub side: u8,          // 0=bid, 1=ask
-    _padding1: [u8; 3],    // Align to 8 bytes
+    pub action: u8,        // OrderAction as u8 (0=new)
+    _padding1: [u8; 2],    // Align to 8 bytes
     pub ingress_ts_ns: u64,
 }
 
@@ -51,7 +81,52 @@ impl Transaction {
             price,
             size,
             side,
-            _padding1: [0; 3],
+            action: OrderAction::New as u8,
+            _padding1: [0; 2],
+            ingress_ts_ns,
+        })
+    }
+
+    /// Create a lifecycle transaction with validation.
+    ///
+    /// Only the fields the action uses are checked: `New` and `Replace` need
+    /// a positive price and non-zero size, `Modify` needs a non-zero size and
+    /// `Cancel` needs neither.
+    ///
+    /// # Errors
+    /// - `InvalidSide`: if side is not 0 (bid) or 1 (ask)
+    /// - `NegativePrice`: if the action needs a price and price is <= 0
+    /// - `ZeroSize`: if the action needs a size and size is 0
+    pub fn with_action(
+        id: u64,
+        price: i64,
+        size: u32,
+        side: u8,
+        action: OrderAction,
+        ingress_ts_ns: u64,
+    ) -> Result<Self, TransactionError> {
+        if side > 1 {
+            return Err(TransactionError::InvalidSide(side));
+        }
+
+        let needs_price = matches!(action, OrderAction::New | OrderAction::Replace);
+        let needs_size = action != OrderAction::Cancel;
+
+        if needs_price && price <= 0 {
+            return Err(TransactionError::NegativePrice(price));
+        }
+
+        if needs_size && size == 0 {
+            return Err(TransactionError::ZeroSize);
+        }
+
+        Ok(Self {
+            id,
+            price,
+            size,
+            side,
+            action: action as u8,
+            _padding1: [0; 2],
             ingress_ts_ns,
         })
     }
@@ -73,34 +148,58 @@ impl Transaction {
             price,
             size,
             side,
-            _padding1: [0; 3],
+            action: OrderAction::New as u8,
+            _padding1: [0; 2],
             ingress_ts_ns,
         }
     }
 
-    /// Zero-copy serialization to bytes
+    /// Serialize to the 32-byte little-endian payload, laid out like the
+    /// `repr(C)` struct (identical to the old in-memory copy on
+    /// little-endian hosts). Use `encode` for a versioned, checksummed frame.
     pub fn to_bytes(&self) -> [u8; 32] {
-        unsafe {
-            let mut bytes = [0u8; 32];
-            ptr::copy_nonoverlapping(
-                self as *const Self as *const u8,
-                bytes.as_mut_ptr(),
-                32,
-            );
-            bytes
+        let mut bytes = [0u8; 32];
+        bytes[0..8].copy_from_slice(&self.id.to_le_bytes());
+        bytes[8..16].copy_from_slice(&self.price.to_le_bytes());
+        bytes[16..20].copy_from_slice(&self.size.to_le_bytes());
+        bytes[20] = self.side;
+        bytes[21] = self.action;
+        bytes[22..24].copy_from_slice(&self._padding1);
+        bytes[24..32].copy_from_slice(&self.ingress_ts_ns.to_le_bytes());
+        bytes
+    }
+
+    /// Deserialize and validate the 32-byte little-endian payload written
+    /// by `to_bytes`, applying the same checks as `with_action`.
+    ///
+    /// # Errors
+    /// - `UnknownAction`: if the action byte is not a known `OrderAction`
+    /// - `NonZeroPadding`: if the reserved bytes are not zero
+    /// - `InvalidSide`, `NegativePrice`, `ZeroSize`: as for `with_action`
+    pub fn from_bytes(bytes: &[u8; 32]) -> Result<Self, TransactionError> {
+        let raw = Self::from_bytes_unchecked(bytes);
+        let action = OrderAction::try_from(raw.action)?;
+        if raw._padding1 != [0; 2] {
+            return Err(TransactionError::NonZeroPadding(raw._padding1));
         }
+        Self::with_action(raw.id, raw.price, raw.size, raw.side, action, raw.ingress_ts_ns)
     }
 
-    /// Zero-copy deserialization from bytes
-    pub fn from_bytes(bytes: &[u8; 32]) -> Self {
-        unsafe {
-            let mut txn = core::mem::MaybeUninit::<Transaction>::uninit();
-            ptr::copy_nonoverlapping(
-                bytes.as_ptr(),
-                txn.as_mut_ptr() as *mut u8,
-                32,
-            );
-            txn.assume_init()
+    /// Deserialize the 32-byte payload without any validation.
+    ///
+    /// Only for bytes this process produced itself (or that were already
+    /// validated): the result may hold any side, price, size, action or
+    /// padding. Untrusted input goes through `from_bytes` or `decode`.
+    pub fn from_bytes_unchecked(bytes: &[u8; 32]) -> Self {
+        let u64_at = |at: usize| u64::from_le_bytes(bytes[at..at + 8].try_into().unwrap());
+        Self {
+            id: u64_at(0),
+            price: u64_at(8) as i64,
+            size: u32::from_le_bytes(bytes[16..20].try_into().unwrap()),
+            side: bytes[20],
+            action: bytes[21],
+            _padding1: [bytes[22], bytes[23]],
+            ingress_ts_ns: u64_at(24),
         }
     }
 
@@ -118,6 +217,14 @@ impl Transaction {
     pub fn is_ask(&self) -> bool {
         self.side == 1
     }
+
+    /// Decode the lifecycle action
+    ///
+    /// # Errors
+    /// - `UnknownAction`: if the action byte is not a known `OrderAction`
+    pub fn order_action(&self) -> Result<OrderAction, TransactionError> {
+        OrderAction::try_from(self.action)
+    }
 }
 
 impl fmt::Debug for Transaction {
@@ -127,17 +234,78 @@ impl fmt::Debug for Transaction {
             .field("price", &self.price_f64())
             .field("size", &self.size)
             .field("side", &if self.is_bid() { "BID" } else { "ASK" })
+            .field("action", &self.order_action())
+            .field("ingress_ts_ns", &self.ingress_ts_ns)
+            .finish()
+    }
+}
+
+/// Fill represents one execution between a resting (maker) order and an
+/// incoming (taker) order. Emitted by the matching engine.
+#[repr(C)]
+#[derive(Copy, Clone, PartialEq, Eq)]
+pub struct Fill {
+    pub maker_id: u64,
+    pub taker_id: u64,
+    pub price: i64,        // Execution price (maker's price), fixed-point
+    pub size: u32,
+    pub taker_side: u8,    // 0=bid, 1=ask
+    _padding1: [u8; 3],    // Align to 8 bytes
+    pub ingress_ts_ns: u64, // Taker's ingress timestamp
+}
+
+// Compile-time assertions for Fill layout
+const_assert_eq!(core::mem::size_of::<Fill>(), 40);
+const_assert_eq!(core::mem::align_of::<Fill>(), 8);
+
+impl Fill {
+    /// Create a new fill
+    pub fn new(maker_id: u64, taker_id: u64, price: i64, size: u32, taker_side: u8, ingress_ts_ns: u64) -> Self {
+        Self {
+            maker_id,
+            taker_id,
+            price,
+            size,
+            taker_side,
+            _padding1: [0; 3],
+            ingress_ts_ns,
+        }
+    }
+
+    /// Get price as f64 for display
+    pub fn price_f64(&self) -> f64 {
+        self.price as f64 / 10000.0
+    }
+}
+
+impl fmt::Debug for Fill {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Fill")
+            .field("maker_id", &self.maker_id)
+            .field("taker_id", &self.taker_id)
+            .field("price", &self.price_f64())
+            .field("size", &self.size)
+            .field("taker_side", &if self.taker_side == 0 { "BID" } else { "ASK" })
             .field("ingress_ts_ns", &self.ingress_ts_ns)
             .finish()
     }
 }
 
-/// Bundle represents a batch of transactions ready for submission.
-/// Stack-allocated, zero-heap.
+/// Bundle represents a batch of up to `N` transactions ready for submission.
+/// Stack-allocated, zero-heap. `N` defaults to `BUNDLE_MAX`; venues with a
+/// different bundle limit instantiate their own capacity.
+///
+/// Layout is checked at compile time for every capacity used: `N` must be
+/// at least 1 and the struct is exactly `32 * N + 16` bytes.
+///
+/// ```compile_fail
+/// # use velox_engine::Bundle;
+/// let empty = Bundle::<0>::default();
+/// ```
 #[repr(C)]
 #[derive(Copy, Clone, PartialEq)]
-pub struct Bundle {
-    pub transactions: [Transaction; BUNDLE_MAX],
+pub struct Bundle<const N: usize = BUNDLE_MAX> {
+    pub transactions: [Transaction; N],
     pub count: u32,
     _padding: u32,  // Align to 8 bytes
     pub timestamp_ns: u64,
@@ -147,30 +315,38 @@ pub struct Bundle {
 const_assert!(core::mem::size_of::<Bundle>() == 32 * BUNDLE_MAX + 16);
 
 impl Bundle {
-    /// Create an empty bundle
+    /// Create an empty bundle (`Bundle::<N>::default()` for other capacities)
     pub fn new() -> Self {
-        Self {
-            transactions: [Transaction::new_unchecked(0, 1, 1, 0, 0); BUNDLE_MAX],
-            count: 0,
-            _padding: 0,
-            timestamp_ns: 0,
-        }
+        Self::default()
     }
+}
+
+impl<const N: usize> Bundle<N> {
+    /// Per-instantiation layout check, evaluated when a constructor for
+    /// this capacity is compiled
+    pub(crate) const LAYOUT: () = {
+        assert!(N >= 1, "bundle capacity must be at least 1");
+        assert!(N <= u32::MAX as usize, "bundle capacity must fit the u32 count");
+        assert!(core::mem::size_of::<Self>() == 32 * N + 16);
+        assert!(core::mem::align_of::<Self>() == 8);
+    };
 
     /// Create a bundle with transactions and validation
     ///
     /// # Errors
-    /// - `CountTooLarge`: if count exceeds BUNDLE_MAX
+    /// - `CountTooLarge`: if count exceeds the capacity `N`
     pub fn with_transactions(
-        transactions: [Transaction; BUNDLE_MAX],
+        transactions: [Transaction; N],
         count: u32,
         timestamp_ns: u64
     ) -> Result<Self, BundleError> {
+        let () = Self::LAYOUT;
+
         // Validate count
-        if count as usize > BUNDLE_MAX {
+        if count as usize > N {
             return Err(BundleError::CountTooLarge {
                 count,
-                max: BUNDLE_MAX,
+                max: N,
             });
         }
 
@@ -184,11 +360,12 @@ impl Bundle {
 
     /// Create a bundle without validation (for trusted inputs)
     pub fn with_transactions_unchecked(
-        transactions: [Transaction; BUNDLE_MAX],
+        transactions: [Transaction; N],
         count: u32,
         timestamp_ns: u64
     ) -> Self {
-        debug_assert!(count as usize <= BUNDLE_MAX, "count must not exceed BUNDLE_MAX");
+        let () = Self::LAYOUT;
+        debug_assert!(count as usize <= N, "count must not exceed capacity");
 
         Self {
             transactions,
@@ -198,6 +375,11 @@ impl Bundle {
         }
     }
 
+    /// Maximum number of transactions this bundle type holds
+    pub const fn capacity(&self) -> usize {
+        N
+    }
+
     /// Get active transactions (only up to count)
     pub fn active_transactions(&self) -> &[Transaction] {
         &self.transactions[..self.count as usize]
@@ -210,17 +392,24 @@ impl Bundle {
 
     /// Check if bundle is full
     pub fn is_full(&self) -> bool {
-        self.count as usize >= BUNDLE_MAX
+        self.count as usize >= N
     }
 }
 
-impl Default for Bundle {
+impl<const N: usize> Default for Bundle<N> {
+    /// Empty bundle
     fn default() -> Self {
-        Self::new()
+        let () = Self::LAYOUT;
+        Self {
+            transactions: [Transaction::new_unchecked(0, 1, 1, 0, 0); N],
+            count: 0,
+            _padding: 0,
+            timestamp_ns: 0,
+        }
     }
 }
 
-impl fmt::Debug for Bundle {
+impl<const N: usize> fmt::Debug for Bundle<N> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("Bundle")
             .field("count", &self.count)
@@ -240,11 +429,17 @@ mod tests {
         assert_eq!(core::mem::align_of::<Transaction>(), 8);
     }
 
+    #[test]
+    fn test_fill_layout() {
+        assert_eq!(core::mem::size_of::<Fill>(), 40);
+        assert_eq!(core::mem::align_of::<Fill>(), 8);
+    }
+
     #[test]
     fn test_transaction_serialization() {
         let txn = Transaction::new_unchecked(123, 1000000, 50, 0, 1234567890);
         let bytes = txn.to_bytes();
-        let txn2 = Transaction::from_bytes(&bytes);
+        let txn2 = Transaction::from_bytes(&bytes).unwrap();
 
         assert_eq!(txn.id, txn2.id);
         assert_eq!(txn.price, txn2.price);
@@ -290,6 +485,61 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_action_validation() {
+        // New transactions default to OrderAction::New
+        let txn = Transaction::new(1, 1000, 100, 0, 0).unwrap();
+        assert_eq!(txn.order_action(), Ok(OrderAction::New));
+
+        // Cancel needs neither price nor size
+        let cancel = Transaction::with_action(1, 0, 0, 0, OrderAction::Cancel, 0).unwrap();
+        assert_eq!(cancel.order_action(), Ok(OrderAction::Cancel));
+
+        // Modify needs a size but not a price
+        assert!(Transaction::with_action(1, 0, 10, 0, OrderAction::Modify, 0).is_ok());
+        assert_eq!(
+            Transaction::with_action(1, 0, 0, 0, OrderAction::Modify, 0),
+            Err(TransactionError::ZeroSize)
+        );
+
+        // Replace needs both
+        assert_eq!(
+            Transaction::with_action(1, 0, 10, 0, OrderAction::Replace, 0),
+            Err(TransactionError::NegativePrice(0))
+        );
+
+        // Unknown action bytes are rejected on decode
+        let mut bytes = txn.to_bytes();
+        bytes[21] = 9;
+        assert_eq!(Transaction::from_bytes(&bytes), Err(TransactionError::UnknownAction(9)));
+        assert_eq!(
+            Transaction::from_bytes_unchecked(&bytes).order_action(),
+            Err(TransactionError::UnknownAction(9))
+        );
+    }
+
+    #[test]
+    fn test_from_bytes_validates() {
+        let txn = Transaction::new_unchecked(1, 1000, 10, 1, 0);
+        let corrupt = |at: usize, value: u8| {
+            let mut bytes = txn.to_bytes();
+            bytes[at] = value;
+            Transaction::from_bytes(&bytes)
+        };
+
+        assert_eq!(corrupt(20, 2), Err(TransactionError::InvalidSide(2)));
+        assert_eq!(corrupt(22, 1), Err(TransactionError::NonZeroPadding([1, 0])));
+        // Top byte of the price: negative
+        assert_eq!(corrupt(15, 0x80), Err(TransactionError::NegativePrice(i64::MIN | 1000)));
+        let mut zero_size = txn;
+        zero_size.size = 0;
+        assert_eq!(Transaction::from_bytes(&zero_size.to_bytes()), Err(TransactionError::ZeroSize));
+
+        // A cancel needs neither price nor size
+        let cancel = Transaction::with_action(1, 0, 0, 0, OrderAction::Cancel, 0).unwrap();
+        assert_eq!(Transaction::from_bytes(&cancel.to_bytes()), Ok(cancel));
+    }
+
     #[test]
     fn test_bundle_validation() {
         let txns = [Transaction::new_unchecked(0, 1, 1, 0, 0); BUNDLE_MAX];
@@ -309,6 +559,19 @@ mod tests {
     fn test_bundle_layout() {
         let size = core::mem::size_of::<Bundle>();
         assert_eq!(size, 32 * BUNDLE_MAX + 16);
+        assert_eq!(core::mem::size_of::<Bundle<4>>(), 32 * 4 + 16);
+    }
+
+    #[test]
+    fn test_bundle_custom_capacity() {
+        let txns = [Transaction::new_unchecked(0, 1, 1, 0, 0); 4];
+        let bundle = Bundle::<4>::with_transactions(txns, 4, 0).unwrap();
+        assert!(bundle.is_full());
+        assert_eq!(bundle.capacity(), 4);
+        assert_eq!(
+            Bundle::<4>::with_transactions(txns, 5, 0),
+            Err(BundleError::CountTooLarge { count: 5, max: 4 })
+        );
     }
 
     #[test]
@@ -0,0 +1,341 @@
+/// Versioned, checksummed binary encoding for `Transaction` and `Bundle`
+///
+/// Every message is one frame, all integers little-endian:
+///
+/// ```text
+/// offset  size  field
+///      0     2  magic    b"VX"
+///      2     1  version  WIRE_VERSION
+///      3     1  kind     FrameKind (1 = transaction, 2 = bundle)
+///      4     4  length   payload length in bytes
+///      8     n  payload
+///    8+n     4  crc32    CRC-32 (IEEE) over header and payload
+/// ```
+///
+/// A transaction payload is the 32 bytes of `Transaction::to_bytes`. A
+/// bundle payload is `count: u32`, 4 reserved zero bytes, `timestamp_ns:
+/// u64`, then `count` transaction payloads (only the active ones).
+///
+/// Upgrading from `to_bytes`: the raw 32-byte form is unchanged on
+/// little-endian hosts and is now explicitly little-endian everywhere, so
+/// stored payloads still decode with `from_bytes`. New producers should send
+/// `encode()` frames; `Transaction::from_bytes(&raw)?.encode()` converts old
+/// payloads.
+///
+/// Decoding validates every transaction like `Transaction::from_bytes`, so a
+/// frame with a good checksum but an invalid order is still rejected.
+use crate::errors::{BundleError, WireError};
+use crate::types::{Bundle, Transaction};
+
+/// First two bytes of every frame
+pub const WIRE_MAGIC: [u8; 2] = *b"VX";
+/// Frame version written by this encoder
+pub const WIRE_VERSION: u8 = 1;
+/// Magic, version, kind and length
+pub const HEADER_LEN: usize = 8;
+/// CRC32 trailer
+pub const TRAILER_LEN: usize = 4;
+/// Encoded transaction payload
+const TXN_PAYLOAD_LEN: usize = 32;
+/// Bundle payload before its transactions: count, reserved, timestamp
+const BUNDLE_PREFIX_LEN: usize = 16;
+/// Size of an encoded transaction frame
+pub const TXN_FRAME_LEN: usize = HEADER_LEN + TXN_PAYLOAD_LEN + TRAILER_LEN;
+
+/// Message carried by a frame
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FrameKind {
+    Transaction = 1,
+    Bundle = 2,
+}
+
+impl TryFrom<u8> for FrameKind {
+    type Error = WireError;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            1 => Ok(Self::Transaction),
+            2 => Ok(Self::Bundle),
+            other => Err(WireError::UnknownKind(other)),
+        }
+    }
+}
+
+/// Parsed frame header
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FrameHeader {
+    pub version: u8,
+    pub kind: FrameKind,
+    /// Payload length in bytes
+    pub length: u32,
+}
+
+impl FrameHeader {
+    /// Parse and check the header at the start of `bytes`. The payload is
+    /// not inspected, so this also finds frame boundaries in a byte stream.
+    ///
+    /// # Errors
+    /// `Truncated` if fewer than `HEADER_LEN` bytes, then `BadMagic`,
+    /// `UnsupportedVersion`, `UnknownKind` or `BadLength`.
+    pub fn parse(bytes: &[u8]) -> Result<Self, WireError> {
+        if bytes.len() < HEADER_LEN {
+            return Err(WireError::Truncated { needed: HEADER_LEN, available: bytes.len() });
+        }
+        let magic = [bytes[0], bytes[1]];
+        if magic != WIRE_MAGIC {
+            return Err(WireError::BadMagic(magic));
+        }
+        if bytes[2] != WIRE_VERSION {
+            return Err(WireError::UnsupportedVersion(bytes[2]));
+        }
+        let kind = FrameKind::try_from(bytes[3])?;
+        let length = u32::from_le_bytes([bytes[4], bytes[5], bytes[6], bytes[7]]);
+
+        let valid = match kind {
+            FrameKind::Transaction => length as usize == TXN_PAYLOAD_LEN,
+            FrameKind::Bundle => {
+                (length as usize) >= BUNDLE_PREFIX_LEN
+                    && (length as usize - BUNDLE_PREFIX_LEN).is_multiple_of(TXN_PAYLOAD_LEN)
+            }
+        };
+        if !valid {
+            return Err(WireError::BadLength { kind: kind as u8, length });
+        }
+
+        Ok(Self { version: bytes[2], kind, length })
+    }
+
+    /// Whole frame size: header, payload and trailer
+    pub fn frame_len(&self) -> usize {
+        HEADER_LEN + self.length as usize + TRAILER_LEN
+    }
+}
+
+/// Write header, payload (already in `buf[HEADER_LEN..]`) and CRC; returns
+/// the frame length
+fn seal_frame(buf: &mut [u8], kind: FrameKind, payload_len: usize) -> usize {
+    buf[0..2].copy_from_slice(&WIRE_MAGIC);
+    buf[2] = WIRE_VERSION;
+    buf[3] = kind as u8;
+    buf[4..8].copy_from_slice(&(payload_len as u32).to_le_bytes());
+    let end = HEADER_LEN + payload_len;
+    let crc = crc32fast::hash(&buf[..end]);
+    buf[end..end + TRAILER_LEN].copy_from_slice(&crc.to_le_bytes());
+    end + TRAILER_LEN
+}
+
+/// Check header, length and CRC of the frame at the start of `bytes`;
+/// returns the header and the payload
+fn open_frame(bytes: &[u8], expected: FrameKind) -> Result<(FrameHeader, &[u8]), WireError> {
+    let header = FrameHeader::parse(bytes)?;
+    if header.kind != expected {
+        return Err(WireError::UnexpectedKind { expected: expected as u8, found: header.kind as u8 });
+    }
+    let frame_len = header.frame_len();
+    if bytes.len() < frame_len {
+        return Err(WireError::Truncated { needed: frame_len, available: bytes.len() });
+    }
+
+    let end = HEADER_LEN + header.length as usize;
+    let expected_crc = u32::from_le_bytes(bytes[end..frame_len].try_into().unwrap());
+    let actual = crc32fast::hash(&bytes[..end]);
+    if actual != expected_crc {
+        return Err(WireError::ChecksumMismatch { expected: expected_crc, actual });
+    }
+
+    Ok((header, &bytes[HEADER_LEN..end]))
+}
+
+impl Transaction {
+    /// Encode as a transaction frame
+    pub fn encode(&self) -> [u8; TXN_FRAME_LEN] {
+        let mut frame = [0u8; TXN_FRAME_LEN];
+        frame[HEADER_LEN..HEADER_LEN + TXN_PAYLOAD_LEN].copy_from_slice(&self.to_bytes());
+        seal_frame(&mut frame, FrameKind::Transaction, TXN_PAYLOAD_LEN);
+        frame
+    }
+
+    /// Decode the transaction frame at the start of `bytes`; returns the
+    /// transaction and the number of bytes consumed. Trailing bytes are left
+    /// for the next frame.
+    pub fn decode(bytes: &[u8]) -> Result<(Self, usize), WireError> {
+        let (header, payload) = open_frame(bytes, FrameKind::Transaction)?;
+        let txn = Self::from_bytes(payload.try_into().unwrap())?;
+        Ok((txn, header.frame_len()))
+    }
+}
+
+impl<const N: usize> Bundle<N> {
+    /// Largest frame a bundle of this capacity encodes to
+    pub const MAX_FRAME_LEN: usize = HEADER_LEN + BUNDLE_PREFIX_LEN + N * TXN_PAYLOAD_LEN + TRAILER_LEN;
+
+    /// Size of this bundle's frame (active transactions only)
+    pub fn frame_len(&self) -> usize {
+        HEADER_LEN + BUNDLE_PREFIX_LEN + self.count as usize * TXN_PAYLOAD_LEN + TRAILER_LEN
+    }
+
+    /// Encode as a bundle frame into `buf`; returns the bytes written.
+    ///
+    /// # Errors
+    /// `BufferTooSmall` if `buf` is shorter than `frame_len()`.
+    pub fn encode_into(&self, buf: &mut [u8]) -> Result<usize, WireError> {
+        let needed = self.frame_len();
+        if buf.len() < needed {
+            return Err(WireError::BufferTooSmall { needed, available: buf.len() });
+        }
+
+        let payload = &mut buf[HEADER_LEN..needed - TRAILER_LEN];
+        payload[0..4].copy_from_slice(&self.count.to_le_bytes());
+        payload[4..8].fill(0);
+        payload[8..16].copy_from_slice(&self.timestamp_ns.to_le_bytes());
+        for (slot, txn) in payload[BUNDLE_PREFIX_LEN..]
+            .chunks_exact_mut(TXN_PAYLOAD_LEN)
+            .zip(self.active_transactions())
+        {
+            slot.copy_from_slice(&txn.to_bytes());
+        }
+
+        Ok(seal_frame(buf, FrameKind::Bundle, needed - HEADER_LEN - TRAILER_LEN))
+    }
+
+    /// Decode the bundle frame at the start of `bytes`; returns the bundle
+    /// and the number of bytes consumed.
+    ///
+    /// # Errors
+    /// Frame errors as for `Transaction::decode`, plus
+    /// `Bundle(CountTooLarge)` if the bundle holds more than `N`
+    /// transactions (checked before waiting for the rest of the frame).
+    pub fn decode(bytes: &[u8]) -> Result<(Self, usize), WireError> {
+        let header = FrameHeader::parse(bytes)?;
+        if header.kind == FrameKind::Bundle {
+            let count = (header.length as usize - BUNDLE_PREFIX_LEN) / TXN_PAYLOAD_LEN;
+            if count > N {
+                return Err(BundleError::CountTooLarge { count: count as u32, max: N }.into());
+            }
+        }
+
+        let (header, payload) = open_frame(bytes, FrameKind::Bundle)?;
+        let count = u32::from_le_bytes(payload[0..4].try_into().unwrap());
+        if count as usize != (payload.len() - BUNDLE_PREFIX_LEN) / TXN_PAYLOAD_LEN
+            || payload[4..8] != [0; 4]
+        {
+            return Err(WireError::BadLength { kind: FrameKind::Bundle as u8, length: header.length });
+        }
+        let timestamp_ns = u64::from_le_bytes(payload[8..16].try_into().unwrap());
+
+        let mut bundle = Self::default();
+        let raw_txns = payload[BUNDLE_PREFIX_LEN..].chunks_exact(TXN_PAYLOAD_LEN);
+        for (slot, raw) in bundle.transactions.iter_mut().zip(raw_txns) {
+            *slot = Transaction::from_bytes(raw.try_into().unwrap())?;
+        }
+        bundle.count = count;
+        bundle.timestamp_ns = timestamp_ns;
+        Ok((bundle, header.frame_len()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::errors::TransactionError;
+    use crate::types::{OrderAction, BUNDLE_MAX};
+
+    fn sample_bundle() -> Bundle {
+        let mut txns = [Transaction::new_unchecked(0, 1, 1, 0, 0); BUNDLE_MAX];
+        txns[0] = Transaction::new_unchecked(7, 1_000_000, 100, 0, 11);
+        txns[1] = Transaction::with_action(8, 0, 0, 1, OrderAction::Cancel, 12).unwrap();
+        txns[2] = Transaction::new_unchecked(9, 999_900, 3, 1, 13);
+        Bundle::with_transactions(txns, 3, 42).unwrap()
+    }
+
+    #[test]
+    fn test_transaction_frame_layout() {
+        let txn = Transaction::new_unchecked(0x0102, 1_000_000, 100, 1, 5);
+        let frame = txn.encode();
+
+        assert_eq!(&frame[0..4], &[b'V', b'X', WIRE_VERSION, FrameKind::Transaction as u8]);
+        assert_eq!(&frame[4..8], &32u32.to_le_bytes());
+        // Explicit little-endian fields, independent of the host
+        assert_eq!(&frame[8..10], &[0x02, 0x01]);
+        assert_eq!(&frame[24..28], &100u32.to_le_bytes());
+        assert_eq!(frame[28], 1);
+        let crc = crc32fast::hash(&frame[..40]);
+        assert_eq!(&frame[40..44], &crc.to_le_bytes());
+
+        assert_eq!(Transaction::decode(&frame), Ok((txn, TXN_FRAME_LEN)));
+    }
+
+    #[test]
+    fn test_bundle_round_trip_and_stream() {
+        let bundle = sample_bundle();
+        let mut buf = [0u8; Bundle::<BUNDLE_MAX>::MAX_FRAME_LEN + TXN_FRAME_LEN];
+        let len = bundle.encode_into(&mut buf).unwrap();
+        assert_eq!(len, bundle.frame_len());
+        assert_eq!(len, HEADER_LEN + 16 + 3 * 32 + TRAILER_LEN);
+
+        // A second frame follows in the same buffer
+        let txn = Transaction::new_unchecked(1, 10, 1, 0, 0);
+        buf[len..len + TXN_FRAME_LEN].copy_from_slice(&txn.encode());
+
+        let (decoded, used) = Bundle::<BUNDLE_MAX>::decode(&buf).unwrap();
+        assert_eq!(used, len);
+        assert_eq!(decoded.active_transactions(), bundle.active_transactions());
+        assert_eq!(decoded.timestamp_ns, 42);
+        assert_eq!(FrameHeader::parse(&buf[used..]).unwrap().kind, FrameKind::Transaction);
+        assert_eq!(Transaction::decode(&buf[used..]).unwrap().0, txn);
+
+        // Fits a smaller capacity as long as the count does
+        let (small, _) = Bundle::<4>::decode(&buf).unwrap();
+        assert_eq!(small.active_transactions(), bundle.active_transactions());
+        assert_eq!(
+            Bundle::<2>::decode(&buf),
+            Err(WireError::Bundle(BundleError::CountTooLarge { count: 3, max: 2 }))
+        );
+    }
+
+    #[test]
+    fn test_decode_rejects_damaged_frames() {
+        let frame = Transaction::new_unchecked(7, 1_000_000, 100, 0, 11).encode();
+
+        for len in [0, 7, 8, 43] {
+            assert!(matches!(Transaction::decode(&frame[..len]), Err(WireError::Truncated { .. })));
+        }
+
+        let mut flipped = frame;
+        flipped[12] ^= 0x10;
+        assert!(matches!(Transaction::decode(&flipped), Err(WireError::ChecksumMismatch { .. })));
+
+        let mut bad = frame;
+        bad[0] = b'Q';
+        assert_eq!(Transaction::decode(&bad), Err(WireError::BadMagic([b'Q', b'X'])));
+
+        let mut bad = frame;
+        bad[2] = 9;
+        assert_eq!(Transaction::decode(&bad), Err(WireError::UnsupportedVersion(9)));
+
+        let mut bad = frame;
+        bad[4] = 33;
+        assert_eq!(Transaction::decode(&bad), Err(WireError::BadLength { kind: 1, length: 33 }));
+
+        assert_eq!(
+            Bundle::<BUNDLE_MAX>::decode(&frame),
+            Err(WireError::UnexpectedKind { expected: 2, found: 1 })
+        );
+
+        // Intact frame, invalid order
+        let mut raw = Transaction::new_unchecked(7, 1_000_000, 100, 0, 11).to_bytes();
+        raw[20] = 2;
+        let frame = Transaction::from_bytes_unchecked(&raw).encode();
+        assert_eq!(
+            Transaction::decode(&frame),
+            Err(WireError::Transaction(TransactionError::InvalidSide(2)))
+        );
+
+        let mut small = [0u8; 8];
+        assert_eq!(
+            sample_bundle().encode_into(&mut small),
+            Err(WireError::BufferTooSmall { needed: 124, available: 8 })
+        );
+    }
+}
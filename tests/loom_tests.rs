@@ -1,5 +1,6 @@
 #[cfg(loom)]
 mod loom_tests {
+    use loom::cell::UnsafeCell;
     use loom::sync::atomic::{AtomicU64, Ordering};
     use loom::sync::Arc;
     use loom::thread;
@@ -86,6 +87,180 @@ mod loom_tests {
             assert!(final_val == 100 || final_val == 200 || final_val == 300);
         });
     }
+
+    /// Loom mirror of `MpmcRingBuffer` (same sequence protocol, capacity 2)
+    struct LoomMpmc {
+        head: AtomicU64,
+        tail: AtomicU64,
+        seqs: [AtomicU64; 2],
+        values: [UnsafeCell<u64>; 2],
+    }
+
+    // Safety: slot values are only touched by the thread that won the CAS
+    unsafe impl Sync for LoomMpmc {}
+
+    impl LoomMpmc {
+        const N: u64 = 2;
+
+        fn new() -> Self {
+            Self {
+                head: AtomicU64::new(0),
+                tail: AtomicU64::new(0),
+                seqs: [AtomicU64::new(0), AtomicU64::new(1)],
+                values: [UnsafeCell::new(0), UnsafeCell::new(0)],
+            }
+        }
+
+        fn push(&self, value: u64) -> Result<(), u64> {
+            let mut pos = self.head.load(Ordering::Relaxed);
+            loop {
+                let idx = (pos & (Self::N - 1)) as usize;
+                let seq = self.seqs[idx].load(Ordering::Acquire);
+                let diff = seq.wrapping_sub(pos) as i64;
+                if diff == 0 {
+                    match self.head.compare_exchange(
+                        pos,
+                        pos + 1,
+                        Ordering::Relaxed,
+                        Ordering::Relaxed,
+                    ) {
+                        Ok(_) => {
+                            self.values[idx].with_mut(|p| unsafe { *p = value });
+                            self.seqs[idx].store(pos + 1, Ordering::Release);
+                            return Ok(());
+                        }
+                        Err(current) => pos = current,
+                    }
+                } else if diff < 0 {
+                    return Err(value);
+                } else {
+                    pos = self.head.load(Ordering::Relaxed);
+                }
+            }
+        }
+
+        fn pop(&self) -> Option<u64> {
+            let mut pos = self.tail.load(Ordering::Relaxed);
+            loop {
+                let idx = (pos & (Self::N - 1)) as usize;
+                let seq = self.seqs[idx].load(Ordering::Acquire);
+                let diff = seq.wrapping_sub(pos + 1) as i64;
+                if diff == 0 {
+                    match self.tail.compare_exchange(
+                        pos,
+                        pos + 1,
+                        Ordering::Relaxed,
+                        Ordering::Relaxed,
+                    ) {
+                        Ok(_) => {
+                            let value = self.values[idx].with(|p| unsafe { *p });
+                            self.seqs[idx].store(pos + Self::N, Ordering::Release);
+                            return Some(value);
+                        }
+                        Err(current) => pos = current,
+                    }
+                } else if diff < 0 {
+                    return None;
+                } else {
+                    pos = self.tail.load(Ordering::Relaxed);
+                }
+            }
+        }
+    }
+
+    /// Two producers fan in to one consumer: every pushed value is popped
+    /// exactly once and nothing is lost
+    #[test]
+    fn test_mpmc_two_producers_no_loss() {
+        loom::model(|| {
+            let ring = Arc::new(LoomMpmc::new());
+
+            let producers: Vec<_> = [1u64, 2]
+                .into_iter()
+                .map(|value| {
+                    let r = Arc::clone(&ring);
+                    thread::spawn(move || r.push(value).is_ok())
+                })
+                .collect();
+
+            // Consumer races with the producers
+            let mut seen = Vec::new();
+            if let Some(v) = ring.pop() {
+                seen.push(v);
+            }
+
+            for producer in producers {
+                // Capacity 2 holds both values, so no push may be rejected
+                assert!(producer.join().unwrap());
+            }
+
+            while let Some(v) = ring.pop() {
+                seen.push(v);
+            }
+
+            seen.sort_unstable();
+            assert_eq!(seen, vec![1, 2]);
+        });
+    }
+
+    /// Three producers race for two slots: rejected pushes are reported to
+    /// the caller and accepted ones are all delivered
+    #[test]
+    fn test_mpmc_full_ring_accounting() {
+        loom::model(|| {
+            let ring = Arc::new(LoomMpmc::new());
+            ring.push(10).unwrap();
+
+            let producers: Vec<_> = [1u64, 2]
+                .into_iter()
+                .map(|value| {
+                    let r = Arc::clone(&ring);
+                    thread::spawn(move || r.push(value).is_ok())
+                })
+                .collect();
+
+            let accepted = producers
+                .into_iter()
+                .map(|p| p.join().unwrap())
+                .filter(|ok| *ok)
+                .count();
+
+            let mut seen = Vec::new();
+            while let Some(v) = ring.pop() {
+                seen.push(v);
+            }
+
+            // One slot was free, so exactly one producer wins it
+            assert_eq!(accepted, 1);
+            assert_eq!(seen.len(), 2);
+            assert_eq!(seen[0], 10);
+        });
+    }
+
+    /// Two consumers drain concurrently: no value is delivered twice
+    #[test]
+    fn test_mpmc_two_consumers_no_duplicates() {
+        loom::model(|| {
+            let ring = Arc::new(LoomMpmc::new());
+            ring.push(1).unwrap();
+            ring.push(2).unwrap();
+
+            let consumers: Vec<_> = (0..2)
+                .map(|_| {
+                    let r = Arc::clone(&ring);
+                    thread::spawn(move || r.pop())
+                })
+                .collect();
+
+            let mut seen: Vec<u64> = consumers
+                .into_iter()
+                .filter_map(|c| c.join().unwrap())
+                .collect();
+
+            seen.sort_unstable();
+            assert_eq!(seen, vec![1, 2]);
+        });
+    }
 }
 
 // Regular tests (non-loom)
@@ -151,4 +326,90 @@ mod regular_tests {
         // Verify best bid is updated
         assert!(book.best_bid() > 0);
     }
+
+    #[test]
+    fn test_mpmc_fan_in_preserves_per_producer_order() {
+        const PRODUCERS: u64 = 4;
+        const PER_PRODUCER: u64 = 10_000;
+
+        let ring = Arc::new(MpmcRingBuffer::<u64, 1024>::new());
+        let mut handles = vec![];
+
+        for p in 0..PRODUCERS {
+            let r = Arc::clone(&ring);
+            handles.push(thread::spawn(move || {
+                for i in 0..PER_PRODUCER {
+                    // Tag each value with its producer in the high bits
+                    let mut value = (p << 32) | i;
+                    while let Err(v) = r.push(value) {
+                        value = v;
+                        std::hint::spin_loop();
+                    }
+                }
+            }));
+        }
+
+        let mut next = [0u64; PRODUCERS as usize];
+        let mut count = 0;
+        while count < PRODUCERS * PER_PRODUCER {
+            if let Some(val) = ring.pop() {
+                let p = (val >> 32) as usize;
+                let i = val & 0xFFFF_FFFF;
+                assert_eq!(i, next[p], "per-producer FIFO order violated");
+                next[p] += 1;
+                count += 1;
+            }
+        }
+
+        for handle in handles {
+            handle.join().unwrap();
+        }
+
+        assert!(ring.pop().is_none());
+        assert!(next.iter().all(|&n| n == PER_PRODUCER));
+    }
+
+    #[test]
+    fn test_mpmc_concurrent_consumers_sum() {
+        const TOTAL: u64 = 20_000;
+
+        let ring = Arc::new(MpmcRingBuffer::<u64, 256>::new());
+        let consumed = Arc::new(std::sync::atomic::AtomicU64::new(0));
+        let sum = Arc::new(std::sync::atomic::AtomicU64::new(0));
+        let mut handles = vec![];
+
+        for p in 0..2 {
+            let r = Arc::clone(&ring);
+            handles.push(thread::spawn(move || {
+                for i in (p..TOTAL).step_by(2) {
+                    while r.push(i).is_err() {
+                        std::hint::spin_loop();
+                    }
+                }
+            }));
+        }
+
+        for _ in 0..2 {
+            let r = Arc::clone(&ring);
+            let c = Arc::clone(&consumed);
+            let s = Arc::clone(&sum);
+            handles.push(thread::spawn(move || {
+                use std::sync::atomic::Ordering;
+                while c.load(Ordering::Relaxed) < TOTAL {
+                    if let Some(val) = r.pop() {
+                        s.fetch_add(val, Ordering::Relaxed);
+                        c.fetch_add(1, Ordering::Relaxed);
+                    }
+                }
+            }));
+        }
+
+        for handle in handles {
+            handle.join().unwrap();
+        }
+
+        use std::sync::atomic::Ordering;
+        assert_eq!(consumed.load(Ordering::Relaxed), TOTAL);
+        assert_eq!(sum.load(Ordering::Relaxed), TOTAL * (TOTAL - 1) / 2);
+    }
 }
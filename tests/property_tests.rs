@@ -65,6 +65,64 @@ proptest! {
         }
     }
 
+    /// Property: Exact book keeps best_bid <= best_ask and never aliases prices
+    #[test]
+    fn prop_exact_orderbook_spread_invariant(
+        bid_price in 900000i64..1000000,
+        ask_price in 1000000i64..1100000,
+    ) {
+        let book = ExactOrderBook::new(900000, 1, 200000);
+
+        book.update_bid(bid_price, 100, 0).unwrap();
+        book.update_ask(ask_price, 100, 0).unwrap();
+
+        prop_assert_eq!(book.best_bid(), bid_price);
+        prop_assert_eq!(book.best_ask(), ask_price);
+        prop_assert_eq!(book.bid_quantity(bid_price + 1), 0);
+        prop_assert_eq!(spread_of(&book), ask_price - bid_price);
+    }
+
+    /// Property: Exact book rejects prices outside its window
+    #[test]
+    fn prop_exact_orderbook_rejects_out_of_window(price in 1i64..2000000) {
+        let book = ExactOrderBook::new(900000, 1, 200000);
+        let in_window = (900000..1100000).contains(&price);
+
+        prop_assert_eq!(book.update_bid(price, 100, 0).is_ok(), in_window);
+        prop_assert_eq!(book.depth_bid(), in_window as u64);
+    }
+
+    /// Property: Matching never leaves a crossed book and conserves quantity
+    #[test]
+    fn prop_matching_book_never_crossed(
+        orders in prop::collection::vec((1000i64..1100, 1u32..50, 0u8..2), 1..200),
+    ) {
+        let mut engine = MatchingEngine::new(1000, 1, 100, 1024);
+        let mut submitted = 0u64;
+        let mut filled = 0u64;
+
+        for (id, (price, size, side)) in orders.iter().enumerate() {
+            let txn = Transaction::new_unchecked(id as u64, *price, *size, *side, 0);
+            let mut fill_qty = 0u64;
+            let taker_filled = engine.submit(&txn, |fill| fill_qty += fill.size as u64).unwrap();
+
+            prop_assert_eq!(taker_filled as u64, fill_qty);
+            submitted += *size as u64;
+            filled += fill_qty;
+
+            let book = engine.book();
+            if book.best_bid() > 0 && book.best_ask() < i64::MAX {
+                prop_assert!(book.best_bid() < book.best_ask());
+            }
+        }
+
+        // Every unit is either resting or was filled against a maker (counted twice)
+        let resting: i64 = (1000..1100)
+            .map(|p| engine.book().bid_quantity(p) + engine.book().ask_quantity(p))
+            .sum();
+        prop_assert_eq!(resting as u64 + 2 * filled, submitted);
+    }
+
     /// Property: Bundle size bounds (1 <= count <= BUNDLE_MAX)
     #[test]
     fn prop_bundle_size_bounds(count in 1usize..=BUNDLE_MAX) {
@@ -85,6 +143,102 @@ proptest! {
         }
     }
 
+    /// Property: Every flush `add` reports reaches the output ring, in order,
+    /// with the reported size
+    #[test]
+    fn prop_bundle_flush_outcomes_match_ring(
+        ops in prop::collection::vec((0u64..8, 0u8..4, 1u32..500, 0u8..2), 1..200),
+        max_size in 1usize..=BUNDLE_MAX,
+        max_quantity in prop::option::of(1u64..2000),
+        expire_immediately in any::<bool>(),
+    ) {
+        init_tsc();
+        let output_ring = RingBuffer::<Bundle, 256>::new();
+        let timeout_ns = if expire_immediately { 0 } else { 1_000_000_000 };
+        let mut policy = FlushTriggers::new(max_size, timeout_ns);
+        if let Some(limit) = max_quantity {
+            policy = policy.with_max_quantity(limit);
+        }
+        let mut builder = BundleBuilder::with_policy(policy);
+
+        let mut reported = Vec::new();
+        for (id, action, size, side) in ops {
+            let action = OrderAction::try_from(action).unwrap();
+            let txn = Transaction::with_action(id, 1_000_000, size, side, action, 0).unwrap();
+            let outcome = builder.add(txn, &output_ring);
+            reported.extend(outcome.flushes());
+        }
+
+        for flushed in &reported {
+            prop_assert!(flushed.size >= 1 && flushed.size <= max_size);
+            match flushed.reason {
+                FlushReason::Size => {}
+                FlushReason::Timeout => prop_assert!(expire_immediately),
+                FlushReason::Quantity => prop_assert!(max_quantity.is_some()),
+                other => prop_assert!(false, "unexpected reason {:?}", other),
+            }
+        }
+
+        let mut delivered = Vec::new();
+        while let Some(bundle) = output_ring.pop() {
+            delivered.push(bundle.count as usize);
+        }
+        let reported: Vec<usize> = reported.iter().map(|flushed| flushed.size).collect();
+        prop_assert_eq!(reported, delivered);
+    }
+
+    /// Property: With a slow output ring, every transaction is delivered,
+    /// pending, held for retry, or counted as dropped; and held bundles are
+    /// delivered in order
+    #[test]
+    fn prop_bundle_backpressure_accounts_every_transaction(
+        sizes in prop::collection::vec(1u32..500, 1..150),
+        max_size in 1usize..=BUNDLE_MAX,
+        overflow in 0usize..4,
+        drain_every in 1usize..12,
+    ) {
+        init_tsc();
+        let output_ring = RingBuffer::<Bundle, 2>::new();
+        let mut builder = BundleBuilder::with_limits(max_size, 1_000_000_000).with_overflow(overflow);
+
+        let mut delivered = Vec::new();
+        let drain = |delivered: &mut Vec<u64>| {
+            while let Some(bundle) = output_ring.pop() {
+                delivered.extend(bundle.active_transactions().iter().map(|txn| txn.id));
+            }
+        };
+        for (i, size) in sizes.iter().enumerate() {
+            let txn = Transaction::new_unchecked(i as u64, 1_000_000, *size, 0, 0);
+            builder.add(txn, &output_ring);
+            if i % drain_every == 0 {
+                drain(&mut delivered);
+            }
+
+            // Bundles still in the ring hold between 1 and max_size each
+            let stats = builder.backpressure();
+            let added = i + 1;
+            let accounted = delivered.len()
+                + builder.len()
+                + builder.held_transactions()
+                + stats.dropped_transactions as usize;
+            prop_assert!(accounted + output_ring.len() <= added);
+            prop_assert!(accounted + output_ring.len() * max_size >= added);
+            prop_assert_eq!(stats.delayed, stats.retried + stats.dropped + builder.held() as u64);
+        }
+
+        // Drain everything still pending or held
+        while builder.force_flush(&output_ring).is_err() {
+            drain(&mut delivered);
+        }
+        drain(&mut delivered);
+
+        let stats = builder.backpressure();
+        prop_assert_eq!(builder.held(), 0);
+        prop_assert_eq!(delivered.len() as u64 + stats.dropped_transactions, sizes.len() as u64);
+        // Drops remove whole bundles; survivors keep submission order
+        prop_assert!(delivered.windows(2).all(|pair| pair[0] < pair[1]));
+    }
+
     /// Property: Transaction serialization round-trip
     #[test]
     fn prop_transaction_serialization(
@@ -93,15 +247,168 @@ proptest! {
         size in 0u32..u32::MAX,
         side in 0u8..2,
     ) {
-        let txn = Transaction::new_unchecked(id, price, size, side, 12345);
+        // Set price and size directly: new_unchecked debug-asserts they are
+        // positive, but serialization must round-trip any field values
+        let mut txn = Transaction::new_unchecked(id, 1, 1, side, 12345);
+        txn.price = price;
+        txn.size = size;
         let bytes = txn.to_bytes();
-        let txn2 = Transaction::from_bytes(&bytes);
+        let txn2 = Transaction::from_bytes_unchecked(&bytes);
 
         prop_assert_eq!(txn.id, txn2.id);
         prop_assert_eq!(txn.price, txn2.price);
         prop_assert_eq!(txn.size, txn2.size);
         prop_assert_eq!(txn.side, txn2.side);
+        // The validating decoder accepts exactly the valid new orders
+        prop_assert_eq!(Transaction::from_bytes(&bytes).is_ok(), price > 0 && size > 0);
     }
+
+    /// Property: A valid payload with one field overwritten decodes exactly
+    /// when it still satisfies the rules; whatever is accepted re-encodes to
+    /// the same bytes
+    #[test]
+    fn prop_from_bytes_mutated_input(bytes in mutated_payload()) {
+        match Transaction::from_bytes(&bytes) {
+            Ok(txn) => {
+                prop_assert!(payload_is_valid(&bytes));
+                prop_assert_eq!(txn.to_bytes(), bytes);
+            }
+            Err(err) => {
+                prop_assert!(!payload_is_valid(&bytes));
+                // The unchecked decoder is explicit about accepting anything
+                let raw = Transaction::from_bytes_unchecked(&bytes);
+                prop_assert_eq!(raw.to_bytes(), bytes);
+                prop_assert!(!err.to_string().is_empty());
+            }
+        }
+    }
+
+    /// Property: Arbitrary byte streams never panic the frame decoders
+    #[test]
+    fn prop_wire_decode_arbitrary_input(
+        mut bytes in prop::collection::vec(any::<u8>(), 0..200),
+        valid_header in any::<bool>(),
+    ) {
+        // Half the cases get a plausible header so the length checks run
+        if valid_header && bytes.len() >= 8 {
+            bytes[0..3].copy_from_slice(&[b'V', b'X', WIRE_VERSION]);
+            bytes[3] = 1 + (bytes[3] & 1);
+        }
+        let _ = Transaction::decode(&bytes);
+        let _ = Bundle::<BUNDLE_MAX>::decode(&bytes);
+        let _ = Bundle::<2>::decode(&bytes);
+    }
+
+    /// Property: Frames sealed with a good checksum reach payload and
+    /// transaction validation, and decode exactly when every payload is valid
+    #[test]
+    fn prop_wire_decode_sealed_frames(
+        payloads in prop::collection::vec(mutated_payload(), 1..4),
+        bad_count in any::<bool>(),
+    ) {
+        let valid = payloads.iter().all(payload_is_valid);
+
+        let frame = seal(1, &payloads[0]);
+        let decoded = Transaction::decode(&frame);
+        prop_assert_eq!(decoded.is_ok(), payload_is_valid(&payloads[0]));
+        if let Ok((txn, used)) = decoded {
+            prop_assert_eq!(txn.to_bytes(), payloads[0]);
+            prop_assert_eq!(used, frame.len());
+        }
+
+        let count = payloads.len() as u32 + bad_count as u32;
+        let mut body = Vec::with_capacity(16 + payloads.len() * 32);
+        body.extend_from_slice(&count.to_le_bytes());
+        body.extend_from_slice(&[0; 4]);
+        body.extend_from_slice(&7u64.to_le_bytes());
+        for payload in &payloads {
+            body.extend_from_slice(payload);
+        }
+        let frame = seal(2, &body);
+        match Bundle::<BUNDLE_MAX>::decode(&frame) {
+            Ok((bundle, used)) => {
+                prop_assert!(valid && !bad_count);
+                prop_assert_eq!(bundle.count as usize, payloads.len());
+                prop_assert_eq!(used, frame.len());
+            }
+            Err(WireError::BadLength { .. }) => prop_assert!(bad_count),
+            Err(WireError::Transaction(_)) => prop_assert!(!valid && !bad_count),
+            Err(err) => prop_assert!(false, "unexpected error: {}", err),
+        }
+        let small_ok = payloads.len() <= 2 && valid && !bad_count;
+        prop_assert_eq!(Bundle::<2>::decode(&frame).is_ok(), small_ok);
+    }
+
+    /// Property: Framed encoding round-trips, and any single bit flip in
+    /// the frame is rejected
+    #[test]
+    fn prop_wire_frame_detects_bit_flips(
+        id in any::<u64>(),
+        price in 1i64..i64::MAX,
+        size in 1u32..u32::MAX,
+        side in 0u8..2,
+        bit in 0usize..TXN_FRAME_LEN * 8,
+    ) {
+        let txn = Transaction::new_unchecked(id, price, size, side, 12345);
+        let frame = txn.encode();
+        prop_assert_eq!(Transaction::decode(&frame), Ok((txn, TXN_FRAME_LEN)));
+
+        let mut damaged = frame;
+        damaged[bit / 8] ^= 1 << (bit % 8);
+        prop_assert!(Transaction::decode(&damaged).is_err());
+    }
+}
+
+/// A valid transaction payload with at most one field overwritten; zero
+/// and small values are common so side and action mutations stay plausible
+fn mutated_payload() -> impl Strategy<Value = [u8; 32]> {
+    let value = prop_oneof![Just(0u64), 1u64..4, any::<u64>()];
+    (
+        (any::<u64>(), 1i64..i64::MAX, 1u32..u32::MAX, 0u8..2, 0u8..4, any::<u64>()),
+        0usize..7,
+        value,
+    )
+        .prop_map(|((id, price, size, side, action, ts), field, value)| {
+            let action = OrderAction::try_from(action).unwrap();
+            let txn = Transaction::with_action(id, price, size, side, action, ts).unwrap();
+            let mut bytes = txn.to_bytes();
+            match field {
+                0 => bytes[0..8].copy_from_slice(&value.to_le_bytes()),
+                1 => bytes[8..16].copy_from_slice(&value.to_le_bytes()),
+                2 => bytes[16..20].copy_from_slice(&(value as u32).to_le_bytes()),
+                3 => bytes[20] = value as u8,
+                4 => bytes[21] = value as u8,
+                5 => bytes[22..24].copy_from_slice(&(value as u16).to_le_bytes()),
+                _ => {}
+            }
+            bytes
+        })
+}
+
+/// The payload rules `from_bytes` enforces, restated over the raw layout
+fn payload_is_valid(bytes: &[u8; 32]) -> bool {
+    let price = i64::from_le_bytes(bytes[8..16].try_into().unwrap());
+    let size = u32::from_le_bytes(bytes[16..20].try_into().unwrap());
+    let (side, action) = (bytes[20], bytes[21]);
+    // Cancel (1) ignores price and size, Modify (2) ignores price
+    let price_ok = price > 0 || matches!(action, 1 | 2);
+    let size_ok = size > 0 || action == 1;
+    side <= 1 && action <= 3 && bytes[22..24] == [0, 0] && price_ok && size_ok
+}
+
+/// Wrap `payload` in a frame of `kind` with a correct length and CRC
+fn seal(kind: u8, payload: &[u8]) -> Vec<u8> {
+    let mut frame = vec![b'V', b'X', WIRE_VERSION, kind];
+    frame.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+    frame.extend_from_slice(payload);
+    let crc = crc32fast::hash(&frame);
+    frame.extend_from_slice(&crc.to_le_bytes());
+    frame
+}
+
+/// Analytics helper written once against any book type
+fn spread_of<B: BookQuery>(book: &B) -> i64 {
+    book.spread()
 }
 
 #[cfg(test)]
@@ -213,3 +520,86 @@ mod stress_tests {
         assert_eq!(produced, consumed, "Transaction loss detected!");
     }
 }
+
+/// Golden runs: a seeded stream on a manual clock must produce exactly the
+/// same fills, book and bundles on every run. If a deliberate change to the
+/// market model or matching moves these values, update them in the same
+/// commit.
+#[cfg(test)]
+mod golden_tests {
+    use super::*;
+
+    /// FNV-1a, enough to fingerprint a run
+    fn fnv(hash: &mut u64, bytes: &[u8]) {
+        for &byte in bytes {
+            *hash = (*hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+
+    struct Run {
+        fills: usize,
+        bundles: usize,
+        resting: (u64, u64),
+        touch: (i64, i64),
+        digest: u64,
+    }
+
+    /// Seeded burst through the matching engine and bundle builder on one
+    /// thread
+    fn run(seed: u64) -> Run {
+        init_tsc();
+        let ring = RingBuffer::<Transaction, 4096>::new();
+        let mut clock = ManualClock::new(1_000_000);
+        assert_eq!(generate_burst_seeded(&ring, 4096, 1_000_000, seed, &mut clock), 4096);
+
+        let mut engine = MatchingEngine::new(900_000, 1, 200_000, 65_536);
+        // Size-only flushes: no wall-clock timeout
+        let mut builder = BundleBuilder::with_limits(BUNDLE_MAX, u64::MAX);
+        let output = RingBuffer::<Bundle, 8>::new();
+        let mut digest = 0xcbf2_9ce4_8422_2325u64;
+        let (mut fills, mut bundles) = (0, 0);
+
+        while let Some(txn) = ring.pop() {
+            engine
+                .submit(&txn, |fill| {
+                    fills += 1;
+                    fnv(&mut digest, &fill.maker_id.to_le_bytes());
+                    fnv(&mut digest, &fill.taker_id.to_le_bytes());
+                    fnv(&mut digest, &fill.price.to_le_bytes());
+                    fnv(&mut digest, &fill.size.to_le_bytes());
+                })
+                .unwrap();
+            builder.add(txn, &output);
+            while let Some(bundle) = output.pop() {
+                bundles += 1;
+                for txn in &bundle.transactions[..bundle.count as usize] {
+                    fnv(&mut digest, &txn.to_bytes());
+                }
+            }
+        }
+
+        let book = engine.book();
+        Run {
+            fills,
+            bundles,
+            resting: (engine.resting_bids(), engine.resting_asks()),
+            touch: (book.best_bid(), book.best_ask()),
+            digest,
+        }
+    }
+
+    #[test]
+    fn golden_seeded_book_and_bundles() {
+        let golden = run(2024);
+        assert_eq!(golden.fills, 3150);
+        assert_eq!(golden.bundles, 256);
+        assert_eq!(golden.resting, (480, 435));
+        assert_eq!(golden.touch, (998_207, 1_002_292));
+        assert_eq!(golden.digest, 0x3cae_a61d_a9b7_a0ed);
+    }
+
+    #[test]
+    fn golden_seed_changes_run() {
+        assert_ne!(run(2024).digest, run(2025).digest);
+    }
+}